@@ -104,7 +104,9 @@ func _handle_update_player_msg(update_player_msg: Global.proto.UpdatePlayer) ->
 		)
 	else:
 		var direction := update_player_msg.get_direction_angle()
-		_update_actor(actor_connection_id, x, y, direction, speed, radius, is_rushing, is_player)
+		_update_actor(
+			actor_connection_id, x, y, direction, speed, radius, color, is_rushing, is_player
+		)
 
 
 func _handle_update_spore_batch_msg(update_spore_batch_msg: Global.proto.UpdateSporeBatch) -> void:
@@ -138,15 +140,15 @@ func _handle_update_spore_msg(update_spore_msg: Global.proto.UpdateSpore) -> voi
 func _handle_consume_spore_msg(consume_spore_msg: Global.proto.ConsumeSpore) -> void:
 	var connection_id := consume_spore_msg.get_connection_id()
 	var spore_id := consume_spore_msg.get_spore_id()
-	if connection_id in player_map and spore_id in spore_map:
+	if spore_id not in spore_map:
+		return
+	var spore = spore_map[spore_id]
+	if connection_id in player_map:
 		var actor = player_map[connection_id]
 		var actor_mass := _radius_to_mass(actor.radius)
-
-		var spore = spore_map[spore_id]
 		var spore_mass := _radius_to_mass(spore.radius)
-
 		_set_actor_mass(actor, actor_mass + spore_mass)
-		_remove_spore(spore)
+	_remove_spore(spore)
 
 
 func _handle_disconnect_msg(disconnect_msg: Global.proto.Disconnect) -> void:
@@ -244,6 +246,7 @@ func _update_actor(
 	direction: float,
 	speed: float,
 	radius: float,
+	color: Color,
 	is_rushing: bool,
 	is_player: bool
 ) -> void:
@@ -252,6 +255,10 @@ func _update_actor(
 	_set_actor_mass(actor, _radius_to_mass(radius))
 	actor.server_radius = radius
 
+	if actor.color != color:
+		actor.color = color
+		actor.queue_redraw()
+
 	actor.speed = speed
 	actor.is_rushing = is_rushing
 	actor.is_player = is_player
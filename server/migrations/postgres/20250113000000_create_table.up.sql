@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS auth (
+    id BIGSERIAL PRIMARY KEY NOT NULL,
+    username TEXT NOT NULL UNIQUE,
+    password TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS player (
+    id BIGSERIAL PRIMARY KEY NOT NULL,
+    auth_id BIGINT NOT NULL,
+    nickname TEXT NOT NULL UNIQUE,
+    color BIGINT NOT NULL,
+    best_score BIGINT NOT NULL DEFAULT 0
+);
@@ -0,0 +1,5 @@
+DROP INDEX IF EXISTS chat_log_player_id_idx;
+
+DROP TABLE IF EXISTS chat_log;
+
+DROP TABLE IF EXISTS mute;
@@ -0,0 +1,17 @@
+CREATE TABLE IF NOT EXISTS mute (
+    player_id BIGINT PRIMARY KEY NOT NULL,
+    reason TEXT NOT NULL,
+    muted_until BIGINT NOT NULL,
+    created_at BIGINT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS chat_log (
+    id BIGSERIAL PRIMARY KEY NOT NULL,
+    player_id BIGINT NOT NULL,
+    nickname TEXT NOT NULL,
+    msg TEXT NOT NULL,
+    blocked_reason TEXT NOT NULL DEFAULT '',
+    created_at BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS chat_log_player_id_idx ON chat_log (player_id, id);
@@ -0,0 +1,3 @@
+ALTER TABLE chat_log DROP COLUMN IF EXISTS target_player_id;
+
+ALTER TABLE chat_log DROP COLUMN IF EXISTS channel;
@@ -0,0 +1,3 @@
+ALTER TABLE chat_log ADD COLUMN IF NOT EXISTS channel TEXT NOT NULL DEFAULT 'global';
+
+ALTER TABLE chat_log ADD COLUMN IF NOT EXISTS target_player_id BIGINT NOT NULL DEFAULT 0;
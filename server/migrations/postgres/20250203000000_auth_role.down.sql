@@ -0,0 +1 @@
+ALTER TABLE auth DROP COLUMN IF EXISTS role;
@@ -0,0 +1 @@
+ALTER TABLE auth ADD COLUMN IF NOT EXISTS role TEXT NOT NULL DEFAULT 'player';
@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS anti_cheat_log (
+    id BIGSERIAL PRIMARY KEY NOT NULL,
+    player_id BIGINT NOT NULL,
+    nickname TEXT NOT NULL,
+    violation TEXT NOT NULL,
+    detail TEXT NOT NULL DEFAULT '',
+    score DOUBLE PRECISION NOT NULL,
+    action TEXT NOT NULL DEFAULT '',
+    created_at BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS anti_cheat_log_player_id_idx ON anti_cheat_log (player_id, id);
@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS friend (
+    player_id BIGINT NOT NULL,
+    friend_player_id BIGINT NOT NULL,
+    status TEXT NOT NULL,
+    created_at BIGINT NOT NULL,
+    PRIMARY KEY (player_id, friend_player_id)
+);
+
+CREATE INDEX IF NOT EXISTS friend_friend_player_id_idx ON friend (friend_player_id);
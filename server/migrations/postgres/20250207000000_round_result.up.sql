@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS round_result (
+    id BIGSERIAL PRIMARY KEY NOT NULL,
+    room_id TEXT NOT NULL,
+    round_number BIGINT NOT NULL,
+    winner_player_id BIGINT NOT NULL DEFAULT 0,
+    winner_nickname TEXT NOT NULL DEFAULT '',
+    winner_score BIGINT NOT NULL DEFAULT 0,
+    winner_team BIGINT NOT NULL DEFAULT 0,
+    player_count BIGINT NOT NULL,
+    started_at BIGINT NOT NULL,
+    ended_at BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS round_result_winner_player_id_idx ON round_result (winner_player_id, id);
@@ -0,0 +1,17 @@
+CREATE TABLE IF NOT EXISTS mute (
+    player_id INTEGER PRIMARY KEY NOT NULL,
+    reason TEXT NOT NULL,
+    muted_until INTEGER NOT NULL,
+    created_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS chat_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    player_id INTEGER NOT NULL,
+    nickname TEXT NOT NULL,
+    msg TEXT NOT NULL,
+    blocked_reason TEXT NOT NULL DEFAULT '',
+    created_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS chat_log_player_id_idx ON chat_log (player_id, id);
@@ -0,0 +1,3 @@
+ALTER TABLE chat_log ADD COLUMN channel TEXT NOT NULL DEFAULT 'global';
+
+ALTER TABLE chat_log ADD COLUMN target_player_id INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1 @@
+ALTER TABLE auth ADD COLUMN role TEXT NOT NULL DEFAULT 'player';
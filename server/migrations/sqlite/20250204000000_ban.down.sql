@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS ban_expires_at_idx;
+
+DROP TABLE IF EXISTS ban;
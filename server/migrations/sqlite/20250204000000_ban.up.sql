@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS ban (
+    id TEXT PRIMARY KEY NOT NULL,
+    player_id INTEGER NOT NULL DEFAULT 0,
+    cidr TEXT NOT NULL DEFAULT '',
+    reason TEXT NOT NULL,
+    issuer TEXT NOT NULL,
+    expires_at INTEGER NOT NULL DEFAULT 0,
+    created_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS ban_expires_at_idx ON ban (expires_at);
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS anti_cheat_log_player_id_idx;
+
+DROP TABLE IF EXISTS anti_cheat_log;
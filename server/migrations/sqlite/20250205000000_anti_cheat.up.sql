@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS anti_cheat_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    player_id INTEGER NOT NULL,
+    nickname TEXT NOT NULL,
+    violation TEXT NOT NULL,
+    detail TEXT NOT NULL DEFAULT '',
+    score REAL NOT NULL,
+    action TEXT NOT NULL DEFAULT '',
+    created_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS anti_cheat_log_player_id_idx ON anti_cheat_log (player_id, id);
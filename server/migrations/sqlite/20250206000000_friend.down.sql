@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS friend_friend_player_id_idx;
+
+DROP TABLE IF EXISTS friend;
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS round_result_winner_player_id_idx;
+
+DROP TABLE IF EXISTS round_result;
@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS round_result (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    room_id TEXT NOT NULL,
+    round_number INTEGER NOT NULL,
+    winner_player_id INTEGER NOT NULL DEFAULT 0,
+    winner_nickname TEXT NOT NULL DEFAULT '',
+    winner_score INTEGER NOT NULL DEFAULT 0,
+    winner_team INTEGER NOT NULL DEFAULT 0,
+    player_count INTEGER NOT NULL,
+    started_at INTEGER NOT NULL,
+    ended_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS round_result_winner_player_id_idx ON round_result (winner_player_id, id);
@@ -1 +0,0 @@
-sqlx migrate run --database-url sqlite:agarust_db.sqlite?mode=rwc
@@ -0,0 +1,575 @@
+use crate::*;
+use anyhow::Result;
+use axum::{
+    Json, Router,
+    extract::{Query, Request, State},
+    http::{StatusCode, header},
+    middleware::{self, Next},
+    response::Response,
+    routing::{get, post, put},
+};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::sync::{mpsc::UnboundedSender, oneshot};
+use tracing::info;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Target {
+    ConnectionId(Arc<str>),
+    PlayerDbId(i64),
+    Ip(Arc<str>),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ClientInfo {
+    pub connection_id: Arc<str>,
+    pub socket_addr: SocketAddr,
+    pub spectator: bool,
+    pub player: Option<PlayerInfo>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlayerInfo {
+    pub db_id: i64,
+    pub nickname: Arc<str>,
+    pub x: f64,
+    pub y: f64,
+    pub radius: f64,
+    pub is_bot: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RoomInfo {
+    pub id: Arc<str>,
+    pub code: Arc<str>,
+    pub settings: Option<room::RoomSettings>,
+    pub client_count: usize,
+    pub player_count: usize,
+    pub bot_count: usize,
+    pub spectator_count: usize,
+    pub spore_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HubState {
+    pub room_list: Vec<RoomInfo>,
+    pub client_list: Vec<ClientInfo>,
+    pub ban_list: Vec<BanInfo>,
+    pub suspicion_list: Vec<SuspicionInfo>,
+    pub game_config: config::GameConfig,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct KickRequest {
+    pub target: Target,
+    pub reason: Option<Arc<str>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BanInfo {
+    pub id: Arc<str>,
+    pub player_db_id: i64,
+    pub cidr: Arc<str>,
+    pub reason: Arc<str>,
+    pub issuer: Arc<str>,
+    pub expires_at: i64,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BanRequest {
+    pub target: Target,
+    pub reason: Option<Arc<str>>,
+    pub duration_secs: Option<u64>,
+    pub issuer: Option<Arc<str>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UnbanRequest {
+    pub ban_id: Option<Arc<str>>,
+    pub player_db_id: Option<i64>,
+    pub ip: Option<Arc<str>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MuteRequest {
+    pub target: Target,
+    pub duration_secs: u64,
+    pub reason: Option<Arc<str>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UnmuteRequest {
+    pub player_db_id: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChatLogQuery {
+    pub player_db_id: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChatLogInfo {
+    pub id: i64,
+    pub player_db_id: i64,
+    pub nickname: Arc<str>,
+    pub msg: Arc<str>,
+    pub blocked_reason: Arc<str>,
+    pub channel: Arc<str>,
+    pub target_player_db_id: i64,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SuspicionInfo {
+    pub player_db_id: i64,
+    pub score: f64,
+    pub restricted: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PardonRequest {
+    pub player_db_id: i64,
+    pub issuer: Option<Arc<str>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AntiCheatLogQuery {
+    pub player_db_id: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AntiCheatLogInfo {
+    pub id: i64,
+    pub player_db_id: i64,
+    pub nickname: Arc<str>,
+    pub violation: Arc<str>,
+    pub detail: Arc<str>,
+    pub score: f64,
+    pub action: Arc<str>,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RoundResultQuery {
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RoundResultInfo {
+    pub id: i64,
+    pub room_id: Arc<str>,
+    pub round_number: i64,
+    pub winner_player_db_id: i64,
+    pub winner_nickname: Arc<str>,
+    pub winner_score: i64,
+    pub winner_team: i64,
+    pub player_count: i64,
+    pub started_at: i64,
+    pub ended_at: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BroadcastRequest {
+    pub msg: Arc<str>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SporeCountRequest {
+    pub max_spore_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AffectedResponse {
+    pub affected: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplayResponse {
+    pub path: Arc<str>,
+}
+
+#[derive(Debug, Clone)]
+struct AdminState {
+    admin_token: Arc<str>,
+    hub_command_sender: UnboundedSender<command::Command>,
+    db: db::Db,
+}
+
+type AdminResult<T> = Result<Json<T>, (StatusCode, String)>;
+
+pub async fn run(
+    bind_addr: &str,
+    admin_token: Arc<str>,
+    hub_command_sender: UnboundedSender<command::Command>,
+    db: db::Db,
+) -> Result<()> {
+    let admin_state = AdminState {
+        admin_token,
+        hub_command_sender,
+        db,
+    };
+
+    let router = Router::new()
+        .route("/hub", get(hub_state))
+        .route("/rooms", get(room_list))
+        .route("/clients", get(client_list))
+        .route("/kick", post(kick))
+        .route("/bans", get(ban_list))
+        .route("/ban", post(ban))
+        .route("/unban", post(unban))
+        .route("/mute", post(mute))
+        .route("/unmute", post(unmute))
+        .route("/chat", get(chat_log_list))
+        .route("/suspicions", get(suspicion_list))
+        .route("/pardon", post(pardon))
+        .route("/anti-cheat", get(anti_cheat_log_list))
+        .route("/rounds", get(round_result_list))
+        .route("/broadcast", post(broadcast))
+        .route("/spore-count", put(spore_count))
+        .route("/replay/start", post(replay_start))
+        .route("/replay/stop", post(replay_stop))
+        .layer(middleware::from_fn_with_state(
+            admin_state.clone(),
+            check_admin_token,
+        ))
+        .with_state(admin_state);
+
+    let tcp_listener = tokio::net::TcpListener::bind(bind_addr).await?;
+    info!("admin listening on {:?}", bind_addr);
+
+    axum::serve(tcp_listener, router).await?;
+
+    Ok(())
+}
+
+async fn check_admin_token(
+    State(admin_state): State<AdminState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let authorized = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|token| {
+            util::constant_time_eq(token.as_bytes(), admin_state.admin_token.as_bytes())
+        });
+
+    if !authorized {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    info!("admin request: {} {}", request.method(), request.uri());
+
+    Ok(next.run(request).await)
+}
+
+async fn hub_request<T>(
+    admin_state: &AdminState,
+    command: impl FnOnce(oneshot::Sender<T>) -> command::Command,
+) -> Result<T, (StatusCode, String)> {
+    let (response_sender, response_receiver) = oneshot::channel();
+    admin_state
+        .hub_command_sender
+        .send(command(response_sender))
+        .map_err(|e| (StatusCode::SERVICE_UNAVAILABLE, e.to_string()))?;
+    response_receiver
+        .await
+        .map_err(|e| (StatusCode::SERVICE_UNAVAILABLE, e.to_string()))
+}
+
+async fn hub_state(State(admin_state): State<AdminState>) -> AdminResult<HubState> {
+    let hub_state = hub_request(&admin_state, |response_sender| {
+        command::Command::AdminHubState { response_sender }
+    })
+    .await?;
+    Ok(Json(hub_state))
+}
+
+async fn room_list(State(admin_state): State<AdminState>) -> AdminResult<Vec<RoomInfo>> {
+    let hub_state = hub_request(&admin_state, |response_sender| {
+        command::Command::AdminHubState { response_sender }
+    })
+    .await?;
+    Ok(Json(hub_state.room_list))
+}
+
+async fn client_list(State(admin_state): State<AdminState>) -> AdminResult<Vec<ClientInfo>> {
+    let hub_state = hub_request(&admin_state, |response_sender| {
+        command::Command::AdminHubState { response_sender }
+    })
+    .await?;
+    Ok(Json(hub_state.client_list))
+}
+
+async fn ban_list(State(admin_state): State<AdminState>) -> AdminResult<Vec<BanInfo>> {
+    let hub_state = hub_request(&admin_state, |response_sender| {
+        command::Command::AdminHubState { response_sender }
+    })
+    .await?;
+    Ok(Json(hub_state.ban_list))
+}
+
+fn check_target(target: &Target) -> Result<(), (StatusCode, String)> {
+    if let Target::Ip(ip) = target {
+        ban::parse_cidr(ip).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    }
+    Ok(())
+}
+
+fn check_duration_secs(duration_secs: u64) -> Result<(), (StatusCode, String)> {
+    if duration_secs == 0 || duration_secs > util::MAX_SANCTION_DURATION.as_secs() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "duration_secs must be between 1 and {}",
+                util::MAX_SANCTION_DURATION.as_secs()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+async fn kick(
+    State(admin_state): State<AdminState>,
+    Json(kick_request): Json<KickRequest>,
+) -> AdminResult<AffectedResponse> {
+    check_target(&kick_request.target)?;
+    let affected = hub_request(&admin_state, |response_sender| command::Command::Kick {
+        target: kick_request.target,
+        reason: kick_request.reason.unwrap_or("kicked".into()),
+        response_sender,
+    })
+    .await?;
+    if affected == 0 {
+        return Err((StatusCode::NOT_FOUND, "target not found".to_string()));
+    }
+    Ok(Json(AffectedResponse { affected }))
+}
+
+async fn ban(
+    State(admin_state): State<AdminState>,
+    Json(ban_request): Json<BanRequest>,
+) -> AdminResult<AffectedResponse> {
+    check_target(&ban_request.target)?;
+    if let Some(duration_secs) = ban_request.duration_secs {
+        check_duration_secs(duration_secs)?;
+    }
+    let affected = hub_request(&admin_state, |response_sender| command::Command::Ban {
+        target: ban_request.target,
+        reason: ban_request.reason.unwrap_or("banned".into()),
+        issuer: ban_request.issuer.unwrap_or("admin".into()),
+        duration: ban_request.duration_secs.map(Duration::from_secs),
+        response_sender,
+    })
+    .await?;
+    if affected == 0 {
+        return Err((StatusCode::NOT_FOUND, "target not found".to_string()));
+    }
+    Ok(Json(AffectedResponse { affected }))
+}
+
+async fn unban(
+    State(admin_state): State<AdminState>,
+    Json(unban_request): Json<UnbanRequest>,
+) -> AdminResult<AffectedResponse> {
+    if unban_request.ban_id.is_none()
+        && unban_request.player_db_id.is_none()
+        && unban_request.ip.is_none()
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "one of ban_id, player_db_id or ip is required".to_string(),
+        ));
+    }
+    if let Some(ip) = unban_request.ip.as_ref() {
+        ban::parse_cidr(ip).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    }
+    let affected = hub_request(&admin_state, |response_sender| command::Command::Unban {
+        unban_request,
+        response_sender,
+    })
+    .await?;
+    Ok(Json(AffectedResponse { affected }))
+}
+
+async fn mute(
+    State(admin_state): State<AdminState>,
+    Json(mute_request): Json<MuteRequest>,
+) -> AdminResult<AffectedResponse> {
+    check_target(&mute_request.target)?;
+    check_duration_secs(mute_request.duration_secs)?;
+    let affected = hub_request(&admin_state, |response_sender| command::Command::Mute {
+        target: mute_request.target,
+        duration: Duration::from_secs(mute_request.duration_secs),
+        reason: mute_request.reason.unwrap_or("muted".into()),
+        response_sender,
+    })
+    .await?;
+    if affected == 0 {
+        return Err((StatusCode::NOT_FOUND, "target not found".to_string()));
+    }
+    Ok(Json(AffectedResponse { affected }))
+}
+
+async fn unmute(
+    State(admin_state): State<AdminState>,
+    Json(unmute_request): Json<UnmuteRequest>,
+) -> AdminResult<AffectedResponse> {
+    let affected = hub_request(&admin_state, |response_sender| command::Command::Unmute {
+        player_db_id: unmute_request.player_db_id,
+        response_sender,
+    })
+    .await?;
+    Ok(Json(AffectedResponse { affected }))
+}
+
+async fn chat_log_list(
+    State(admin_state): State<AdminState>,
+    Query(chat_log_query): Query<ChatLogQuery>,
+) -> AdminResult<Vec<ChatLogInfo>> {
+    let limit = chat_log_query.limit.unwrap_or(100).clamp(1, 1000);
+    let chat_log_list = admin_state
+        .db
+        .chat_log_get_list(chat_log_query.player_db_id, limit)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .into_iter()
+        .map(|chat_log| ChatLogInfo {
+            id: chat_log.id,
+            player_db_id: chat_log.player_id,
+            nickname: chat_log.nickname,
+            msg: chat_log.msg,
+            blocked_reason: chat_log.blocked_reason,
+            channel: chat_log.channel,
+            target_player_db_id: chat_log.target_player_id,
+            created_at: chat_log.created_at,
+        })
+        .collect();
+    Ok(Json(chat_log_list))
+}
+
+async fn suspicion_list(State(admin_state): State<AdminState>) -> AdminResult<Vec<SuspicionInfo>> {
+    let hub_state = hub_request(&admin_state, |response_sender| {
+        command::Command::AdminHubState { response_sender }
+    })
+    .await?;
+    Ok(Json(hub_state.suspicion_list))
+}
+
+async fn pardon(
+    State(admin_state): State<AdminState>,
+    Json(pardon_request): Json<PardonRequest>,
+) -> AdminResult<AffectedResponse> {
+    let affected = hub_request(&admin_state, |response_sender| command::Command::Pardon {
+        player_db_id: pardon_request.player_db_id,
+        issuer: pardon_request.issuer.unwrap_or("admin".into()),
+        response_sender,
+    })
+    .await?;
+    Ok(Json(AffectedResponse { affected }))
+}
+
+async fn anti_cheat_log_list(
+    State(admin_state): State<AdminState>,
+    Query(anti_cheat_log_query): Query<AntiCheatLogQuery>,
+) -> AdminResult<Vec<AntiCheatLogInfo>> {
+    let limit = anti_cheat_log_query.limit.unwrap_or(100).clamp(1, 1000);
+    let anti_cheat_log_list = admin_state
+        .db
+        .anti_cheat_log_get_list(anti_cheat_log_query.player_db_id, limit)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .into_iter()
+        .map(|anti_cheat_log| AntiCheatLogInfo {
+            id: anti_cheat_log.id,
+            player_db_id: anti_cheat_log.player_id,
+            nickname: anti_cheat_log.nickname,
+            violation: anti_cheat_log.violation,
+            detail: anti_cheat_log.detail,
+            score: anti_cheat_log.score,
+            action: anti_cheat_log.action,
+            created_at: anti_cheat_log.created_at,
+        })
+        .collect();
+    Ok(Json(anti_cheat_log_list))
+}
+
+async fn round_result_list(
+    State(admin_state): State<AdminState>,
+    Query(round_result_query): Query<RoundResultQuery>,
+) -> AdminResult<Vec<RoundResultInfo>> {
+    let limit = round_result_query.limit.unwrap_or(100).clamp(1, 1000);
+    let round_result_list = admin_state
+        .db
+        .round_result_get_list(limit)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .into_iter()
+        .map(|round_result| RoundResultInfo {
+            id: round_result.id,
+            room_id: round_result.room_id,
+            round_number: round_result.round_number,
+            winner_player_db_id: round_result.winner_player_id,
+            winner_nickname: round_result.winner_nickname,
+            winner_score: round_result.winner_score,
+            winner_team: round_result.winner_team,
+            player_count: round_result.player_count,
+            started_at: round_result.started_at,
+            ended_at: round_result.ended_at,
+        })
+        .collect();
+    Ok(Json(round_result_list))
+}
+
+async fn broadcast(
+    State(admin_state): State<AdminState>,
+    Json(broadcast_request): Json<BroadcastRequest>,
+) -> AdminResult<AffectedResponse> {
+    let affected = hub_request(&admin_state, |response_sender| {
+        command::Command::BroadcastServerMessage {
+            msg: broadcast_request.msg,
+            response_sender,
+        }
+    })
+    .await?;
+    Ok(Json(AffectedResponse { affected }))
+}
+
+async fn spore_count(
+    State(admin_state): State<AdminState>,
+    Json(spore_count_request): Json<SporeCountRequest>,
+) -> AdminResult<config::GameConfig> {
+    let game_config = hub_request(&admin_state, |response_sender| {
+        command::Command::SetMaxSporeCount {
+            max_spore_count: spore_count_request.max_spore_count,
+            response_sender,
+        }
+    })
+    .await?
+    .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    Ok(Json(game_config))
+}
+
+async fn replay_start(State(admin_state): State<AdminState>) -> AdminResult<ReplayResponse> {
+    let path = hub_request(&admin_state, |response_sender| {
+        command::Command::StartReplay { response_sender }
+    })
+    .await?
+    .map_err(|e| (StatusCode::CONFLICT, e.to_string()))?;
+    Ok(Json(ReplayResponse { path }))
+}
+
+async fn replay_stop(State(admin_state): State<AdminState>) -> AdminResult<ReplayResponse> {
+    let path = hub_request(&admin_state, |response_sender| {
+        command::Command::StopReplay { response_sender }
+    })
+    .await?
+    .ok_or((StatusCode::NOT_FOUND, "replay not recording".to_string()))?;
+    Ok(Json(ReplayResponse { path }))
+}
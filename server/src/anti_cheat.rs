@@ -0,0 +1,284 @@
+use crate::*;
+use hashbrown::HashMap;
+use indexmap::IndexMap;
+use std::time::Duration;
+use tokio::time::Instant;
+
+const DIRECTION_WINDOW: Duration = Duration::from_secs(1);
+const SPORE_TOMBSTONE_WINDOW: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Violation {
+    ImpossibleConsume,
+    DirectionFlood,
+    BadPacket,
+}
+
+impl Violation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ImpossibleConsume => "impossible_consume",
+            Self::DirectionFlood => "direction_flood",
+            Self::BadPacket => "bad_packet",
+        }
+    }
+
+    fn score(&self, game_config: &config::GameConfig) -> f64 {
+        match self {
+            Self::ImpossibleConsume => game_config.anti_cheat_impossible_consume_score,
+            Self::DirectionFlood => game_config.anti_cheat_direction_flood_score,
+            Self::BadPacket => game_config.anti_cheat_bad_packet_score,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Suspicion {
+    pub score: f64,
+    pub updated_at: Instant,
+    pub direction_window_start: Instant,
+    pub direction_count: usize,
+    pub acted: bool,
+    pub restricted: bool,
+}
+
+impl Suspicion {
+    fn new(now: Instant) -> Self {
+        Self {
+            score: 0.0,
+            updated_at: now,
+            direction_window_start: now,
+            direction_count: 0,
+            acted: false,
+            restricted: false,
+        }
+    }
+
+    pub fn score_at(&self, now: Instant, game_config: &config::GameConfig) -> f64 {
+        let elapsed = now.duration_since(self.updated_at).as_secs_f64();
+        (self.score - elapsed * game_config.anti_cheat_decay_per_sec).max(0.0)
+    }
+
+    fn decay(&mut self, now: Instant, game_config: &config::GameConfig) {
+        self.score = self.score_at(now, game_config);
+        self.updated_at = now;
+        if self.score < game_config.anti_cheat_threshold {
+            self.acted = false;
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct SporeTombstone {
+    pub room_id: Arc<str>,
+    pub consumed_at: Instant,
+}
+
+#[derive(Debug, Default)]
+pub struct AntiCheat {
+    pub suspicion_map: HashMap<i64, Suspicion>,
+    pub spore_tombstone_map: IndexMap<Arc<str>, SporeTombstone>,
+}
+
+impl AntiCheat {
+    pub fn report(
+        &mut self,
+        player_db_id: i64,
+        violation: Violation,
+        game_config: &config::GameConfig,
+    ) -> (f64, Option<config::AntiCheatAction>) {
+        let now = Instant::now();
+        let suspicion = self
+            .suspicion_map
+            .entry(player_db_id)
+            .or_insert_with(|| Suspicion::new(now));
+        suspicion.decay(now, game_config);
+        suspicion.score += violation.score(game_config);
+
+        if suspicion.acted || suspicion.score < game_config.anti_cheat_threshold {
+            return (suspicion.score, None);
+        }
+        suspicion.acted = true;
+        if game_config.anti_cheat_action == config::AntiCheatAction::Restrict {
+            suspicion.restricted = true;
+        }
+        (suspicion.score, Some(game_config.anti_cheat_action))
+    }
+
+    pub fn count_direction(&mut self, player_db_id: i64) -> usize {
+        let now = Instant::now();
+        let suspicion = self
+            .suspicion_map
+            .entry(player_db_id)
+            .or_insert_with(|| Suspicion::new(now));
+        if now.duration_since(suspicion.direction_window_start) > DIRECTION_WINDOW {
+            suspicion.direction_window_start = now;
+            suspicion.direction_count = 0;
+        }
+        suspicion.direction_count += 1;
+        suspicion.direction_count
+    }
+
+    pub fn tombstone_spore(&mut self, spore_id: Arc<str>, room_id: Arc<str>) {
+        let now = Instant::now();
+        self.prune_spore_tombstone(now);
+        self.spore_tombstone_map.insert(
+            spore_id,
+            SporeTombstone {
+                room_id,
+                consumed_at: now,
+            },
+        );
+    }
+
+    pub fn spore_tombstone(&self, spore_id: &str) -> Option<&SporeTombstone> {
+        let now = Instant::now();
+        self.spore_tombstone_map
+            .get(spore_id)
+            .filter(|tombstone| now.duration_since(tombstone.consumed_at) <= SPORE_TOMBSTONE_WINDOW)
+    }
+
+    fn prune_spore_tombstone(&mut self, now: Instant) {
+        while let Some((_, tombstone)) = self.spore_tombstone_map.first()
+            && now.duration_since(tombstone.consumed_at) > SPORE_TOMBSTONE_WINDOW
+        {
+            self.spore_tombstone_map.shift_remove_index(0);
+        }
+    }
+
+    pub fn is_restricted(&self, player_db_id: i64) -> bool {
+        self.suspicion_map
+            .get(&player_db_id)
+            .is_some_and(|suspicion| suspicion.restricted)
+    }
+
+    pub fn score(&mut self, player_db_id: i64, game_config: &config::GameConfig) -> f64 {
+        match self.suspicion_map.get_mut(&player_db_id) {
+            Some(suspicion) => {
+                suspicion.decay(Instant::now(), game_config);
+                suspicion.score
+            }
+            None => 0.0,
+        }
+    }
+
+    pub fn pardon(&mut self, player_db_id: i64) -> bool {
+        self.suspicion_map.remove(&player_db_id).is_some()
+    }
+
+    pub fn forget(&mut self, player_db_id: i64, game_config: &config::GameConfig) {
+        if self.score(player_db_id, game_config) == 0.0 && !self.is_restricted(player_db_id) {
+            self.suspicion_map.remove(&player_db_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn report_acts_once_at_threshold() {
+        let game_config = config::GameConfig::default();
+        let mut anti_cheat = AntiCheat::default();
+
+        for _ in 0..9 {
+            let (_, action) = anti_cheat.report(1, Violation::ImpossibleConsume, &game_config);
+            assert_eq!(action, None);
+        }
+        let (score, action) = anti_cheat.report(1, Violation::ImpossibleConsume, &game_config);
+        assert_eq!(score, 100.0);
+        assert_eq!(action, Some(config::AntiCheatAction::Kick));
+
+        let (score, action) = anti_cheat.report(1, Violation::ImpossibleConsume, &game_config);
+        assert_eq!(score, 110.0);
+        assert_eq!(action, None);
+        assert_eq!(anti_cheat.score(2, &game_config), 0.0);
+        assert!(!anti_cheat.is_restricted(1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn report_restricts_when_configured() {
+        let game_config = config::GameConfig {
+            anti_cheat_action: config::AntiCheatAction::Restrict,
+            ..Default::default()
+        };
+        let mut anti_cheat = AntiCheat::default();
+
+        for _ in 0..5 {
+            anti_cheat.report(1, Violation::DirectionFlood, &game_config);
+        }
+        assert!(anti_cheat.is_restricted(1));
+        assert!(anti_cheat.pardon(1));
+        assert!(!anti_cheat.is_restricted(1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn score_decays_over_time() {
+        let game_config = config::GameConfig::default();
+        let mut anti_cheat = AntiCheat::default();
+
+        anti_cheat.report(1, Violation::BadPacket, &game_config);
+        tokio::time::advance(Duration::from_secs(4)).await;
+        assert_eq!(anti_cheat.score(1, &game_config), 6.0);
+
+        tokio::time::advance(Duration::from_secs(10)).await;
+        assert_eq!(anti_cheat.score(1, &game_config), 0.0);
+        anti_cheat.forget(1, &game_config);
+        assert!(anti_cheat.suspicion_map.is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn acted_resets_after_decay_below_threshold() {
+        let game_config = config::GameConfig::default();
+        let mut anti_cheat = AntiCheat::default();
+
+        for _ in 0..10 {
+            anti_cheat.report(1, Violation::ImpossibleConsume, &game_config);
+        }
+        assert!(anti_cheat.suspicion_map[&1].acted);
+
+        tokio::time::advance(Duration::from_secs(5)).await;
+        assert_eq!(anti_cheat.score(1, &game_config), 95.0);
+        assert!(!anti_cheat.suspicion_map[&1].acted);
+
+        let (score, action) = anti_cheat.report(1, Violation::ImpossibleConsume, &game_config);
+        assert_eq!(score, 105.0);
+        assert_eq!(action, Some(config::AntiCheatAction::Kick));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn count_direction_resets_after_window() {
+        let mut anti_cheat = AntiCheat::default();
+
+        for count in 1..=3 {
+            assert_eq!(anti_cheat.count_direction(1), count);
+        }
+        tokio::time::advance(DIRECTION_WINDOW).await;
+        assert_eq!(anti_cheat.count_direction(1), 4);
+        assert_eq!(anti_cheat.count_direction(2), 1);
+
+        tokio::time::advance(Duration::from_millis(1)).await;
+        assert_eq!(anti_cheat.count_direction(1), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn spore_tombstone_expires() {
+        let mut anti_cheat = AntiCheat::default();
+
+        anti_cheat.tombstone_spore("1".into(), room::MAIN_ROOM_ID.into());
+        assert_eq!(
+            anti_cheat
+                .spore_tombstone("1")
+                .map(|tombstone| &*tombstone.room_id),
+            Some(room::MAIN_ROOM_ID)
+        );
+        assert!(anti_cheat.spore_tombstone("2").is_none());
+
+        tokio::time::advance(SPORE_TOMBSTONE_WINDOW + Duration::from_millis(1)).await;
+        assert!(anti_cheat.spore_tombstone("1").is_none());
+
+        anti_cheat.tombstone_spore("2".into(), room::MAIN_ROOM_ID.into());
+        assert_eq!(anti_cheat.spore_tombstone_map.len(), 1);
+    }
+}
@@ -0,0 +1,78 @@
+use crate::*;
+use anyhow::{Result, anyhow};
+use indexmap::IndexMap;
+use ipnet::IpNet;
+use std::{net::IpAddr, time::Duration};
+
+#[derive(Debug, Clone)]
+pub struct BanEntry {
+    pub ban: db::Ban,
+    pub ip_net: Option<IpNet>,
+}
+
+#[derive(Debug, Default)]
+pub struct BanList {
+    pub entry_map: IndexMap<Arc<str>, BanEntry>,
+}
+
+impl BanList {
+    pub fn insert(&mut self, ban: db::Ban) {
+        let ip_net = match ban.cidr.as_ref() {
+            "" => None,
+            cidr => match parse_cidr(cidr) {
+                Ok(ip_net) => Some(ip_net),
+                Err(e) => {
+                    tracing::warn!("skip ban {:?}: {:?}", ban.id, e);
+                    return;
+                }
+            },
+        };
+        self.entry_map
+            .insert(ban.id.clone(), BanEntry { ban, ip_net });
+    }
+
+    pub fn remove(&mut self, id: &str) -> Option<db::Ban> {
+        self.entry_map.shift_remove(id).map(|entry| entry.ban)
+    }
+
+    pub fn ban_list(&self) -> impl Iterator<Item = &db::Ban> {
+        self.entry_map.values().map(|entry| &entry.ban)
+    }
+
+    pub fn find(&mut self, ip: IpAddr, player_db_id: Option<i64>, now: i64) -> Option<&db::Ban> {
+        self.entry_map
+            .retain(|_, entry| entry.ban.expires_at == 0 || entry.ban.expires_at > now);
+
+        let ip = ip.to_canonical();
+        self.entry_map
+            .values()
+            .find(|entry| {
+                let account_match = entry.ban.player_id != 0
+                    && player_db_id.is_some_and(|player_db_id| player_db_id == entry.ban.player_id);
+                let ip_match = entry.ip_net.is_some_and(|ip_net| ip_net.contains(&ip));
+                account_match || ip_match
+            })
+            .map(|entry| &entry.ban)
+    }
+}
+
+pub fn parse_cidr(s: &str) -> Result<IpNet> {
+    s.parse::<IpNet>()
+        .or_else(|_| s.parse::<IpAddr>().map(IpNet::from))
+        .map(|ip_net| ip_net.trunc())
+        .map_err(|_| anyhow!("invalid ip or cidr: {:?}", s))
+}
+
+pub fn ban_message(ban: &db::Ban, now: i64) -> Arc<str> {
+    let time_left = match ban.expires_at {
+        0 => "permanent".to_string(),
+        expires_at => {
+            let millis_left = (expires_at - now).max(0) as u64;
+            format!(
+                "{} left",
+                util::format_duration(Duration::from_millis(millis_left.div_ceil(1000) * 1000))
+            )
+        }
+    };
+    format!("banned: {} ({})", ban.reason, time_left).into()
+}
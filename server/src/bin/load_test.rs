@@ -0,0 +1,277 @@
+use agarust_server::proto;
+use anyhow::{Result, anyhow, bail};
+use futures_util::{SinkExt, StreamExt};
+use prost::Message as _;
+use std::{f64::consts::PI, io::Cursor, time::Duration};
+use tokio::{
+    net::TcpStream,
+    time::{Instant, interval, timeout},
+};
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, connect_async, tungstenite::Message};
+
+const DEFAULT_SERVER_URL: &str = "ws://127.0.0.1:8080";
+const DEFAULT_CLIENT_COUNT: usize = 50;
+const DEFAULT_DURATION_SECS: u64 = 30;
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(60);
+const INPUT_DURATION: Duration = Duration::from_millis(100);
+const PASSWORD: &str = "load_test";
+
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+#[derive(Debug, Default)]
+struct ClientReport {
+    error: Option<String>,
+    bytes_received: u64,
+    packet_received: u64,
+    tick_interval_list: Vec<Duration>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    dotenv::dotenv().ok();
+
+    let server_url = std::env::var("SERVER_URL").unwrap_or(DEFAULT_SERVER_URL.to_string());
+
+    let mut arg_list = std::env::args().skip(1);
+    let client_count = match arg_list.next() {
+        Some(arg) => arg.parse::<usize>()?,
+        None => DEFAULT_CLIENT_COUNT,
+    };
+    let duration = Duration::from_secs(match arg_list.next() {
+        Some(arg) => arg.parse::<u64>()?,
+        None => DEFAULT_DURATION_SECS,
+    });
+
+    println!(
+        "server_url: {:?}, client_count: {}, duration: {:?}",
+        server_url, client_count, duration
+    );
+
+    let run_id = nanoid::nanoid!(6, &nanoid::alphabet::SAFE[..62]);
+
+    let handle_list = (0..client_count)
+        .map(|index| {
+            let server_url = server_url.clone();
+            let username = format!("lt{}{}", run_id, index);
+            tokio::spawn(async move {
+                let mut report = ClientReport::default();
+                if let Err(e) = run_client(&server_url, &username, duration, &mut report).await {
+                    report.error = Some(e.to_string());
+                }
+                report
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut report_list = vec![];
+    for handle in handle_list {
+        report_list.push(handle.await?);
+    }
+
+    print_summary(&report_list, duration);
+
+    Ok(())
+}
+
+async fn run_client(
+    server_url: &str,
+    username: &str,
+    duration: Duration,
+    report: &mut ClientReport,
+) -> Result<()> {
+    let (mut ws_stream, _) = connect_async(server_url).await?;
+
+    expect_packet(&mut ws_stream, report, |data| {
+        matches!(data, proto::packet::Data::Hello(_))
+    })
+    .await?;
+
+    send_data(
+        &mut ws_stream,
+        proto::packet::Data::Register(proto::Register {
+            username: username.to_string(),
+            password: PASSWORD.to_string(),
+            color: rand::random::<i64>(),
+        }),
+    )
+    .await?;
+    expect_packet(&mut ws_stream, report, |data| {
+        matches!(
+            data,
+            proto::packet::Data::RegisterOk(_) | proto::packet::Data::RegisterErr(_)
+        )
+    })
+    .await?;
+
+    send_data(
+        &mut ws_stream,
+        proto::packet::Data::Login(proto::Login {
+            username: username.to_string(),
+            password: PASSWORD.to_string(),
+        }),
+    )
+    .await?;
+    let login_result = expect_packet(&mut ws_stream, report, |data| {
+        matches!(
+            data,
+            proto::packet::Data::LoginOk(_) | proto::packet::Data::LoginErr(_)
+        )
+    })
+    .await?;
+    if let proto::packet::Data::LoginErr(login_err) = login_result {
+        bail!("login error: {}", login_err.reason);
+    }
+
+    send_data(&mut ws_stream, proto::packet::Data::Join(proto::Join {})).await?;
+
+    let deadline = Instant::now() + duration;
+
+    let mut input_interval = interval(INPUT_DURATION);
+    let mut last_update_player_batch: Option<Instant> = None;
+
+    while Instant::now() < deadline {
+        tokio::select! {
+            _ = input_interval.tick() => {
+                send_random_input(&mut ws_stream).await?;
+            }
+            ws_stream_next = ws_stream.next() => {
+                let data = match ws_stream_next {
+                    Some(message) => receive_data(message?, report),
+                    None => bail!("connection closed by server"),
+                };
+                if let Some(proto::packet::Data::UpdatePlayerBatch(_)) = data {
+                    let now = Instant::now();
+                    if let Some(last) = last_update_player_batch {
+                        report.tick_interval_list.push(now - last);
+                    }
+                    last_update_player_batch = Some(now);
+                }
+            }
+        }
+    }
+
+    let _ = ws_stream.close(None).await;
+
+    Ok(())
+}
+
+async fn send_random_input(ws_stream: &mut WsStream) -> Result<()> {
+    let roll = rand::random::<f64>();
+    if roll < 0.01 {
+        send_data(ws_stream, proto::packet::Data::Rush(proto::Rush {})).await?;
+    } else if roll < 0.02 {
+        send_data(
+            ws_stream,
+            proto::packet::Data::Chat(proto::Chat {
+                connection_id: String::new(),
+                msg: format!("load test {}", rand::random::<u16>()),
+                ..Default::default()
+            }),
+        )
+        .await?;
+    }
+
+    send_data(
+        ws_stream,
+        proto::packet::Data::UpdatePlayerDirectionAngle(proto::UpdatePlayerDirectionAngle {
+            direction_angle: (rand::random::<f64>() * 2.0 - 1.0) * PI,
+        }),
+    )
+    .await
+}
+
+async fn send_data(ws_stream: &mut WsStream, data: proto::packet::Data) -> Result<()> {
+    let packet = proto::Packet { data: Some(data) };
+    ws_stream
+        .send(Message::binary(packet.encode_to_vec()))
+        .await
+        .map_err(|e| e.into())
+}
+
+fn receive_data(message: Message, report: &mut ClientReport) -> Option<proto::packet::Data> {
+    let bytes = match message {
+        Message::Binary(bytes) => bytes,
+        _ => return None,
+    };
+    report.bytes_received += bytes.len() as u64;
+    report.packet_received += 1;
+    proto::Packet::decode(Cursor::new(bytes))
+        .ok()
+        .and_then(|packet| packet.data)
+}
+
+async fn expect_packet(
+    ws_stream: &mut WsStream,
+    report: &mut ClientReport,
+    predicate: impl Fn(&proto::packet::Data) -> bool,
+) -> Result<proto::packet::Data> {
+    timeout(RESPONSE_TIMEOUT, async {
+        while let Some(message) = ws_stream.next().await {
+            if let Some(data) = receive_data(message?, report)
+                && predicate(&data)
+            {
+                return Ok(data);
+            }
+        }
+        Err(anyhow!("connection closed by server"))
+    })
+    .await?
+}
+
+fn percentile(sorted_list: &[Duration], p: f64) -> Duration {
+    if sorted_list.is_empty() {
+        return Duration::ZERO;
+    }
+    let index = ((sorted_list.len() - 1) as f64 * p).round() as usize;
+    sorted_list[index]
+}
+
+fn print_summary(report_list: &[ClientReport], duration: Duration) {
+    let failure_list = report_list
+        .iter()
+        .filter_map(|report| report.error.as_ref())
+        .collect::<Vec<_>>();
+
+    let bytes_received_total = report_list
+        .iter()
+        .map(|report| report.bytes_received)
+        .sum::<u64>();
+    let packet_received_total = report_list
+        .iter()
+        .map(|report| report.packet_received)
+        .sum::<u64>();
+
+    let mut tick_interval_list = report_list
+        .iter()
+        .flat_map(|report| report.tick_interval_list.iter().copied())
+        .collect::<Vec<_>>();
+    tick_interval_list.sort();
+
+    let client_count = report_list.len().max(1) as u64;
+    let duration_secs = duration.as_secs_f64().max(1.0);
+
+    println!("--- load test summary ---");
+    println!("clients: {}", report_list.len());
+    println!("connection failures: {}", failure_list.len());
+    for failure in failure_list.iter().take(10) {
+        println!("  {}", failure);
+    }
+    println!(
+        "bytes received: {} total, {} per client, {:.0} B/s per client",
+        bytes_received_total,
+        bytes_received_total / client_count,
+        bytes_received_total as f64 / client_count as f64 / duration_secs
+    );
+    println!(
+        "packets received: {} total, {} per client",
+        packet_received_total,
+        packet_received_total / client_count
+    );
+    println!(
+        "tick latency (interval between UpdatePlayerBatch): p50 {:?}, p95 {:?}, p99 {:?}, max {:?}",
+        percentile(&tick_interval_list, 0.50),
+        percentile(&tick_interval_list, 0.95),
+        percentile(&tick_interval_list, 0.99),
+        tick_interval_list.last().copied().unwrap_or_default()
+    );
+}
@@ -0,0 +1,36 @@
+use anyhow::{Result, bail};
+use std::time::Duration;
+
+const DEFAULT_BIND_ADDR: &str = "127.0.0.1:8090";
+const DEFAULT_SPEED: f64 = 1.0;
+const DEFAULT_START_SECS: f64 = 0.0;
+const USAGE: &str = "usage: replay <path> [speed] [start_secs]";
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    dotenv::dotenv().ok();
+
+    tracing_subscriber::fmt().init();
+
+    let bind_addr = std::env::var("REPLAY_BIND_ADDR").unwrap_or(DEFAULT_BIND_ADDR.to_string());
+
+    let mut arg_list = std::env::args().skip(1);
+    let path = match arg_list.next() {
+        Some(path) => path,
+        None => bail!(USAGE),
+    };
+    let speed = match arg_list.next() {
+        Some(arg) => arg.parse::<f64>()?,
+        None => DEFAULT_SPEED,
+    };
+    let start_secs = match arg_list.next() {
+        Some(arg) => arg.parse::<f64>()?,
+        None => DEFAULT_START_SECS,
+    };
+    let start_offset = match Duration::try_from_secs_f64(start_secs) {
+        Ok(start_offset) => start_offset,
+        Err(_) => bail!("start_secs must be a finite number >= 0"),
+    };
+
+    agarust_server::replay::play(&path, &bind_addr, speed, start_offset).await
+}
@@ -0,0 +1,128 @@
+use crate::*;
+use rand::Rng;
+use std::f64::consts::PI;
+
+const VIEW_DISTANCE: f64 = 600.0;
+const HUNT_RUSH_DISTANCE: f64 = 200.0;
+const WANDER_BOUND_RATIO: f64 = 0.9;
+
+const NICKNAME_LIST: [&str; 8] = [
+    "Amoeba", "Blob", "Cell", "Dot", "Echo", "Fungus", "Germ", "Hydra",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Behavior {
+    Flee { threat_connection_id: Arc<str> },
+    Hunt { prey_connection_id: Arc<str> },
+    ChaseSpore { spore_id: Arc<str> },
+    Wander,
+}
+
+#[derive(Debug, Clone)]
+pub struct Decision {
+    pub behavior: Behavior,
+    pub direction_angle: f64,
+    pub rush: bool,
+}
+
+pub fn random_nickname(rng: &mut impl Rng, index: usize) -> Arc<str> {
+    let nickname = NICKNAME_LIST[rng.random_range(0..NICKNAME_LIST.len())];
+    format!("[BOT] {} {}", nickname, index).into()
+}
+
+pub fn random_color(rng: &mut impl Rng) -> i64 {
+    // force alpha 0xFFFF
+    rng.random::<i64>() | 0xFFFF
+}
+
+fn distance_sq(x1: f64, y1: f64, x2: f64, y2: f64) -> f64 {
+    (x1 - x2).powi(2) + (y1 - y2).powi(2)
+}
+
+fn angle_to(x1: f64, y1: f64, x2: f64, y2: f64) -> f64 {
+    (y2 - y1).atan2(x2 - x1)
+}
+
+pub fn think<'a>(
+    rng: &mut impl Rng,
+    bot: &player::Player,
+    player_list: impl Iterator<Item = &'a player::Player>,
+    spore_list: impl Iterator<Item = &'a spore::Spore>,
+    game_config: &config::GameConfig,
+) -> Decision {
+    let bot_mass = util::radius_to_mass(bot.radius);
+    let view_distance_sq = VIEW_DISTANCE.powi(2);
+
+    let mut nearest_threat: Option<(&player::Player, f64)> = None;
+    let mut nearest_prey: Option<(&player::Player, f64)> = None;
+
+    for player in player_list {
+        if player.connection_id == bot.connection_id {
+            continue;
+        }
+        let player_distance_sq = distance_sq(bot.x, bot.y, player.x, player.y);
+        if player_distance_sq > view_distance_sq {
+            continue;
+        }
+        let player_mass = util::radius_to_mass(player.radius);
+        if player_mass >= bot_mass * game_config.eat_ratio {
+            if nearest_threat.is_none_or(|(_, d)| player_distance_sq < d) {
+                nearest_threat = Some((player, player_distance_sq));
+            }
+        } else if bot_mass >= player_mass * game_config.eat_ratio
+            && nearest_prey.is_none_or(|(_, d)| player_distance_sq < d)
+        {
+            nearest_prey = Some((player, player_distance_sq));
+        }
+    }
+
+    if let Some((threat, _)) = nearest_threat {
+        return Decision {
+            behavior: Behavior::Flee {
+                threat_connection_id: threat.connection_id.clone(),
+            },
+            direction_angle: angle_to(threat.x, threat.y, bot.x, bot.y),
+            rush: false,
+        };
+    }
+
+    if let Some((prey, prey_distance_sq)) = nearest_prey {
+        return Decision {
+            behavior: Behavior::Hunt {
+                prey_connection_id: prey.connection_id.clone(),
+            },
+            direction_angle: angle_to(bot.x, bot.y, prey.x, prey.y),
+            rush: prey_distance_sq < HUNT_RUSH_DISTANCE.powi(2),
+        };
+    }
+
+    let nearest_spore = spore_list
+        .map(|spore| (spore, distance_sq(bot.x, bot.y, spore.x, spore.y)))
+        .filter(|(_, spore_distance_sq)| *spore_distance_sq <= view_distance_sq)
+        .min_by(|(_, a), (_, b)| a.total_cmp(b));
+
+    if let Some((spore, _)) = nearest_spore {
+        return Decision {
+            behavior: Behavior::ChaseSpore {
+                spore_id: spore.id.clone(),
+            },
+            direction_angle: angle_to(bot.x, bot.y, spore.x, spore.y),
+            rush: false,
+        };
+    }
+
+    let wander_bound = game_config.world_bound * WANDER_BOUND_RATIO;
+    let direction_angle = if bot.x.abs() > wander_bound || bot.y.abs() > wander_bound {
+        angle_to(bot.x, bot.y, 0.0, 0.0)
+    } else if rng.random::<f64>() < 0.05 {
+        bot.direction_angle + (rng.random::<f64>() - 0.5) * PI / 2.0
+    } else {
+        bot.direction_angle
+    };
+
+    Decision {
+        behavior: Behavior::Wander,
+        direction_angle,
+        rush: false,
+    }
+}
@@ -0,0 +1,682 @@
+use crate::*;
+use hashbrown::HashMap;
+use std::{collections::VecDeque, time::Duration};
+use tokio::time::Instant;
+
+pub const HELP: &str = "commands: /w <nickname> <msg>, /p <msg>, /party [invite|accept|kick <nickname>|leave|protect on|off], /room [create [key=value ...]|join <code>], /friend add|accept|remove <nickname>, /friends, /stats, /leaderboard, /round, /help";
+pub const MODERATOR_HELP: &str = "moderator commands: /kick <nickname> [reason], /mute <nickname> <duration> [reason], /unmute <nickname>, /ban <nickname> <duration|perm> [reason], /suspicion <nickname>, /pardon <nickname>";
+pub const ADMIN_HELP: &str = "admin commands: /spawn <spore_count>, /set <game_setting> <value>";
+const MAX_SPAWN_SPORE_COUNT: usize = 1000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChatCommand {
+    Global {
+        msg: Arc<str>,
+    },
+    Whisper {
+        target_nickname: Arc<str>,
+        msg: Arc<str>,
+    },
+    Party {
+        msg: Arc<str>,
+    },
+    PartyAction {
+        party_action: party::PartyAction,
+    },
+    RoomAction {
+        room_action: room::RoomAction,
+    },
+    Friend {
+        friend_action: friend::FriendAction,
+    },
+    FriendList,
+    Help,
+    Stats,
+    Leaderboard,
+    Round,
+    Kick {
+        target_nickname: Arc<str>,
+        reason: Option<Arc<str>>,
+    },
+    Mute {
+        target_nickname: Arc<str>,
+        duration: Duration,
+        reason: Option<Arc<str>>,
+    },
+    Unmute {
+        target_nickname: Arc<str>,
+    },
+    Ban {
+        target_nickname: Arc<str>,
+        duration: Option<Duration>,
+        reason: Option<Arc<str>>,
+    },
+    Suspicion {
+        target_nickname: Arc<str>,
+    },
+    Pardon {
+        target_nickname: Arc<str>,
+    },
+    Spawn {
+        spore_count: usize,
+    },
+    Set {
+        key: Arc<str>,
+        value: Arc<str>,
+    },
+}
+
+impl ChatCommand {
+    pub fn required_role(&self) -> role::Role {
+        match self {
+            Self::Kick { .. }
+            | Self::Mute { .. }
+            | Self::Unmute { .. }
+            | Self::Ban { .. }
+            | Self::Suspicion { .. }
+            | Self::Pardon { .. } => role::Role::Moderator,
+            Self::Spawn { .. } | Self::Set { .. } => role::Role::Admin,
+            _ => role::Role::Player,
+        }
+    }
+
+    pub fn moderation_target(&self) -> Option<&str> {
+        match self {
+            Self::Kick {
+                target_nickname, ..
+            }
+            | Self::Mute {
+                target_nickname, ..
+            }
+            | Self::Ban {
+                target_nickname, ..
+            } => Some(target_nickname),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ChatTarget {
+    pub connection_id: Arc<str>,
+    pub db_id: i64,
+    pub nickname: Arc<str>,
+}
+
+#[derive(Debug, Default)]
+pub struct ChatModerator {
+    pub mute_map: HashMap<i64, db::Mute>,
+    pub history_map: HashMap<i64, VecDeque<(Instant, Arc<str>)>>,
+}
+
+impl ChatModerator {
+    pub fn check(
+        &mut self,
+        player_db_id: i64,
+        msg: &str,
+        game_config: &config::GameConfig,
+    ) -> Result<Arc<str>, Arc<str>> {
+        let msg = msg.trim();
+        if msg.is_empty() {
+            return Err("empty message".into());
+        }
+
+        if let Some(mute) = self.mute_map.get(&player_db_id) {
+            let remaining_millis = mute.muted_until - util::now_millis();
+            if remaining_millis > 0 {
+                return Err(format!(
+                    "you are muted for {}s: {}",
+                    (remaining_millis as u64).div_ceil(1000),
+                    mute.reason
+                )
+                .into());
+            }
+            self.mute_map.remove(&player_db_id);
+        }
+
+        let msg_length = msg.chars().count();
+        if msg_length > game_config.chat_max_length {
+            return Err(format!(
+                "message too long: {} > {}",
+                msg_length, game_config.chat_max_length
+            )
+            .into());
+        }
+
+        let now = Instant::now();
+        let spam_window = Duration::from_millis(game_config.chat_spam_window_ms);
+        let history = self.history_map.entry(player_db_id).or_default();
+        while history
+            .front()
+            .is_some_and(|(instant, _)| now.duration_since(*instant) > spam_window)
+        {
+            history.pop_front();
+        }
+        if history.len() >= game_config.chat_spam_max_message {
+            return Err("sending messages too fast".into());
+        }
+        let msg: Arc<str> = filter_word(msg, &game_config.chat_word_filter).into();
+        let repeat_count = history
+            .iter()
+            .filter(|(_, old_msg)| old_msg.eq_ignore_ascii_case(&msg))
+            .count();
+        if repeat_count >= game_config.chat_repeat_max {
+            return Err("repeated message".into());
+        }
+
+        history.push_back((now, msg.clone()));
+
+        Ok(msg)
+    }
+
+    pub fn forget(&mut self, player_db_id: i64) {
+        self.history_map.remove(&player_db_id);
+    }
+}
+
+pub fn filter_word(msg: &str, word_filter: &[String]) -> String {
+    if word_filter.is_empty() {
+        return msg.to_string();
+    }
+
+    let mut filtered = String::with_capacity(msg.len());
+    let mut word = String::new();
+    let flush = |word: &mut String, filtered: &mut String| {
+        let blocked = word_filter
+            .iter()
+            .any(|filter| filter.to_lowercase() == word.to_lowercase());
+        if blocked {
+            filtered.extend(word.chars().map(|_| '*'));
+        } else {
+            filtered.push_str(word);
+        }
+        word.clear();
+    };
+    for c in msg.chars() {
+        if c.is_alphanumeric() {
+            word.push(c);
+        } else {
+            flush(&mut word, &mut filtered);
+            filtered.push(c);
+        }
+    }
+    flush(&mut word, &mut filtered);
+
+    filtered
+}
+
+pub fn channel_name(channel: proto::ChatChannel) -> &'static str {
+    match channel {
+        proto::ChatChannel::Global => "global",
+        proto::ChatChannel::Whisper => "whisper",
+        proto::ChatChannel::Party => "party",
+        proto::ChatChannel::System => "system",
+    }
+}
+
+pub fn parse_chat_command(chat: &proto::Chat) -> Result<ChatCommand, Arc<str>> {
+    let msg = chat.msg.trim();
+    match chat.channel() {
+        proto::ChatChannel::Global => {}
+        proto::ChatChannel::Whisper => {
+            return Ok(ChatCommand::Whisper {
+                target_nickname: chat.target_nickname.trim().into(),
+                msg: msg.into(),
+            });
+        }
+        proto::ChatChannel::Party => return Ok(ChatCommand::Party { msg: msg.into() }),
+        proto::ChatChannel::System => return Err("can not send to the system channel".into()),
+    }
+
+    let command_line = match msg.strip_prefix('/') {
+        Some(command_line) => command_line,
+        None => return Ok(ChatCommand::Global { msg: msg.into() }),
+    };
+
+    let (name, rest) = split_word(command_line);
+    match name.to_lowercase().as_str() {
+        "w" | "whisper" => match split_word(rest) {
+            (target_nickname, msg) if !target_nickname.is_empty() && !msg.is_empty() => {
+                Ok(ChatCommand::Whisper {
+                    target_nickname: target_nickname.into(),
+                    msg: msg.into(),
+                })
+            }
+            _ => Err("usage: /w <nickname> <msg>".into()),
+        },
+        "p" if !rest.is_empty() => Ok(ChatCommand::Party { msg: rest.into() }),
+        "p" => Err("usage: /p <msg>".into()),
+        "party" => {
+            let (action, rest) = split_word(rest);
+            let (nickname, rest) = split_word(rest);
+            let nickname: Arc<str> = nickname.into();
+            let party_action = match action.to_lowercase().as_str() {
+                _ if !rest.is_empty() => None,
+                "" | "info" if nickname.is_empty() => Some(party::PartyAction::Info),
+                "leave" if nickname.is_empty() => Some(party::PartyAction::Leave),
+                "protect" => match nickname.to_lowercase().as_str() {
+                    "on" => Some(party::PartyAction::Protect {
+                        friendly_fire_protection: true,
+                    }),
+                    "off" => Some(party::PartyAction::Protect {
+                        friendly_fire_protection: false,
+                    }),
+                    _ => None,
+                },
+                _ if nickname.is_empty() => None,
+                "invite" => Some(party::PartyAction::Invite { nickname }),
+                "accept" => Some(party::PartyAction::Accept { nickname }),
+                "kick" => Some(party::PartyAction::Kick { nickname }),
+                _ => None,
+            };
+            match party_action {
+                Some(party_action) => Ok(ChatCommand::PartyAction { party_action }),
+                None => {
+                    Err("usage: /party [invite|accept|kick <nickname>|leave|protect on|off]".into())
+                }
+            }
+        }
+        "friend" => {
+            let (action, rest) = split_word(rest);
+            let (nickname, rest) = split_word(rest);
+            let nickname: Arc<str> = nickname.into();
+            let friend_action = match action.to_lowercase().as_str() {
+                _ if nickname.is_empty() || !rest.is_empty() => None,
+                "add" => Some(friend::FriendAction::Request { nickname }),
+                "accept" => Some(friend::FriendAction::Accept { nickname }),
+                "remove" => Some(friend::FriendAction::Remove { nickname }),
+                _ => None,
+            };
+            match friend_action {
+                Some(friend_action) => Ok(ChatCommand::Friend { friend_action }),
+                None => Err("usage: /friend add|accept|remove <nickname>".into()),
+            }
+        }
+        "room" => {
+            let (action, rest) = split_word(rest);
+            match action.to_lowercase().as_str() {
+                "" | "info" if rest.is_empty() => Ok(ChatCommand::RoomAction {
+                    room_action: room::RoomAction::Info,
+                }),
+                "create" => {
+                    let mut settings = proto::RoomSettings::default();
+                    for setting in rest.split_whitespace() {
+                        let (key, value) = setting.split_once('=').ok_or_else(|| {
+                            Arc::from("usage: /room create [key=value ...], keys: world_bound, max_player_count, spore_density, eat_ratio, rush_cost_ratio, ranked, team_count, round_mode")
+                        })?;
+                        room::parse_setting(&mut settings, key, value)?;
+                    }
+                    Ok(ChatCommand::RoomAction {
+                        room_action: room::RoomAction::Create { settings },
+                    })
+                }
+                "join" => match split_word(rest) {
+                    (code, "") if !code.is_empty() => Ok(ChatCommand::RoomAction {
+                        room_action: room::RoomAction::Join {
+                            code: code.to_uppercase().into(),
+                        },
+                    }),
+                    _ => Err("usage: /room join <code>".into()),
+                },
+                _ => Err("usage: /room [create [key=value ...]|join <code>]".into()),
+            }
+        }
+        "friends" => Ok(ChatCommand::FriendList),
+        "help" => Ok(ChatCommand::Help),
+        "stats" => Ok(ChatCommand::Stats),
+        "leaderboard" | "top" => Ok(ChatCommand::Leaderboard),
+        "round" => Ok(ChatCommand::Round),
+        "kick" => match split_word(rest) {
+            (target_nickname, reason) if !target_nickname.is_empty() => Ok(ChatCommand::Kick {
+                target_nickname: target_nickname.into(),
+                reason: (!reason.is_empty()).then(|| reason.into()),
+            }),
+            _ => Err("usage: /kick <nickname> [reason]".into()),
+        },
+        "mute" => {
+            let (target_nickname, rest) = split_word(rest);
+            let (duration, reason) = split_word(rest);
+            match parse_duration(duration) {
+                Some(duration) if !target_nickname.is_empty() => Ok(ChatCommand::Mute {
+                    target_nickname: target_nickname.into(),
+                    duration,
+                    reason: (!reason.is_empty()).then(|| reason.into()),
+                }),
+                _ => {
+                    Err("usage: /mute <nickname> <duration like 30s, 10m, 2h, 1d> [reason]".into())
+                }
+            }
+        }
+        "unmute" => match split_word(rest) {
+            (target_nickname, "") if !target_nickname.is_empty() => Ok(ChatCommand::Unmute {
+                target_nickname: target_nickname.into(),
+            }),
+            _ => Err("usage: /unmute <nickname>".into()),
+        },
+        "ban" => {
+            let (target_nickname, rest) = split_word(rest);
+            let (duration, reason) = split_word(rest);
+            let duration = match duration {
+                "perm" => Some(None),
+                duration => parse_duration(duration).map(Some),
+            };
+            match duration {
+                Some(duration) if !target_nickname.is_empty() => Ok(ChatCommand::Ban {
+                    target_nickname: target_nickname.into(),
+                    duration,
+                    reason: (!reason.is_empty()).then(|| reason.into()),
+                }),
+                _ => Err(
+                    "usage: /ban <nickname> <duration like 30s, 10m, 2h, 1d or perm> [reason]"
+                        .into(),
+                ),
+            }
+        }
+        "suspicion" => match split_word(rest) {
+            (target_nickname, "") if !target_nickname.is_empty() => Ok(ChatCommand::Suspicion {
+                target_nickname: target_nickname.into(),
+            }),
+            _ => Err("usage: /suspicion <nickname>".into()),
+        },
+        "pardon" => match split_word(rest) {
+            (target_nickname, "") if !target_nickname.is_empty() => Ok(ChatCommand::Pardon {
+                target_nickname: target_nickname.into(),
+            }),
+            _ => Err("usage: /pardon <nickname>".into()),
+        },
+        "spawn" => match rest.parse::<usize>() {
+            Ok(spore_count) if (1..=MAX_SPAWN_SPORE_COUNT).contains(&spore_count) => {
+                Ok(ChatCommand::Spawn { spore_count })
+            }
+            _ => Err(format!("usage: /spawn <1..={}>", MAX_SPAWN_SPORE_COUNT).into()),
+        },
+        "set" => match split_word(rest) {
+            (key, value) if !key.is_empty() && !value.is_empty() => Ok(ChatCommand::Set {
+                key: key.into(),
+                value: value.into(),
+            }),
+            _ => Err("usage: /set <game_setting> <value>".into()),
+        },
+        _ => Err(format!("unknown command /{}, try /help", name).into()),
+    }
+}
+
+fn split_word(s: &str) -> (&str, &str) {
+    let s = s.trim_start();
+    match s.split_once(char::is_whitespace) {
+        Some((word, rest)) => (word, rest.trim()),
+        None => (s, ""),
+    }
+}
+
+pub fn parse_duration(s: &str) -> Option<Duration> {
+    let unit_index = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (value, unit) = s.split_at(unit_index);
+    let value = value.parse::<u64>().ok().filter(|value| *value > 0)?;
+    let unit_secs = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 60 * 60 * 24,
+        _ => return None,
+    };
+    value
+        .checked_mul(unit_secs)
+        .map(Duration::from_secs)
+        .filter(|duration| *duration <= util::MAX_SANCTION_DURATION)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(msg: &str) -> Result<ChatCommand, Arc<str>> {
+        parse_chat_command(&proto::Chat {
+            msg: msg.into(),
+            ..Default::default()
+        })
+    }
+
+    fn usage(msg: &str) -> String {
+        parse(msg).unwrap_err().to_string()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn check_rejects_empty_and_long_messages() {
+        let game_config = config::GameConfig {
+            chat_max_length: 5,
+            ..Default::default()
+        };
+        let mut chat_moderator = ChatModerator::default();
+
+        assert_eq!(
+            &*chat_moderator.check(1, "  ", &game_config).unwrap_err(),
+            "empty message"
+        );
+        assert_eq!(
+            &*chat_moderator
+                .check(1, "toolong", &game_config)
+                .unwrap_err(),
+            "message too long: 7 > 5"
+        );
+        assert_eq!(
+            &*chat_moderator.check(1, " hello ", &game_config).unwrap(),
+            "hello"
+        );
+        assert_eq!(
+            &*chat_moderator.check(1, "héllo", &game_config).unwrap(),
+            "héllo"
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn check_limits_messages_per_spam_window() {
+        let game_config = config::GameConfig::default();
+        let mut chat_moderator = ChatModerator::default();
+
+        for i in 0..game_config.chat_spam_max_message {
+            assert!(
+                chat_moderator
+                    .check(1, &format!("msg {}", i), &game_config)
+                    .is_ok()
+            );
+        }
+        assert_eq!(
+            &*chat_moderator
+                .check(1, "one more", &game_config)
+                .unwrap_err(),
+            "sending messages too fast"
+        );
+        assert!(
+            chat_moderator
+                .check(2, "other player", &game_config)
+                .is_ok()
+        );
+
+        tokio::time::advance(Duration::from_millis(game_config.chat_spam_window_ms + 1)).await;
+        assert!(chat_moderator.check(1, "one more", &game_config).is_ok());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn check_detects_repeats_after_filtering() {
+        let game_config = config::GameConfig {
+            chat_word_filter: vec!["darn".into(), "heck".into()],
+            ..Default::default()
+        };
+        let mut chat_moderator = ChatModerator::default();
+
+        assert_eq!(
+            &*chat_moderator.check(1, "darn it", &game_config).unwrap(),
+            "**** it"
+        );
+        assert_eq!(
+            &*chat_moderator.check(1, "HECK it", &game_config).unwrap(),
+            "**** it"
+        );
+        assert_eq!(
+            &*chat_moderator
+                .check(1, "Darn It", &game_config)
+                .unwrap_err(),
+            "repeated message"
+        );
+
+        chat_moderator.forget(1);
+        assert!(chat_moderator.check(1, "darn it", &game_config).is_ok());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn check_blocks_until_mute_expires() {
+        let game_config = config::GameConfig::default();
+        let mut chat_moderator = ChatModerator::default();
+        let now = util::now_millis();
+
+        chat_moderator.mute_map.insert(
+            1,
+            db::Mute {
+                player_id: 1,
+                reason: "spam".into(),
+                muted_until: now + 60_000,
+                created_at: now,
+            },
+        );
+        let reason = chat_moderator.check(1, "hello", &game_config).unwrap_err();
+        assert!(reason.starts_with("you are muted for "));
+        assert!(reason.ends_with("s: spam"));
+
+        chat_moderator.mute_map.get_mut(&1).unwrap().muted_until = now - 1;
+        assert!(chat_moderator.check(1, "hello", &game_config).is_ok());
+        assert!(chat_moderator.mute_map.is_empty());
+    }
+
+    #[test]
+    fn filter_word_masks_whole_words_only() {
+        let word_filter = vec!["bad".to_string()];
+
+        assert_eq!(filter_word("bad", &word_filter), "***");
+        assert_eq!(filter_word("BAD, bad!", &word_filter), "***, ***!");
+        assert_eq!(filter_word("badge not bad", &word_filter), "badge not ***");
+        assert_eq!(filter_word("bad", &[]), "bad");
+        assert_eq!(filter_word("", &word_filter), "");
+    }
+
+    #[test]
+    fn parse_chat_command_reports_usage() {
+        assert_eq!(usage("/w"), "usage: /w <nickname> <msg>");
+        assert_eq!(usage("/w alice"), "usage: /w <nickname> <msg>");
+        assert_eq!(usage("/p"), "usage: /p <msg>");
+        assert_eq!(
+            usage("/party invite"),
+            "usage: /party [invite|accept|kick <nickname>|leave|protect on|off]"
+        );
+        assert_eq!(
+            usage("/party protect maybe"),
+            "usage: /party [invite|accept|kick <nickname>|leave|protect on|off]"
+        );
+        assert_eq!(
+            usage("/friend add"),
+            "usage: /friend add|accept|remove <nickname>"
+        );
+        assert_eq!(
+            usage("/friend block alice"),
+            "usage: /friend add|accept|remove <nickname>"
+        );
+        assert!(usage("/room create ranked").starts_with("usage: /room create [key=value ...]"));
+        assert_eq!(usage("/room join"), "usage: /room join <code>");
+        assert_eq!(
+            usage("/room leave"),
+            "usage: /room [create [key=value ...]|join <code>]"
+        );
+        assert_eq!(usage("/kick"), "usage: /kick <nickname> [reason]");
+        assert_eq!(
+            usage("/mute alice"),
+            "usage: /mute <nickname> <duration like 30s, 10m, 2h, 1d> [reason]"
+        );
+        assert_eq!(
+            usage("/mute alice forever"),
+            "usage: /mute <nickname> <duration like 30s, 10m, 2h, 1d> [reason]"
+        );
+        assert_eq!(usage("/unmute"), "usage: /unmute <nickname>");
+        assert_eq!(usage("/unmute alice bob"), "usage: /unmute <nickname>");
+        assert_eq!(
+            usage("/ban alice"),
+            "usage: /ban <nickname> <duration like 30s, 10m, 2h, 1d or perm> [reason]"
+        );
+        assert_eq!(usage("/suspicion"), "usage: /suspicion <nickname>");
+        assert_eq!(usage("/pardon"), "usage: /pardon <nickname>");
+        assert_eq!(usage("/spawn 0"), "usage: /spawn <1..=1000>");
+        assert_eq!(usage("/spawn 1001"), "usage: /spawn <1..=1000>");
+        assert_eq!(
+            usage("/set max_spore_count"),
+            "usage: /set <game_setting> <value>"
+        );
+        assert_eq!(usage("/dance"), "unknown command /dance, try /help");
+    }
+
+    #[test]
+    fn parse_chat_command_parses_commands() {
+        assert_eq!(
+            parse("hello"),
+            Ok(ChatCommand::Global {
+                msg: "hello".into()
+            })
+        );
+        assert_eq!(
+            parse("/W alice hi there"),
+            Ok(ChatCommand::Whisper {
+                target_nickname: "alice".into(),
+                msg: "hi there".into(),
+            })
+        );
+        assert_eq!(
+            parse("/mute alice 10m spam"),
+            Ok(ChatCommand::Mute {
+                target_nickname: "alice".into(),
+                duration: Duration::from_secs(600),
+                reason: Some("spam".into()),
+            })
+        );
+        assert_eq!(
+            parse("/ban alice perm"),
+            Ok(ChatCommand::Ban {
+                target_nickname: "alice".into(),
+                duration: None,
+                reason: None,
+            })
+        );
+        assert_eq!(
+            parse("/room join abc"),
+            Ok(ChatCommand::RoomAction {
+                room_action: room::RoomAction::Join { code: "ABC".into() },
+            })
+        );
+        assert_eq!(
+            parse_chat_command(&proto::Chat {
+                msg: "hi".into(),
+                channel: proto::ChatChannel::System.into(),
+                ..Default::default()
+            })
+            .unwrap_err()
+            .as_ref(),
+            "can not send to the system channel"
+        );
+    }
+
+    #[test]
+    fn parse_duration_checks_units_and_bounds() {
+        assert_eq!(parse_duration("30"), Some(Duration::from_secs(30)));
+        assert_eq!(parse_duration("30s"), Some(Duration::from_secs(30)));
+        assert_eq!(parse_duration("10m"), Some(Duration::from_secs(600)));
+        assert_eq!(parse_duration("2h"), Some(Duration::from_secs(7200)));
+        assert_eq!(parse_duration("1d"), Some(Duration::from_secs(86400)));
+        assert_eq!(parse_duration("3650d"), Some(util::MAX_SANCTION_DURATION));
+        assert_eq!(parse_duration("3651d"), None);
+        assert_eq!(parse_duration("0s"), None);
+        assert_eq!(parse_duration(""), None);
+        assert_eq!(parse_duration("s"), None);
+        assert_eq!(parse_duration("-1m"), None);
+        assert_eq!(parse_duration("5w"), None);
+        assert_eq!(parse_duration("1.5h"), None);
+        assert_eq!(parse_duration("18446744073709551615d"), None);
+        assert_eq!(parse_duration("99999999999999999999s"), None);
+    }
+}
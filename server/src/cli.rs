@@ -0,0 +1,94 @@
+use crate::*;
+use anyhow::{Context, Result, anyhow, bail};
+
+const USAGE: &str = "usage:
+  agarust-server                                    run the game server
+  agarust-server migrate status                     show migration status
+  agarust-server migrate rollback [target_version]  roll back the latest migration, or down to target_version
+  agarust-server role <username> <role>             set the role of an account: player, moderator or admin";
+
+pub async fn run(arg_list: &[String], database_url: &str) -> Result<()> {
+    let arg_list = arg_list.iter().map(|arg| arg.as_str()).collect::<Vec<_>>();
+    match arg_list.as_slice() {
+        ["migrate", "status"] => migrate_status(database_url).await,
+        ["migrate", "rollback"] => migrate_rollback(database_url, None).await,
+        ["migrate", "rollback", target_version] => {
+            let target_version = target_version
+                .parse::<i64>()
+                .with_context(|| format!("invalid target_version: {:?}", target_version))?;
+            migrate_rollback(database_url, Some(target_version)).await
+        }
+        ["role", username, role] => {
+            let role = role
+                .parse::<role::Role>()
+                .map_err(|e| anyhow!("invalid role: {}", e))?;
+            set_role(database_url, username, role).await
+        }
+        _ => {
+            println!("{}", USAGE);
+            bail!("unknown command: {:?}", arg_list);
+        }
+    }
+}
+
+async fn migrate_status(database_url: &str) -> Result<()> {
+    let db = db::Db::connect(database_url).await?;
+
+    for migration_status in db.migrate_status().await? {
+        let state = match (migration_status.applied, migration_status.known) {
+            (true, true) => "applied",
+            (false, _) => "pending",
+            (true, false) => "applied (newer than this binary)",
+        };
+        println!(
+            "{} {} {}",
+            migration_status.version, state, migration_status.description
+        );
+    }
+
+    Ok(())
+}
+
+async fn migrate_rollback(database_url: &str, target_version: Option<i64>) -> Result<()> {
+    let db = db::Db::connect(database_url).await?;
+
+    let target_version = match target_version {
+        Some(target_version) => target_version,
+        None => {
+            let applied_version_list = db
+                .migrate_status()
+                .await?
+                .iter()
+                .filter(|migration_status| migration_status.applied)
+                .map(|migration_status| migration_status.version)
+                .collect::<Vec<_>>();
+            match applied_version_list.as_slice() {
+                [] => {
+                    println!("no applied migration");
+                    return Ok(());
+                }
+                [.., previous_version, _] => *previous_version,
+                [_] => 0,
+            }
+        }
+    };
+
+    db.migrate_undo(target_version).await?;
+    println!("rolled back to version {}", target_version);
+
+    Ok(())
+}
+
+async fn set_role(database_url: &str, username: &str, role: role::Role) -> Result<()> {
+    let db = db::Db::connect(database_url).await?;
+
+    let auth = db
+        .auth_get_one_by_username(username)
+        .await
+        .with_context(|| format!("account not found: {:?}", username))?;
+
+    db.auth_update_role(username, role).await?;
+    println!("{}: {} -> {}", username, auth.role, role);
+
+    Ok(())
+}
@@ -3,7 +3,6 @@ use anyhow::Result;
 use bytes::Bytes;
 use futures_util::{SinkExt, StreamExt};
 use prost::Message as _;
-use sqlx::query_as;
 use std::{io::Cursor, net::SocketAddr, sync::Arc, time::Duration};
 use tokio::{
     net::TcpStream,
@@ -23,9 +22,12 @@ pub struct ClientAgent {
     pub connection_id: Arc<str>,
     pub db: db::Db,
     pub hub_command_sender: UnboundedSender<command::Command>,
+    pub db_writer_command_sender: UnboundedSender<command::Command>,
     pub client_agent_command_sender: UnboundedSender<command::Command>,
     pub client_agent_command_receiver: UnboundedReceiver<command::Command>,
     pub db_player: Option<db::Player>,
+    pub username: Arc<str>,
+    pub role: role::Role,
 }
 
 impl ClientAgent {
@@ -34,6 +36,7 @@ impl ClientAgent {
         socket_addr: SocketAddr,
         db: db::Db,
         hub_command_sender: UnboundedSender<command::Command>,
+        db_writer_command_sender: UnboundedSender<command::Command>,
     ) -> Result<Self> {
         let (client_agent_command_sender, client_agent_command_receiver) =
             unbounded_channel::<command::Command>();
@@ -55,9 +58,12 @@ impl ClientAgent {
             connection_id,
             db,
             hub_command_sender,
+            db_writer_command_sender,
             client_agent_command_sender,
             client_agent_command_receiver,
             db_player: None,
+            username: "".into(),
+            role: role::Role::default(),
         };
 
         Ok(client_agent)
@@ -102,6 +108,14 @@ impl ClientAgent {
             };
         }
 
+        if let Some(db_player) = self.db_player.as_ref() {
+            let _ = self
+                .db_writer_command_sender
+                .send(command::Command::FlushPlayerBestScore {
+                    player_db_id: db_player.id,
+                });
+        }
+
         let _ = self
             .hub_command_sender
             .send(command::Command::UnregisterClientAgent {
@@ -111,18 +125,24 @@ impl ClientAgent {
 
     async fn handle_ws_stream_message(&mut self, ws_stream_message: Message) {
         match ws_stream_message {
-            Message::Binary(bytes) => match proto::Packet::decode(Cursor::new(bytes)) {
+            Message::Binary(bytes) => match proto::Packet::decode(Cursor::new(&bytes)) {
                 Ok(packet) => {
+                    metrics::record_packet_in(proto_util::packet_type(&packet), bytes.len());
                     self.handle_packet(&packet).await;
                 }
                 Err(e) => {
                     warn!("proto decode error {:?}: {:?}", self, e);
+                    self.report_bad_packet(format!("decode error: {}", e));
                 }
             },
             Message::Close(close_frame) => {
                 info!("client close_frame: {:?}", close_frame);
                 let _ = self.ws_stream.close(None).await;
             }
+            Message::Text(_) | Message::Frame(_) => {
+                warn!("unkonwn message: {:?}", ws_stream_message);
+                self.report_bad_packet("non-binary message".to_string());
+            }
             _ => {
                 warn!("unkonwn message: {:?}", ws_stream_message);
             }
@@ -134,6 +154,7 @@ impl ClientAgent {
             Some(data) => data,
             None => {
                 warn!("packet has no data");
+                self.report_bad_packet("packet has no data".to_string());
                 return;
             }
         };
@@ -146,6 +167,7 @@ impl ClientAgent {
                     Ok(auth) => auth,
                     Err(e) => {
                         warn!("auth query error: {:?}", e);
+                        metrics::LOGIN.with_label_values(&["failure"]).inc();
                         let packet =
                             proto_util::login_err_packet("incorrect username or password".into());
                         self.send_packet(&packet).await;
@@ -157,6 +179,7 @@ impl ClientAgent {
                     Ok(valid) => {
                         if !valid {
                             warn!("bcrypt valid false");
+                            metrics::LOGIN.with_label_values(&["failure"]).inc();
                             let packet = proto_util::login_err_packet(
                                 "incorrect username or password".into(),
                             );
@@ -166,6 +189,7 @@ impl ClientAgent {
                     }
                     Err(e) => {
                         warn!("bcrypt verify error: {:?}", e);
+                        metrics::LOGIN.with_label_values(&["failure"]).inc();
                         let packet =
                             proto_util::login_err_packet("incorrect username or password".into());
                         self.send_packet(&packet).await;
@@ -177,6 +201,7 @@ impl ClientAgent {
                     Ok(player) => player,
                     Err(e) => {
                         warn!("player query error: {:?}", e);
+                        metrics::LOGIN.with_label_values(&["failure"]).inc();
                         let packet =
                             proto_util::login_err_packet("incorrect username or password".into());
                         self.send_packet(&packet).await;
@@ -184,98 +209,59 @@ impl ClientAgent {
                     }
                 };
 
-                self.db_player = Some(player);
-
-                let packet = proto_util::login_ok_packet();
-                self.send_packet(&packet).await;
-            }
-            proto::packet::Data::Register(register) => {
-                let mut transaction = match self.db.db_pool.begin().await {
-                    Ok(transaction) => transaction,
-                    Err(e) => {
-                        warn!("transaction begin error: {:?}", e);
-                        let packet =
-                            proto_util::register_err_packet("transaction begin error".into());
-                        self.send_packet(&packet).await;
-                        return;
-                    }
-                };
-
-                if register.username.is_empty() {
-                    let packet = proto_util::register_err_packet("username is empty".into());
-                    self.send_packet(&packet).await;
-                    return;
-                }
-                if register.username.len() > 16 {
-                    let packet = proto_util::register_err_packet("username too long".into());
-                    self.send_packet(&packet).await;
-                    return;
-                }
-
-                let query_result = query_as!(
-                    db::Auth,
-                    r#"SELECT * FROM auth WHERE username = ? LIMIT 1"#,
-                    register.username
-                )
-                .fetch_one(&mut *transaction)
-                .await;
-
-                if query_result.is_ok() {
-                    let packet = proto_util::register_err_packet("username already exists".into());
+                let (response_sender, response_receiver) = oneshot::channel();
+                let _ = self.hub_command_sender.send(command::Command::CheckBan {
+                    socket_addr: self.socket_addr,
+                    player_db_id: Some(player.id),
+                    response_sender,
+                });
+                if let Ok(Some(ban_message)) = response_receiver.await {
+                    warn!("banned login: {:?} {:?}", auth.username, ban_message);
+                    metrics::LOGIN.with_label_values(&["banned"]).inc();
+                    let packet = proto_util::login_err_packet(ban_message);
                     self.send_packet(&packet).await;
                     return;
                 }
 
-                let password = match bcrypt::hash(&register.password, bcrypt::DEFAULT_COST) {
-                    Ok(password) => password,
-                    Err(_) => {
-                        let packet = proto_util::register_err_packet("password hash error".into());
-                        self.send_packet(&packet).await;
-                        return;
-                    }
-                };
-
-                let query_result = query_as!(
-                    db::Auth,
-                    r#"INSERT INTO auth ( username, password ) VALUES ( ?, ? )"#,
-                    register.username,
-                    password,
-                )
-                .execute(&mut *transaction)
-                .await;
-
-                let auth_id = match query_result {
-                    Ok(query_result) => query_result.last_insert_rowid(),
+                info!("login: {:?} {:?}", auth.username, auth.role);
+                let friend_player_db_id_list = match self.db.friend_get_list(player.id).await {
+                    Ok(friend_list) => friend_list
+                        .iter()
+                        .filter(|friend| {
+                            friend::friend_status(&friend.status) == proto::FriendStatus::Accepted
+                        })
+                        .map(|friend| friend.friend_player_id)
+                        .collect(),
                     Err(e) => {
-                        warn!("auth insert error: {:?}", e);
-                        let packet = proto_util::register_err_packet("auth insert error".into());
-                        self.send_packet(&packet).await;
-                        return;
+                        warn!("friend list query error: {:?}", e);
+                        Vec::new()
                     }
                 };
+                let _ = self.hub_command_sender.send(command::Command::Login {
+                    connection_id: self.connection_id.clone(),
+                    player_db_id: player.id,
+                    nickname: player.nickname.clone(),
+                    color: player.color,
+                    role: auth.role,
+                    friend_player_db_id_list,
+                });
+                self.db_player = Some(player);
+                self.username = auth.username;
+                self.role = auth.role;
 
-                // force alpha 0xFFFF
-                let color = register.color | 0xFFFF;
-                let query_result = query_as!(
-                    db::Player,
-                    r#"INSERT INTO player ( auth_id, nickname, color ) VALUES ( ?, ?, ? )"#,
-                    auth_id,
-                    register.username,
-                    color,
-                )
-                .execute(&mut *transaction)
-                .await;
-
-                if let Err(e) = query_result {
-                    warn!("player insert error: {:?}", e);
-                    let packet = proto_util::register_err_packet("player insert error".into());
-                    self.send_packet(&packet).await;
-                    return;
-                }
+                metrics::LOGIN.with_label_values(&["success"]).inc();
 
-                if let Err(e) = transaction.commit().await {
-                    warn!("transaction commit error: {:?}", e);
-                    let packet = proto_util::register_err_packet("transaction commit error".into());
+                let packet = proto_util::login_ok_packet();
+                self.send_packet(&packet).await;
+            }
+            proto::packet::Data::Register(register) => {
+                if let Err(e) = self
+                    .db
+                    .register(&register.username, &register.password, register.color)
+                    .await
+                {
+                    warn!("register error: {:?}", e);
+                    let packet = proto_util::register_err_packet(e.to_string().into());
                     self.send_packet(&packet).await;
                     return;
                 }
@@ -298,10 +284,59 @@ impl ClientAgent {
                     color: db_player.color,
                 });
             }
+            proto::packet::Data::Spectate(spectate) => {
+                let _ = self.hub_command_sender.send(command::Command::Spectate {
+                    connection_id: self.connection_id.clone(),
+                    mode: spectator::spectate_mode(spectate),
+                });
+            }
             proto::packet::Data::Chat(chat) => {
+                let (player_db_id, nickname) = match self.db_player.as_ref() {
+                    Some(db_player) => (db_player.id, db_player.nickname.clone()),
+                    None => {
+                        warn!("chat without login");
+                        return;
+                    }
+                };
+                let chat_command = match chat::parse_chat_command(chat) {
+                    Ok(chat::ChatCommand::Friend { friend_action }) => {
+                        let msg = match self.friend(friend_action).await {
+                            Ok(msg) | Err(msg) => msg,
+                        };
+                        let packet = proto_util::system_chat_packet(msg);
+                        self.send_packet(&packet).await;
+                        return;
+                    }
+                    Ok(chat::ChatCommand::FriendList) => {
+                        let msg = match self.friend_list().await {
+                            Ok(friend_list) => friend::format_friend_list(&friend_list),
+                            Err(reason) => reason,
+                        };
+                        let packet = proto_util::system_chat_packet(msg);
+                        self.send_packet(&packet).await;
+                        return;
+                    }
+                    Ok(chat_command) => chat_command,
+                    Err(reason) => {
+                        let packet = proto_util::system_chat_packet(reason);
+                        self.send_packet(&packet).await;
+                        return;
+                    }
+                };
+                if chat_command.required_role() > role::Role::Player
+                    || matches!(chat_command, chat::ChatCommand::Help)
+                {
+                    self.refresh_role().await;
+                }
+                if let Some(target_nickname) = chat_command.moderation_target() {
+                    self.refresh_target_role(target_nickname).await;
+                }
                 let _ = self.hub_command_sender.send(command::Command::Chat {
                     connection_id: self.connection_id.clone(),
-                    msg: chat.msg.as_str().into(),
+                    player_db_id,
+                    nickname,
+                    role: self.role,
+                    chat_command,
                 });
             }
             proto::packet::Data::UpdatePlayerDirectionAngle(update_player_direction_angle) => {
@@ -333,6 +368,60 @@ impl ClientAgent {
                     connection_id: self.connection_id.clone(),
                 });
             }
+            proto::packet::Data::FriendRequest(friend_request) => {
+                self.handle_friend_packet(Some(friend::FriendAction::Request {
+                    nickname: friend_request.nickname.trim().into(),
+                }))
+                .await;
+            }
+            proto::packet::Data::FriendAccept(friend_accept) => {
+                self.handle_friend_packet(Some(friend::FriendAction::Accept {
+                    nickname: friend_accept.nickname.trim().into(),
+                }))
+                .await;
+            }
+            proto::packet::Data::FriendRemove(friend_remove) => {
+                self.handle_friend_packet(Some(friend::FriendAction::Remove {
+                    nickname: friend_remove.nickname.trim().into(),
+                }))
+                .await;
+            }
+            proto::packet::Data::FriendListRequest(_) => {
+                self.handle_friend_packet(None).await;
+            }
+            proto::packet::Data::PartyInvite(party_invite) => {
+                self.send_party_action(party::PartyAction::Invite {
+                    nickname: party_invite.nickname.trim().into(),
+                });
+            }
+            proto::packet::Data::PartyAccept(party_accept) => {
+                self.send_party_action(party::PartyAction::Accept {
+                    nickname: party_accept.nickname.trim().into(),
+                });
+            }
+            proto::packet::Data::PartyLeave(_) => {
+                self.send_party_action(party::PartyAction::Leave);
+            }
+            proto::packet::Data::PartyKick(party_kick) => {
+                self.send_party_action(party::PartyAction::Kick {
+                    nickname: party_kick.nickname.trim().into(),
+                });
+            }
+            proto::packet::Data::PartyProtect(party_protect) => {
+                self.send_party_action(party::PartyAction::Protect {
+                    friendly_fire_protection: party_protect.friendly_fire_protection,
+                });
+            }
+            proto::packet::Data::CreateRoom(create_room) => {
+                self.send_room_action(room::RoomAction::Create {
+                    settings: create_room.settings.unwrap_or_default(),
+                });
+            }
+            proto::packet::Data::JoinRoom(join_room) => {
+                self.send_room_action(room::RoomAction::Join {
+                    code: join_room.code.trim().to_uppercase().into(),
+                });
+            }
             proto::packet::Data::Disconnect(_) => {
                 let _ = self
                     .client_agent_command_sender
@@ -360,6 +449,10 @@ impl ClientAgent {
             }
             _ => {
                 warn!("unknown packet data: {:?}", data);
+                self.report_bad_packet(format!(
+                    "unexpected packet {}",
+                    proto_util::packet_type(packet)
+                ));
             }
         }
     }
@@ -369,8 +462,8 @@ impl ClientAgent {
             command::Command::SendPacket { packet } => {
                 self.send_packet(&packet).await;
             }
-            command::Command::SendBytes { bytes } => {
-                self.send_bytes(bytes).await;
+            command::Command::SendBytes { packet_type, bytes } => {
+                self.send_bytes(packet_type, bytes).await;
             }
             command::Command::UpdateSporeBatch { spore_batch } => {
                 const SEND_INTERNAL_DURATION: Duration = Duration::from_millis(20);
@@ -380,38 +473,34 @@ impl ClientAgent {
                     let mut send_interval = interval(SEND_INTERNAL_DURATION);
                     for spore_chunk in spore_batch.chunks(SPORE_CHUNKS) {
                         let packet = proto_util::update_spore_batch_packet(spore_chunk);
+                        let packet_type = proto_util::packet_type(&packet);
                         let bytes = packet.encode_to_vec().into();
-                        let _ =
-                            client_agent_command_sender.send(command::Command::SendBytes { bytes });
+                        let _ = client_agent_command_sender
+                            .send(command::Command::SendBytes { packet_type, bytes });
                         send_interval.tick().await;
                     }
                 });
             }
             command::Command::SyncPlayerBestScore { current_score } => {
-                let db_player_id = {
-                    let db_player = match self.db_player.as_mut() {
-                        Some(db_player) => db_player,
-                        None => {
-                            warn!("sync player best score without login");
-                            return;
-                        }
-                    };
-                    if db_player.best_score > current_score {
+                let db_player = match self.db_player.as_mut() {
+                    Some(db_player) => db_player,
+                    None => {
+                        warn!("sync player best score without login");
                         return;
                     }
-
-                    db_player.best_score = current_score;
-
-                    db_player.id
                 };
-
-                if let Err(e) = self
-                    .db
-                    .player_update_best_score_by_id(current_score, db_player_id)
-                    .await
-                {
-                    error!("UPDATE player SET best_score error: {:?}", e);
+                if db_player.best_score >= current_score {
+                    return;
                 }
+
+                db_player.best_score = current_score;
+
+                let _ =
+                    self.db_writer_command_sender
+                        .send(command::Command::PersistPlayerBestScore {
+                            player_db_id: db_player.id,
+                            best_score: current_score,
+                        });
             }
             command::Command::DisconnectClinet => {
                 warn!("Command::DisconnectClinet");
@@ -423,12 +512,164 @@ impl ClientAgent {
         }
     }
 
+    fn send_party_action(&self, party_action: party::PartyAction) {
+        if self.db_player.is_none() {
+            warn!("party without login");
+            return;
+        }
+        let _ = self.hub_command_sender.send(command::Command::Party {
+            connection_id: self.connection_id.clone(),
+            party_action,
+        });
+    }
+
+    fn send_room_action(&self, room_action: room::RoomAction) {
+        if self.db_player.is_none() {
+            warn!("room without login");
+            return;
+        }
+        let _ = self.hub_command_sender.send(command::Command::Room {
+            connection_id: self.connection_id.clone(),
+            room_action,
+        });
+    }
+
+    async fn handle_friend_packet(&mut self, friend_action: Option<friend::FriendAction>) {
+        if let Some(friend_action) = friend_action
+            && let Err(reason) = self.friend(friend_action).await
+        {
+            let packet = proto_util::friend_err_packet(reason);
+            self.send_packet(&packet).await;
+            return;
+        }
+        let packet = match self.friend_list().await {
+            Ok(friend_list) => proto_util::friend_list_packet(friend_list),
+            Err(reason) => proto_util::friend_err_packet(reason),
+        };
+        self.send_packet(&packet).await;
+    }
+
+    async fn friend(&self, friend_action: friend::FriendAction) -> Result<Arc<str>, Arc<str>> {
+        let db_player = match self.db_player.as_ref() {
+            Some(db_player) => db_player,
+            None => {
+                warn!("friend without login");
+                return Err("login first".into());
+            }
+        };
+        let friend_outcome = friend::apply(&self.db, db_player, friend_action).await?;
+        info!("friend: {:?} {:?}", db_player.nickname, friend_outcome.msg);
+        if let Some((player_db_id, msg)) = friend_outcome.notify {
+            let _ = self
+                .hub_command_sender
+                .send(command::Command::NotifyPlayer { player_db_id, msg });
+        }
+        Ok(friend_outcome.msg)
+    }
+
+    async fn friend_list(&self) -> Result<Vec<proto::Friend>, Arc<str>> {
+        let db_player = match self.db_player.as_ref() {
+            Some(db_player) => db_player,
+            None => {
+                warn!("friend list without login");
+                return Err("login first".into());
+            }
+        };
+        let friend_list = match self.db.friend_get_list(db_player.id).await {
+            Ok(friend_list) => friend_list,
+            Err(e) => {
+                warn!("friend list query error: {:?}", e);
+                return Err("friend list unavailable, try again later".into());
+            }
+        };
+
+        let (response_sender, response_receiver) = oneshot::channel();
+        let _ = self.hub_command_sender.send(command::Command::Presence {
+            player_db_id_list: friend_list
+                .iter()
+                .map(|friend| friend.friend_player_id)
+                .collect(),
+            response_sender,
+        });
+        let presence_list = response_receiver.await.unwrap_or_default();
+
+        Ok(friend_list
+            .iter()
+            .enumerate()
+            .map(|(index, friend)| {
+                let status = friend::friend_status(&friend.status);
+                let presence = match status {
+                    proto::FriendStatus::Accepted => presence_list
+                        .get(index)
+                        .cloned()
+                        .unwrap_or_else(friend::Presence::offline),
+                    _ => friend::Presence::offline(),
+                };
+                proto::Friend {
+                    nickname: friend.nickname.to_string(),
+                    status: status.into(),
+                    presence: presence.status.into(),
+                    room_id: presence.room_id.to_string(),
+                }
+            })
+            .collect())
+    }
+
+    async fn refresh_role(&mut self) {
+        match self.db.auth_get_one_by_username(&self.username).await {
+            Ok(auth) if auth.role != self.role => {
+                info!(
+                    "role changed: {:?} {} -> {}",
+                    self.username, self.role, auth.role
+                );
+                self.role = auth.role;
+            }
+            Ok(_) => {}
+            Err(e) => warn!("role query error: {:?}", e),
+        }
+    }
+
+    async fn refresh_target_role(&self, target_nickname: &str) {
+        let (response_sender, response_receiver) = oneshot::channel();
+        let _ = self.hub_command_sender.send(command::Command::FindPlayer {
+            nickname: target_nickname.into(),
+            response_sender,
+        });
+        let player_db_id = match response_receiver.await {
+            Ok(Some(player_db_id)) => player_db_id,
+            _ => return,
+        };
+        match self.db.auth_get_one_by_player_id(player_db_id).await {
+            Ok(auth) => {
+                let _ = self.hub_command_sender.send(command::Command::SetRole {
+                    player_db_id,
+                    role: auth.role,
+                });
+            }
+            Err(e) => warn!("target role query error: {:?}", e),
+        }
+    }
+
+    fn report_bad_packet(&self, detail: String) {
+        if let Some(db_player) = self.db_player.as_ref() {
+            let _ = self.hub_command_sender.send(command::Command::Violation {
+                connection_id: self.connection_id.clone(),
+                player_db_id: db_player.id,
+                nickname: db_player.nickname.clone(),
+                violation: anti_cheat::Violation::BadPacket,
+                detail: detail.into(),
+            });
+        }
+    }
+
     async fn send_packet(&mut self, packet: &proto::Packet) {
         let bytes = packet.encode_to_vec().into();
-        self.send_bytes(bytes).await;
+        self.send_bytes(proto_util::packet_type(packet), bytes)
+            .await;
     }
 
-    async fn send_bytes(&mut self, bytes: Bytes) {
+    async fn send_bytes(&mut self, packet_type: &'static str, bytes: Bytes) {
+        metrics::record_packet_out(packet_type, bytes.len());
         let _ = self.ws_stream.send(Message::binary(bytes)).await;
     }
 }
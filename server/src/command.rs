@@ -16,19 +16,79 @@ pub enum Command {
         nickname: Arc<str>,
         color: i64,
     },
+    Login {
+        connection_id: Arc<str>,
+        player_db_id: i64,
+        nickname: Arc<str>,
+        color: i64,
+        role: role::Role,
+        friend_player_db_id_list: Vec<i64>,
+    },
+    Presence {
+        player_db_id_list: Vec<i64>,
+        response_sender: tokio::sync::oneshot::Sender<Vec<friend::Presence>>,
+    },
+    FindPlayer {
+        nickname: Arc<str>,
+        response_sender: tokio::sync::oneshot::Sender<Option<i64>>,
+    },
+    NotifyPlayer {
+        player_db_id: i64,
+        msg: Arc<str>,
+    },
     DisconnectClinet,
     SendPacket {
         packet: proto::Packet,
     },
     SendBytes {
+        packet_type: &'static str,
         bytes: bytes::Bytes,
     },
     SyncPlayerBestScore {
         current_score: i64,
     },
+    PersistPlayerBestScore {
+        player_db_id: i64,
+        best_score: i64,
+    },
+    FlushPlayerBestScore {
+        player_db_id: i64,
+    },
+    FlushAll {
+        response_sender: tokio::sync::oneshot::Sender<()>,
+    },
+    Shutdown {
+        reason: Arc<str>,
+        countdown: std::time::Duration,
+    },
+    DisconnectAll,
+    Spectate {
+        connection_id: Arc<str>,
+        mode: spectator::SpectateMode,
+    },
+    Party {
+        connection_id: Arc<str>,
+        party_action: party::PartyAction,
+    },
+    Room {
+        connection_id: Arc<str>,
+        room_action: room::RoomAction,
+    },
     Chat {
         connection_id: Arc<str>,
-        msg: Arc<str>,
+        player_db_id: i64,
+        nickname: Arc<str>,
+        role: role::Role,
+        chat_command: chat::ChatCommand,
+    },
+    PersistChatLog {
+        chat_log: db::ChatLog,
+    },
+    PersistMute {
+        mute: db::Mute,
+    },
+    DeleteMute {
+        player_db_id: i64,
     },
     UpdatePlayerDirectionAngle {
         connection_id: Arc<str>,
@@ -48,6 +108,86 @@ pub enum Command {
     Rush {
         connection_id: Arc<str>,
     },
+    ReloadConfig {
+        response_sender:
+            Option<tokio::sync::oneshot::Sender<Result<config::ConfigReload, Arc<str>>>>,
+    },
+    AdminHubState {
+        response_sender: tokio::sync::oneshot::Sender<admin::HubState>,
+    },
+    Kick {
+        target: admin::Target,
+        reason: Arc<str>,
+        response_sender: tokio::sync::oneshot::Sender<usize>,
+    },
+    Ban {
+        target: admin::Target,
+        reason: Arc<str>,
+        issuer: Arc<str>,
+        duration: Option<std::time::Duration>,
+        response_sender: tokio::sync::oneshot::Sender<usize>,
+    },
+    Unban {
+        unban_request: admin::UnbanRequest,
+        response_sender: tokio::sync::oneshot::Sender<usize>,
+    },
+    SetRole {
+        player_db_id: i64,
+        role: role::Role,
+    },
+    CheckBan {
+        socket_addr: SocketAddr,
+        player_db_id: Option<i64>,
+        response_sender: tokio::sync::oneshot::Sender<Option<Arc<str>>>,
+    },
+    Violation {
+        connection_id: Arc<str>,
+        player_db_id: i64,
+        nickname: Arc<str>,
+        violation: anti_cheat::Violation,
+        detail: Arc<str>,
+    },
+    PersistAntiCheatLog {
+        anti_cheat_log: db::AntiCheatLog,
+    },
+    Pardon {
+        player_db_id: i64,
+        issuer: Arc<str>,
+        response_sender: tokio::sync::oneshot::Sender<usize>,
+    },
+    PersistBan {
+        ban: db::Ban,
+    },
+    PersistRoundResult {
+        round_result: db::RoundResult,
+    },
+    DeleteBan {
+        ban_id: Arc<str>,
+    },
+    Mute {
+        target: admin::Target,
+        duration: std::time::Duration,
+        reason: Arc<str>,
+        response_sender: tokio::sync::oneshot::Sender<usize>,
+    },
+    Unmute {
+        player_db_id: i64,
+        response_sender: tokio::sync::oneshot::Sender<usize>,
+    },
+    BroadcastServerMessage {
+        msg: Arc<str>,
+        response_sender: tokio::sync::oneshot::Sender<usize>,
+    },
+    SetMaxSporeCount {
+        max_spore_count: usize,
+        response_sender: tokio::sync::oneshot::Sender<Result<config::GameConfig, Arc<str>>>,
+    },
+    StartReplay {
+        response_sender: tokio::sync::oneshot::Sender<Result<Arc<str>, Arc<str>>>,
+    },
+    StopReplay {
+        response_sender: tokio::sync::oneshot::Sender<Option<Arc<str>>>,
+    },
     LeaderboardRequest,
     LeaderboardResponse {
         entry_list: Vec<LeaderboardEntry>,
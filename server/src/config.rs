@@ -0,0 +1,682 @@
+use anyhow::{Context, Result, anyhow, bail};
+use serde::{Deserialize, Serialize};
+use std::{convert::Infallible, fmt::Display, ops::Deref, str::FromStr, time::Duration};
+use tokio::time::MissedTickBehavior;
+use toml::Table;
+
+const DEFAULT_CONFIG_PATH: &str = "agarust.toml";
+const MAX_SPORE_COUNT: usize = 20000;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ConfigReload {
+    pub changed_list: Vec<String>,
+    pub restart_required_list: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MissedTick {
+    #[default]
+    Burst,
+    Delay,
+    Skip,
+}
+
+impl From<MissedTick> for MissedTickBehavior {
+    fn from(missed_tick: MissedTick) -> Self {
+        match missed_tick {
+            MissedTick::Burst => MissedTickBehavior::Burst,
+            MissedTick::Delay => MissedTickBehavior::Delay,
+            MissedTick::Skip => MissedTickBehavior::Skip,
+        }
+    }
+}
+
+impl FromStr for MissedTick {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "burst" => Ok(Self::Burst),
+            "delay" => Ok(Self::Delay),
+            "skip" => Ok(Self::Skip),
+            _ => Err("expected one of burst, delay, skip".to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AntiCheatAction {
+    Flag,
+    #[default]
+    Kick,
+    Restrict,
+}
+
+impl AntiCheatAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Flag => "flag",
+            Self::Kick => "kick",
+            Self::Restrict => "restrict",
+        }
+    }
+}
+
+impl FromStr for AntiCheatAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "flag" => Ok(Self::Flag),
+            "kick" => Ok(Self::Kick),
+            "restrict" => Ok(Self::Restrict),
+            _ => Err("expected one of flag, kick, restrict".to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub game: GameConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub bind_addr: String,
+    pub database_url: DatabaseUrl,
+    pub log_directory: String,
+    pub log_file_name_prefix: String,
+    pub best_score_flush_duration_ms: u64,
+    pub admin_bind_addr: String,
+    pub admin_token: Secret,
+    pub metrics_bind_addr: String,
+    pub health_bind_addr: String,
+    pub shutdown_countdown_secs: u64,
+    pub rng_seed: u64,
+    pub replay_directory: String,
+    pub replay_record: bool,
+    pub replay_snapshot_interval_step: u64,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind_addr: "127.0.0.1:8080".to_string(),
+            database_url: DatabaseUrl("sqlite:agarust_db.sqlite".to_string()),
+            log_directory: "./".to_string(),
+            log_file_name_prefix: "agarust_server.log".to_string(),
+            best_score_flush_duration_ms: 5000,
+            admin_bind_addr: "127.0.0.1:8081".to_string(),
+            admin_token: Secret::default(),
+            metrics_bind_addr: "127.0.0.1:9090".to_string(),
+            health_bind_addr: String::new(),
+            shutdown_countdown_secs: 5,
+            rng_seed: 0,
+            replay_directory: "replays".to_string(),
+            replay_record: false,
+            replay_snapshot_interval_step: 200,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Secret(pub String);
+
+impl std::fmt::Debug for Secret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let redacted = if self.0.is_empty() { "" } else { "***" };
+        write!(f, "{:?}", redacted)
+    }
+}
+
+impl Deref for Secret {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl FromStr for Secret {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self(s.to_string()))
+    }
+}
+
+#[derive(Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct DatabaseUrl(pub String);
+
+impl std::fmt::Debug for DatabaseUrl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", redact_database_url(&self.0))
+    }
+}
+
+impl Deref for DatabaseUrl {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl FromStr for DatabaseUrl {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self(s.to_string()))
+    }
+}
+
+fn redact_database_url(database_url: &str) -> String {
+    let Some((scheme, rest)) = database_url.split_once("://") else {
+        return database_url.to_string();
+    };
+    let Some((user_info, host)) = rest.rsplit_once('@') else {
+        return database_url.to_string();
+    };
+    match user_info.split_once(':') {
+        Some((user, _)) => format!("{}://{}:***@{}", scheme, user, host),
+        None => database_url.to_string(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GameConfig {
+    pub tick_duration_ms: u64,
+    pub missed_tick: MissedTick,
+    pub max_command_per_tick: usize,
+    pub spawn_spore_duration_ms: u64,
+    pub max_spore_count: usize,
+    pub world_bound: f64,
+    pub init_radius: f64,
+    pub init_speed: f64,
+    pub rush_speed: f64,
+    pub rush_duration_ms: u64,
+    pub rush_min_radius: f64,
+    pub rush_cost_ratio: f64,
+    pub eat_ratio: f64,
+    pub decay_probability_per_radius: f64,
+    pub decay_drop_radius_base: f64,
+    pub decay_drop_radius_per_radius: f64,
+    pub decay_drop_radius_max: f64,
+    pub bot_target_population: usize,
+    pub max_spectator_count: usize,
+    pub max_party_size: usize,
+    pub max_room_count: usize,
+    pub team_count: usize,
+    pub team_target_score: i64,
+    pub round_mode: bool,
+    pub round_min_player_count: usize,
+    pub round_waiting_ms: u64,
+    pub round_countdown_ms: u64,
+    pub round_running_ms: u64,
+    pub round_results_ms: u64,
+    pub round_reset_ms: u64,
+    pub chat_max_length: usize,
+    pub chat_word_filter: Vec<String>,
+    pub chat_spam_window_ms: u64,
+    pub chat_spam_max_message: usize,
+    pub chat_repeat_max: usize,
+    pub anti_cheat_threshold: f64,
+    pub anti_cheat_action: AntiCheatAction,
+    pub anti_cheat_decay_per_sec: f64,
+    pub anti_cheat_max_direction_per_sec: usize,
+    pub anti_cheat_consume_distance_slack: f64,
+    pub anti_cheat_impossible_consume_score: f64,
+    pub anti_cheat_direction_flood_score: f64,
+    pub anti_cheat_bad_packet_score: f64,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            tick_duration_ms: 50,
+            missed_tick: MissedTick::Burst,
+            max_command_per_tick: 2000,
+            spawn_spore_duration_ms: 2000,
+            max_spore_count: 1000,
+            world_bound: 3000.0,
+            init_radius: 20.0,
+            init_speed: 150.0,
+            rush_speed: 300.0,
+            rush_duration_ms: 2000,
+            rush_min_radius: 20.0,
+            rush_cost_ratio: 0.2,
+            eat_ratio: 1.2,
+            decay_probability_per_radius: 0.00025,
+            decay_drop_radius_base: 5.0,
+            decay_drop_radius_per_radius: 0.02,
+            decay_drop_radius_max: 15.0,
+            bot_target_population: 8,
+            max_spectator_count: 20,
+            max_party_size: 4,
+            max_room_count: 20,
+            team_count: 0,
+            team_target_score: 20000,
+            round_mode: false,
+            round_min_player_count: 2,
+            round_waiting_ms: 5000,
+            round_countdown_ms: 10000,
+            round_running_ms: 180000,
+            round_results_ms: 10000,
+            round_reset_ms: 3000,
+            chat_max_length: 200,
+            chat_word_filter: Vec::new(),
+            chat_spam_window_ms: 10000,
+            chat_spam_max_message: 5,
+            chat_repeat_max: 2,
+            anti_cheat_threshold: 100.0,
+            anti_cheat_action: AntiCheatAction::Kick,
+            anti_cheat_decay_per_sec: 1.0,
+            anti_cheat_max_direction_per_sec: 120,
+            anti_cheat_consume_distance_slack: 100.0,
+            anti_cheat_impossible_consume_score: 10.0,
+            anti_cheat_direction_flood_score: 20.0,
+            anti_cheat_bad_packet_score: 10.0,
+        }
+    }
+}
+
+impl ServerConfig {
+    pub fn best_score_flush_duration(&self) -> Duration {
+        Duration::from_millis(self.best_score_flush_duration_ms)
+    }
+
+    pub fn shutdown_countdown(&self) -> Duration {
+        Duration::from_secs(self.shutdown_countdown_secs)
+    }
+}
+
+impl GameConfig {
+    pub fn tick_duration(&self) -> Duration {
+        Duration::from_millis(self.tick_duration_ms)
+    }
+
+    pub fn is_interval_step(&self, step_count: u64, interval: Duration) -> bool {
+        let interval_step = (interval.as_millis() as u64 / self.tick_duration_ms).max(1);
+        step_count.is_multiple_of(interval_step)
+    }
+
+    pub fn spawn_spore_duration(&self) -> Duration {
+        Duration::from_millis(self.spawn_spore_duration_ms)
+    }
+
+    pub fn rush_duration(&self) -> Duration {
+        Duration::from_millis(self.rush_duration_ms)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.tick_duration_ms == 0 {
+            bail!("game.tick_duration_ms must be greater than 0");
+        }
+        if self.max_command_per_tick == 0 {
+            bail!("game.max_command_per_tick must be greater than 0");
+        }
+        if self.max_spore_count > MAX_SPORE_COUNT {
+            bail!(
+                "game.max_spore_count must not be greater than {}",
+                MAX_SPORE_COUNT
+            );
+        }
+        if self.spawn_spore_duration_ms == 0 {
+            bail!("game.spawn_spore_duration_ms must be greater than 0");
+        }
+        if self.world_bound <= 0.0 {
+            bail!("game.world_bound must be greater than 0");
+        }
+        if self.init_radius <= 0.0 {
+            bail!("game.init_radius must be greater than 0");
+        }
+        if self.init_speed <= 0.0 || self.rush_speed <= 0.0 {
+            bail!("game.init_speed and game.rush_speed must be greater than 0");
+        }
+        if !(0.0..1.0).contains(&self.rush_cost_ratio) {
+            bail!("game.rush_cost_ratio must be in [0, 1)");
+        }
+        if self.eat_ratio < 1.0 {
+            bail!("game.eat_ratio must be at least 1");
+        }
+        if !(0.0..=1.0).contains(&self.decay_probability_per_radius) {
+            bail!("game.decay_probability_per_radius must be in [0, 1]");
+        }
+        if self.decay_drop_radius_base < 0.0
+            || self.decay_drop_radius_per_radius < 0.0
+            || self.decay_drop_radius_max < self.decay_drop_radius_base
+        {
+            bail!(
+                "game.decay_drop_radius_* must be non-negative and decay_drop_radius_max must not be less than decay_drop_radius_base"
+            );
+        }
+        if self.max_party_size < 2 {
+            bail!("game.max_party_size must be at least 2");
+        }
+        if self.team_count == 1 || self.team_count > crate::team::MAX_TEAM_COUNT {
+            bail!(
+                "game.team_count must be 0 (free-for-all) or in [2, {}]",
+                crate::team::MAX_TEAM_COUNT
+            );
+        }
+        if self.team_target_score <= 0 {
+            bail!("game.team_target_score must be greater than 0");
+        }
+        if self.round_min_player_count == 0 {
+            bail!("game.round_min_player_count must be greater than 0");
+        }
+        if self.round_running_ms < self.tick_duration_ms {
+            bail!("game.round_running_ms must not be less than game.tick_duration_ms");
+        }
+        if self.chat_max_length == 0 {
+            bail!("game.chat_max_length must be greater than 0");
+        }
+        if self.chat_spam_max_message == 0 || self.chat_repeat_max == 0 {
+            bail!("game.chat_spam_max_message and game.chat_repeat_max must be greater than 0");
+        }
+        if self.anti_cheat_threshold <= 0.0 {
+            bail!("game.anti_cheat_threshold must be greater than 0");
+        }
+        if self.anti_cheat_max_direction_per_sec == 0 {
+            bail!("game.anti_cheat_max_direction_per_sec must be greater than 0");
+        }
+        if self.anti_cheat_decay_per_sec < 0.0
+            || self.anti_cheat_consume_distance_slack < 0.0
+            || self.anti_cheat_impossible_consume_score < 0.0
+            || self.anti_cheat_direction_flood_score < 0.0
+            || self.anti_cheat_bad_packet_score < 0.0
+        {
+            bail!("game.anti_cheat_* must be non-negative");
+        }
+        Ok(())
+    }
+}
+
+impl GameConfig {
+    pub fn with_setting(&self, key: &str, value: &str) -> Result<Self> {
+        let mut table = Table::try_from(self)?;
+        let old_value = match table.get(key) {
+            Some(old_value) => old_value,
+            None => bail!("unknown game setting: {}", key),
+        };
+        let new_value = match format!("value = {}", value).parse::<Table>() {
+            Ok(mut value_table) => value_table.remove("value"),
+            Err(_) => None,
+        }
+        .unwrap_or(toml::Value::String(value.to_string()));
+        if old_value.type_str() != new_value.type_str() {
+            bail!(
+                "game.{} expects {}, got {}",
+                key,
+                old_value.type_str(),
+                new_value.type_str()
+            );
+        }
+        table.insert(key.to_string(), new_value);
+
+        let game_config = table.try_into::<Self>()?;
+        game_config.validate()?;
+
+        Ok(game_config)
+    }
+}
+
+impl Config {
+    pub fn path() -> String {
+        std::env::var("CONFIG_PATH").unwrap_or(DEFAULT_CONFIG_PATH.to_string())
+    }
+
+    pub fn load() -> Result<Self> {
+        Self::load_from_path(&Self::path())
+    }
+
+    pub fn load_from_path(config_path: &str) -> Result<Self> {
+        let mut config = match std::fs::read_to_string(config_path) {
+            Ok(config_str) => toml::from_str::<Self>(&config_str)
+                .with_context(|| format!("parse config file {:?} error", config_path))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Self::default(),
+            Err(e) => {
+                return Err(e).with_context(|| format!("read config file {:?} error", config_path));
+            }
+        };
+
+        config.apply_env()?;
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    fn apply_env(&mut self) -> Result<()> {
+        let server = &mut self.server;
+        env_override(&mut server.bind_addr, "BIND_ADDR")?;
+        env_override(&mut server.database_url, "DATABASE_URL")?;
+        env_override(&mut server.log_directory, "LOG_DIRECTORY")?;
+        env_override(&mut server.log_file_name_prefix, "LOG_FILE_NAME_PREFIX")?;
+        env_override(
+            &mut server.best_score_flush_duration_ms,
+            "BEST_SCORE_FLUSH_DURATION_MS",
+        )?;
+        env_override(&mut server.admin_bind_addr, "ADMIN_BIND_ADDR")?;
+        env_override(&mut server.admin_token, "ADMIN_TOKEN")?;
+        env_override(&mut server.metrics_bind_addr, "METRICS_BIND_ADDR")?;
+        env_override(&mut server.health_bind_addr, "HEALTH_BIND_ADDR")?;
+        env_override(
+            &mut server.shutdown_countdown_secs,
+            "SHUTDOWN_COUNTDOWN_SECS",
+        )?;
+        env_override(&mut server.rng_seed, "RNG_SEED")?;
+        env_override(&mut server.replay_directory, "REPLAY_DIRECTORY")?;
+        env_override(&mut server.replay_record, "REPLAY_RECORD")?;
+        env_override(
+            &mut server.replay_snapshot_interval_step,
+            "REPLAY_SNAPSHOT_INTERVAL_STEP",
+        )?;
+
+        let game = &mut self.game;
+        env_override(&mut game.tick_duration_ms, "TICK_DURATION_MS")?;
+        env_override(&mut game.missed_tick, "MISSED_TICK")?;
+        env_override(&mut game.max_command_per_tick, "MAX_COMMAND_PER_TICK")?;
+        env_override(&mut game.spawn_spore_duration_ms, "SPAWN_SPORE_DURATION_MS")?;
+        env_override(&mut game.max_spore_count, "MAX_SPORE_COUNT")?;
+        env_override(&mut game.world_bound, "WORLD_BOUND")?;
+        env_override(&mut game.init_radius, "INIT_RADIUS")?;
+        env_override(&mut game.init_speed, "INIT_SPEED")?;
+        env_override(&mut game.rush_speed, "RUSH_SPEED")?;
+        env_override(&mut game.rush_duration_ms, "RUSH_DURATION_MS")?;
+        env_override(&mut game.rush_min_radius, "RUSH_MIN_RADIUS")?;
+        env_override(&mut game.rush_cost_ratio, "RUSH_COST_RATIO")?;
+        env_override(&mut game.eat_ratio, "EAT_RATIO")?;
+        env_override(
+            &mut game.decay_probability_per_radius,
+            "DECAY_PROBABILITY_PER_RADIUS",
+        )?;
+        env_override(&mut game.decay_drop_radius_base, "DECAY_DROP_RADIUS_BASE")?;
+        env_override(
+            &mut game.decay_drop_radius_per_radius,
+            "DECAY_DROP_RADIUS_PER_RADIUS",
+        )?;
+        env_override(&mut game.decay_drop_radius_max, "DECAY_DROP_RADIUS_MAX")?;
+        env_override(&mut game.bot_target_population, "BOT_TARGET_POPULATION")?;
+        env_override(&mut game.max_spectator_count, "MAX_SPECTATOR_COUNT")?;
+        env_override(&mut game.max_party_size, "MAX_PARTY_SIZE")?;
+        env_override(&mut game.max_room_count, "MAX_ROOM_COUNT")?;
+        env_override(&mut game.team_count, "TEAM_COUNT")?;
+        env_override(&mut game.team_target_score, "TEAM_TARGET_SCORE")?;
+        env_override(&mut game.round_mode, "ROUND_MODE")?;
+        env_override(&mut game.round_min_player_count, "ROUND_MIN_PLAYER_COUNT")?;
+        env_override(&mut game.round_waiting_ms, "ROUND_WAITING_MS")?;
+        env_override(&mut game.round_countdown_ms, "ROUND_COUNTDOWN_MS")?;
+        env_override(&mut game.round_running_ms, "ROUND_RUNNING_MS")?;
+        env_override(&mut game.round_results_ms, "ROUND_RESULTS_MS")?;
+        env_override(&mut game.round_reset_ms, "ROUND_RESET_MS")?;
+        env_override(&mut game.chat_max_length, "CHAT_MAX_LENGTH")?;
+        env_override_list(&mut game.chat_word_filter, "CHAT_WORD_FILTER")?;
+        env_override(&mut game.chat_spam_window_ms, "CHAT_SPAM_WINDOW_MS")?;
+        env_override(&mut game.chat_spam_max_message, "CHAT_SPAM_MAX_MESSAGE")?;
+        env_override(&mut game.chat_repeat_max, "CHAT_REPEAT_MAX")?;
+        env_override(&mut game.anti_cheat_threshold, "ANTI_CHEAT_THRESHOLD")?;
+        env_override(&mut game.anti_cheat_action, "ANTI_CHEAT_ACTION")?;
+        env_override(
+            &mut game.anti_cheat_decay_per_sec,
+            "ANTI_CHEAT_DECAY_PER_SEC",
+        )?;
+        env_override(
+            &mut game.anti_cheat_max_direction_per_sec,
+            "ANTI_CHEAT_MAX_DIRECTION_PER_SEC",
+        )?;
+        env_override(
+            &mut game.anti_cheat_consume_distance_slack,
+            "ANTI_CHEAT_CONSUME_DISTANCE_SLACK",
+        )?;
+        env_override(
+            &mut game.anti_cheat_impossible_consume_score,
+            "ANTI_CHEAT_IMPOSSIBLE_CONSUME_SCORE",
+        )?;
+        env_override(
+            &mut game.anti_cheat_direction_flood_score,
+            "ANTI_CHEAT_DIRECTION_FLOOD_SCORE",
+        )?;
+        env_override(
+            &mut game.anti_cheat_bad_packet_score,
+            "ANTI_CHEAT_BAD_PACKET_SCORE",
+        )?;
+
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        let server = &self.server;
+        if server.bind_addr.is_empty() {
+            bail!("server.bind_addr is empty");
+        }
+        if server.database_url.is_empty() {
+            bail!("server.database_url is empty");
+        }
+        if server.metrics_bind_addr.is_empty() && server.health_bind_addr.is_empty() {
+            bail!("server.health_bind_addr must be set when server.metrics_bind_addr is empty");
+        }
+        if server.best_score_flush_duration_ms == 0 {
+            bail!("server.best_score_flush_duration_ms must be greater than 0");
+        }
+        if server.replay_snapshot_interval_step == 0 {
+            bail!("server.replay_snapshot_interval_step must be greater than 0");
+        }
+
+        self.game.validate()
+    }
+}
+
+pub fn diff<T: Serialize>(section: &str, old: &T, new: &T) -> Result<Vec<String>> {
+    let old = Table::try_from(old)?;
+    let new = Table::try_from(new)?;
+
+    let diff_list = new
+        .iter()
+        .filter_map(|(key, new_value)| match old.get(key) {
+            Some(old_value) if old_value == new_value => None,
+            Some(old_value) => Some(format!(
+                "{}.{}: {} -> {}",
+                section,
+                key,
+                format_value(key, old_value),
+                format_value(key, new_value)
+            )),
+            None => Some(format!(
+                "{}.{}: {}",
+                section,
+                key,
+                format_value(key, new_value)
+            )),
+        })
+        .collect();
+
+    Ok(diff_list)
+}
+
+fn format_value(key: &str, value: &toml::Value) -> String {
+    match (key, value.as_str()) {
+        ("admin_token", Some(value)) => format!("{:?}", Secret(value.to_string())),
+        ("database_url", Some(value)) => format!("{:?}", DatabaseUrl(value.to_string())),
+        _ => value.to_string(),
+    }
+}
+
+fn env_override<T>(value: &mut T, key: &str) -> Result<()>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    if let Ok(env_value) = std::env::var(key) {
+        *value = env_value
+            .parse()
+            .map_err(|e| anyhow!("invalid env {}={:?}: {}", key, env_value, e))?;
+    }
+    Ok(())
+}
+
+fn env_override_list<T>(value: &mut Vec<T>, key: &str) -> Result<()>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    if let Ok(env_value) = std::env::var(key) {
+        *value = env_value
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(|item| {
+                item.parse()
+                    .map_err(|e| anyhow!("invalid env {}={:?}: {}", key, env_value, e))
+            })
+            .collect::<Result<_>>()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server_config(admin_token: &str, database_url: &str) -> ServerConfig {
+        ServerConfig {
+            admin_token: Secret(admin_token.to_string()),
+            database_url: DatabaseUrl(database_url.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn debug_redacts_secrets() {
+        let debug = format!(
+            "{:?}",
+            server_config("token123", "postgres://agarust:hunter2@db:5432/agarust")
+        );
+        assert!(!debug.contains("token123"));
+        assert!(!debug.contains("hunter2"));
+        assert!(debug.contains(r#"admin_token: "***""#));
+        assert!(debug.contains("postgres://agarust:***@db:5432/agarust"));
+        assert!(format!("{:?}", Secret::default()).contains(r#""""#));
+    }
+
+    #[test]
+    fn diff_redacts_secrets() {
+        let old = server_config("old-token", "postgres://agarust:old-pass@db/agarust");
+        let new = server_config("new-token", "postgres://agarust:new-pass@db/agarust");
+        let diff_list = diff("server", &old, &new).unwrap();
+        assert_eq!(diff_list.len(), 2);
+        for diff in diff_list.iter() {
+            for secret in ["old-token", "new-token", "old-pass", "new-pass"] {
+                assert!(!diff.contains(secret), "{} leaks {}", diff, secret);
+            }
+        }
+        assert!(diff("server", &old, &old.clone()).unwrap().is_empty());
+    }
+}
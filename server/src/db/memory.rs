@@ -0,0 +1,509 @@
+use super::*;
+use anyhow::anyhow;
+use std::sync::Mutex;
+
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    pub state: Mutex<MemoryState>,
+}
+
+#[derive(Debug, Default)]
+pub struct MemoryState {
+    pub auth_list: Vec<Auth>,
+    pub player_list: Vec<Player>,
+    pub mute_list: Vec<Mute>,
+    pub chat_log_list: Vec<ChatLog>,
+    pub ban_list: Vec<Ban>,
+    pub anti_cheat_log_list: Vec<AntiCheatLog>,
+    pub round_result_list: Vec<RoundResult>,
+    pub friend_list: Vec<Friend>,
+}
+
+#[async_trait]
+impl Storage for MemoryStorage {
+    async fn migrate_run(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn migrate_status(&self) -> Result<Vec<MigrationStatus>> {
+        Ok(vec![])
+    }
+
+    async fn migrate_undo(&self, _target_version: i64) -> Result<()> {
+        Ok(())
+    }
+
+    async fn ping(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn auth_get_one_by_username(&self, username: &str) -> Result<Auth> {
+        let state = self.state.lock().unwrap();
+        state
+            .auth_list
+            .iter()
+            .find(|auth| auth.username.as_ref() == username)
+            .cloned()
+            .ok_or_else(|| anyhow!("auth not found: {:?}", username))
+    }
+
+    async fn auth_get_one_by_player_id(&self, player_id: i64) -> Result<Auth> {
+        let state = self.state.lock().unwrap();
+        state
+            .player_list
+            .iter()
+            .find(|player| player.id == player_id)
+            .and_then(|player| {
+                state
+                    .auth_list
+                    .iter()
+                    .find(|auth| auth.id == player.auth_id)
+            })
+            .cloned()
+            .ok_or_else(|| anyhow!("auth not found for player: {:?}", player_id))
+    }
+
+    async fn player_get_one_by_auth_id(&self, auth_id: i64) -> Result<Player> {
+        let state = self.state.lock().unwrap();
+        state
+            .player_list
+            .iter()
+            .find(|player| player.auth_id == auth_id)
+            .cloned()
+            .ok_or_else(|| anyhow!("player not found: {:?}", auth_id))
+    }
+
+    async fn player_get_one_by_nickname(&self, nickname: &str) -> Result<Player> {
+        let state = self.state.lock().unwrap();
+        state
+            .player_list
+            .iter()
+            .find(|player| player.nickname.as_ref() == nickname)
+            .cloned()
+            .ok_or_else(|| anyhow!("player not found: {:?}", nickname))
+    }
+
+    async fn player_get_list(&self, limit: i64) -> Result<Vec<Player>> {
+        let state = self.state.lock().unwrap();
+        let mut player_list = state.player_list.clone();
+        player_list.sort_by_key(|player| std::cmp::Reverse(player.best_score));
+        player_list.truncate(limit.max(0) as usize);
+        Ok(player_list)
+    }
+
+    async fn player_update_best_score_batch(&self, best_score_list: &[(i64, i64)]) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        for (id, best_score) in best_score_list {
+            if let Some(player) = state.player_list.iter_mut().find(|player| player.id == *id) {
+                player.best_score = player.best_score.max(*best_score);
+            }
+        }
+        Ok(())
+    }
+
+    async fn auth_player_insert(&self, username: &str, password: &str, color: i64) -> Result<i64> {
+        let mut state = self.state.lock().unwrap();
+        if state
+            .auth_list
+            .iter()
+            .any(|auth| auth.username.as_ref() == username)
+        {
+            return Err(anyhow!("username already exists: {:?}", username));
+        }
+
+        let auth_id = state.auth_list.len() as i64 + 1;
+        state.auth_list.push(Auth {
+            id: auth_id,
+            username: username.into(),
+            password: password.into(),
+            role: Role::default(),
+        });
+
+        let player_id = state.player_list.len() as i64 + 1;
+        state.player_list.push(Player {
+            id: player_id,
+            auth_id,
+            nickname: username.into(),
+            color,
+            best_score: 0,
+        });
+
+        Ok(auth_id)
+    }
+
+    async fn mute_get_list(&self, now: i64) -> Result<Vec<Mute>> {
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .mute_list
+            .iter()
+            .filter(|mute| mute.muted_until > now)
+            .cloned()
+            .collect())
+    }
+
+    async fn mute_upsert(&self, mute: &Mute) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state
+            .mute_list
+            .retain(|old_mute| old_mute.player_id != mute.player_id);
+        state.mute_list.push(mute.clone());
+        Ok(())
+    }
+
+    async fn mute_delete(&self, player_id: i64) -> Result<u64> {
+        let mut state = self.state.lock().unwrap();
+        let len = state.mute_list.len();
+        state.mute_list.retain(|mute| mute.player_id != player_id);
+        Ok((len - state.mute_list.len()) as u64)
+    }
+
+    async fn chat_log_insert_batch(&self, chat_log_list: &[ChatLog]) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        for chat_log in chat_log_list {
+            let id = state.chat_log_list.len() as i64 + 1;
+            state.chat_log_list.push(ChatLog {
+                id,
+                ..chat_log.clone()
+            });
+        }
+        Ok(())
+    }
+
+    async fn chat_log_get_list(&self, player_id: Option<i64>, limit: i64) -> Result<Vec<ChatLog>> {
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .chat_log_list
+            .iter()
+            .rev()
+            .filter(|chat_log| player_id.is_none_or(|player_id| chat_log.player_id == player_id))
+            .take(limit.max(0) as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn auth_update_role(&self, username: &str, role: Role) -> Result<u64> {
+        let mut state = self.state.lock().unwrap();
+        let mut rows_affected = 0;
+        state
+            .auth_list
+            .iter_mut()
+            .filter(|auth| auth.username.as_ref() == username)
+            .for_each(|auth| {
+                auth.role = role;
+                rows_affected += 1;
+            });
+        Ok(rows_affected)
+    }
+
+    async fn ban_get_list(&self, now: i64) -> Result<Vec<Ban>> {
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .ban_list
+            .iter()
+            .filter(|ban| ban.expires_at == 0 || ban.expires_at > now)
+            .cloned()
+            .collect())
+    }
+
+    async fn ban_insert(&self, ban: &Ban) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if state.ban_list.iter().any(|old_ban| old_ban.id == ban.id) {
+            return Err(anyhow!("ban id already exists: {:?}", ban.id));
+        }
+        state.ban_list.push(ban.clone());
+        Ok(())
+    }
+
+    async fn ban_delete(&self, id: &str) -> Result<u64> {
+        let mut state = self.state.lock().unwrap();
+        let len = state.ban_list.len();
+        state.ban_list.retain(|ban| ban.id.as_ref() != id);
+        Ok((len - state.ban_list.len()) as u64)
+    }
+
+    async fn anti_cheat_log_insert_batch(
+        &self,
+        anti_cheat_log_list: &[AntiCheatLog],
+    ) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        for anti_cheat_log in anti_cheat_log_list {
+            let id = state.anti_cheat_log_list.len() as i64 + 1;
+            state.anti_cheat_log_list.push(AntiCheatLog {
+                id,
+                ..anti_cheat_log.clone()
+            });
+        }
+        Ok(())
+    }
+
+    async fn anti_cheat_log_get_list(
+        &self,
+        player_id: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<AntiCheatLog>> {
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .anti_cheat_log_list
+            .iter()
+            .rev()
+            .filter(|anti_cheat_log| {
+                player_id.is_none_or(|player_id| anti_cheat_log.player_id == player_id)
+            })
+            .take(limit.max(0) as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn round_result_insert(&self, round_result: &RoundResult) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let id = state.round_result_list.len() as i64 + 1;
+        state.round_result_list.push(RoundResult {
+            id,
+            ..round_result.clone()
+        });
+        Ok(())
+    }
+
+    async fn round_result_get_list(&self, limit: i64) -> Result<Vec<RoundResult>> {
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .round_result_list
+            .iter()
+            .rev()
+            .take(limit.max(0) as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn friend_get_list(&self, player_id: i64) -> Result<Vec<Friend>> {
+        let state = self.state.lock().unwrap();
+        let nickname = |player_id: i64| {
+            state
+                .player_list
+                .iter()
+                .find(|player| player.id == player_id)
+                .map(|player| player.nickname.clone())
+        };
+        let mut friend_list = state
+            .friend_list
+            .iter()
+            .filter_map(|friend| {
+                if friend.player_id == player_id {
+                    Some(Friend {
+                        nickname: nickname(friend.friend_player_id)?,
+                        status: if friend.status.as_ref() == "accepted" {
+                            "accepted".into()
+                        } else {
+                            "outgoing".into()
+                        },
+                        ..friend.clone()
+                    })
+                } else if friend.friend_player_id == player_id
+                    && friend.status.as_ref() == "pending"
+                {
+                    Some(Friend {
+                        player_id,
+                        friend_player_id: friend.player_id,
+                        nickname: nickname(friend.player_id)?,
+                        status: "incoming".into(),
+                        created_at: friend.created_at,
+                    })
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        friend_list.sort_by(|a, b| a.nickname.cmp(&b.nickname));
+        Ok(friend_list)
+    }
+
+    async fn friend_insert_request(
+        &self,
+        player_id: i64,
+        friend_player_id: i64,
+        created_at: i64,
+    ) -> Result<u64> {
+        let mut state = self.state.lock().unwrap();
+        if state.friend_list.iter().any(|friend| {
+            friend.player_id == player_id && friend.friend_player_id == friend_player_id
+        }) {
+            return Ok(0);
+        }
+        state.friend_list.push(Friend {
+            player_id,
+            friend_player_id,
+            nickname: "".into(),
+            status: "pending".into(),
+            created_at,
+        });
+        Ok(1)
+    }
+
+    async fn friend_accept(
+        &self,
+        player_id: i64,
+        friend_player_id: i64,
+        created_at: i64,
+    ) -> Result<u64> {
+        let mut state = self.state.lock().unwrap();
+        let request = state.friend_list.iter_mut().find(|friend| {
+            friend.player_id == friend_player_id
+                && friend.friend_player_id == player_id
+                && friend.status.as_ref() == "pending"
+        });
+        match request {
+            Some(request) => request.status = "accepted".into(),
+            None => return Ok(0),
+        }
+        state.friend_list.retain(|friend| {
+            friend.player_id != player_id || friend.friend_player_id != friend_player_id
+        });
+        state.friend_list.push(Friend {
+            player_id,
+            friend_player_id,
+            nickname: "".into(),
+            status: "accepted".into(),
+            created_at,
+        });
+        Ok(1)
+    }
+
+    async fn friend_delete(&self, player_id: i64, friend_player_id: i64) -> Result<u64> {
+        let mut state = self.state.lock().unwrap();
+        let len = state.friend_list.len();
+        state.friend_list.retain(|friend| {
+            (friend.player_id, friend.friend_player_id) != (player_id, friend_player_id)
+                && (friend.player_id, friend.friend_player_id) != (friend_player_id, player_id)
+        });
+        Ok((len - state.friend_list.len()) as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn db() -> Db {
+        Db::new("memory:").await.unwrap()
+    }
+
+    fn chat_log(player_id: i64, msg: &str) -> ChatLog {
+        ChatLog {
+            id: 0,
+            player_id,
+            nickname: format!("player{}", player_id).into(),
+            msg: msg.into(),
+            blocked_reason: "".into(),
+            channel: "global".into(),
+            target_player_id: 0,
+            created_at: 0,
+        }
+    }
+
+    fn ban(id: &str, expires_at: i64) -> Ban {
+        Ban {
+            id: id.into(),
+            player_id: 1,
+            cidr: "".into(),
+            reason: "test".into(),
+            issuer: "admin".into(),
+            expires_at,
+            created_at: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn auth_player_insert_and_get() {
+        let db = db().await;
+
+        let auth_id = db
+            .auth_player_insert("alice", "hash", 0xff0000)
+            .await
+            .unwrap();
+        let auth = db.auth_get_one_by_username("alice").await.unwrap();
+        assert_eq!(auth.id, auth_id);
+        assert_eq!(auth.role, Role::default());
+
+        let player = db.player_get_one_by_auth_id(auth_id).await.unwrap();
+        assert_eq!(player.nickname.as_ref(), "alice");
+        assert_eq!(player.color, 0xff0000);
+        assert_eq!(player.best_score, 0);
+
+        let player_by_nickname = db.player_get_one_by_nickname("alice").await.unwrap();
+        assert_eq!(player_by_nickname.id, player.id);
+
+        let auth_by_player_id = db.auth_get_one_by_player_id(player.id).await.unwrap();
+        assert_eq!(auth_by_player_id.id, auth_id);
+        assert!(db.auth_get_one_by_player_id(player.id + 1).await.is_err());
+
+        assert!(db.auth_player_insert("alice", "hash", 0).await.is_err());
+        assert!(db.player_get_one_by_nickname("bob").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn player_update_best_score_batch_keeps_max() {
+        let db = db().await;
+        let alice = db.auth_player_insert("alice", "hash", 0).await.unwrap();
+        let bob = db.auth_player_insert("bob", "hash", 0).await.unwrap();
+        let alice = db.player_get_one_by_auth_id(alice).await.unwrap().id;
+        let bob = db.player_get_one_by_auth_id(bob).await.unwrap().id;
+
+        db.player_update_best_score_batch(&[(alice, 100), (bob, 50)])
+            .await
+            .unwrap();
+        db.player_update_best_score_batch(&[(alice, 20), (bob, 80), (404, 1)])
+            .await
+            .unwrap();
+
+        let player_list = db.player_get_list(10).await.unwrap();
+        let best_score_list = player_list
+            .iter()
+            .map(|player| (player.id, player.best_score))
+            .collect::<Vec<_>>();
+        assert_eq!(best_score_list, vec![(alice, 100), (bob, 80)]);
+        assert_eq!(db.player_get_list(1).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn chat_log_insert_and_get_list() {
+        let db = db().await;
+
+        db.chat_log_insert_batch(&[chat_log(1, "a"), chat_log(2, "b"), chat_log(1, "c")])
+            .await
+            .unwrap();
+
+        let chat_log_list = db.chat_log_get_list(None, 10).await.unwrap();
+        let msg_list = chat_log_list
+            .iter()
+            .map(|chat_log| (chat_log.id, chat_log.msg.as_ref()))
+            .collect::<Vec<_>>();
+        assert_eq!(msg_list, vec![(3, "c"), (2, "b"), (1, "a")]);
+
+        let chat_log_list = db.chat_log_get_list(Some(1), 1).await.unwrap();
+        assert_eq!(chat_log_list.len(), 1);
+        assert_eq!(chat_log_list[0].msg.as_ref(), "c");
+    }
+
+    #[tokio::test]
+    async fn ban_insert_get_list_and_delete() {
+        let db = db().await;
+
+        db.ban_insert(&ban("permanent", 0)).await.unwrap();
+        db.ban_insert(&ban("expired", 100)).await.unwrap();
+        db.ban_insert(&ban("active", 300)).await.unwrap();
+        assert!(db.ban_insert(&ban("active", 300)).await.is_err());
+
+        let ban_id_list = db
+            .ban_get_list(200)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|ban| ban.id)
+            .collect::<Vec<_>>();
+        assert_eq!(ban_id_list, vec!["permanent".into(), "active".into()]);
+
+        assert_eq!(db.ban_delete("active").await.unwrap(), 1);
+        assert_eq!(db.ban_delete("active").await.unwrap(), 0);
+        assert_eq!(db.ban_get_list(200).await.unwrap().len(), 1);
+    }
+}
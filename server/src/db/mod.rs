@@ -0,0 +1,315 @@
+pub mod memory;
+pub mod postgres;
+pub mod sqlite;
+pub mod timed;
+
+use crate::role::Role;
+use anyhow::{Context, Result, bail};
+use async_trait::async_trait;
+use sqlx::migrate::{Migrate, Migrator};
+use std::{fmt::Debug, ops::Deref, sync::Arc};
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Auth {
+    pub id: i64,
+    #[sqlx(try_from = "String")]
+    pub username: Arc<str>,
+    #[sqlx(try_from = "String")]
+    pub password: Arc<str>,
+    #[sqlx(try_from = "String")]
+    pub role: Role,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Player {
+    pub id: i64,
+    pub auth_id: i64,
+    #[sqlx(try_from = "String")]
+    pub nickname: Arc<str>,
+    pub color: i64,
+    pub best_score: i64,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Mute {
+    pub player_id: i64,
+    #[sqlx(try_from = "String")]
+    pub reason: Arc<str>,
+    pub muted_until: i64,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ChatLog {
+    pub id: i64,
+    pub player_id: i64,
+    #[sqlx(try_from = "String")]
+    pub nickname: Arc<str>,
+    #[sqlx(try_from = "String")]
+    pub msg: Arc<str>,
+    #[sqlx(try_from = "String")]
+    pub blocked_reason: Arc<str>,
+    #[sqlx(try_from = "String")]
+    pub channel: Arc<str>,
+    pub target_player_id: i64,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Ban {
+    #[sqlx(try_from = "String")]
+    pub id: Arc<str>,
+    pub player_id: i64,
+    #[sqlx(try_from = "String")]
+    pub cidr: Arc<str>,
+    #[sqlx(try_from = "String")]
+    pub reason: Arc<str>,
+    #[sqlx(try_from = "String")]
+    pub issuer: Arc<str>,
+    pub expires_at: i64,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct AntiCheatLog {
+    pub id: i64,
+    pub player_id: i64,
+    #[sqlx(try_from = "String")]
+    pub nickname: Arc<str>,
+    #[sqlx(try_from = "String")]
+    pub violation: Arc<str>,
+    #[sqlx(try_from = "String")]
+    pub detail: Arc<str>,
+    pub score: f64,
+    #[sqlx(try_from = "String")]
+    pub action: Arc<str>,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct RoundResult {
+    pub id: i64,
+    #[sqlx(try_from = "String")]
+    pub room_id: Arc<str>,
+    pub round_number: i64,
+    pub winner_player_id: i64,
+    #[sqlx(try_from = "String")]
+    pub winner_nickname: Arc<str>,
+    pub winner_score: i64,
+    pub winner_team: i64,
+    pub player_count: i64,
+    pub started_at: i64,
+    pub ended_at: i64,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Friend {
+    pub player_id: i64,
+    pub friend_player_id: i64,
+    #[sqlx(try_from = "String")]
+    pub nickname: Arc<str>,
+    #[sqlx(try_from = "String")]
+    pub status: Arc<str>,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: Arc<str>,
+    pub applied: bool,
+    pub known: bool,
+}
+
+#[async_trait]
+pub trait Storage: Debug + Send + Sync {
+    async fn migrate_run(&self) -> Result<()>;
+
+    async fn migrate_status(&self) -> Result<Vec<MigrationStatus>>;
+
+    async fn migrate_undo(&self, target_version: i64) -> Result<()>;
+
+    async fn ping(&self) -> Result<()>;
+
+    async fn auth_get_one_by_username(&self, username: &str) -> Result<Auth>;
+
+    async fn auth_get_one_by_player_id(&self, player_id: i64) -> Result<Auth>;
+
+    async fn player_get_one_by_auth_id(&self, auth_id: i64) -> Result<Player>;
+
+    async fn player_get_one_by_nickname(&self, nickname: &str) -> Result<Player>;
+
+    async fn player_get_list(&self, limit: i64) -> Result<Vec<Player>>;
+
+    async fn player_update_best_score_batch(&self, best_score_list: &[(i64, i64)]) -> Result<()>;
+
+    async fn auth_player_insert(&self, username: &str, password: &str, color: i64) -> Result<i64>;
+
+    async fn auth_update_role(&self, username: &str, role: Role) -> Result<u64>;
+
+    async fn mute_get_list(&self, now: i64) -> Result<Vec<Mute>>;
+
+    async fn mute_upsert(&self, mute: &Mute) -> Result<()>;
+
+    async fn mute_delete(&self, player_id: i64) -> Result<u64>;
+
+    async fn chat_log_insert_batch(&self, chat_log_list: &[ChatLog]) -> Result<()>;
+
+    async fn chat_log_get_list(&self, player_id: Option<i64>, limit: i64) -> Result<Vec<ChatLog>>;
+
+    async fn ban_get_list(&self, now: i64) -> Result<Vec<Ban>>;
+
+    async fn ban_insert(&self, ban: &Ban) -> Result<()>;
+
+    async fn ban_delete(&self, id: &str) -> Result<u64>;
+
+    async fn anti_cheat_log_insert_batch(&self, anti_cheat_log_list: &[AntiCheatLog])
+    -> Result<()>;
+
+    async fn anti_cheat_log_get_list(
+        &self,
+        player_id: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<AntiCheatLog>>;
+
+    async fn round_result_insert(&self, round_result: &RoundResult) -> Result<()>;
+
+    async fn round_result_get_list(&self, limit: i64) -> Result<Vec<RoundResult>>;
+
+    async fn friend_get_list(&self, player_id: i64) -> Result<Vec<Friend>>;
+
+    async fn friend_insert_request(
+        &self,
+        player_id: i64,
+        friend_player_id: i64,
+        created_at: i64,
+    ) -> Result<u64>;
+
+    async fn friend_accept(
+        &self,
+        player_id: i64,
+        friend_player_id: i64,
+        created_at: i64,
+    ) -> Result<u64>;
+
+    async fn friend_delete(&self, player_id: i64, friend_player_id: i64) -> Result<u64>;
+}
+
+#[derive(Debug, Clone)]
+pub struct Db {
+    pub storage: Arc<dyn Storage>,
+}
+
+impl Deref for Db {
+    type Target = dyn Storage;
+
+    fn deref(&self) -> &Self::Target {
+        self.storage.as_ref()
+    }
+}
+
+impl Db {
+    pub async fn new(database_url: &str) -> Result<Self> {
+        let db = Self::connect(database_url).await?;
+        db.migrate_run().await?;
+        Ok(db)
+    }
+
+    pub async fn connect(database_url: &str) -> Result<Self> {
+        let storage: Arc<dyn Storage> = if database_url.starts_with("sqlite:") {
+            Arc::new(sqlite::SqliteStorage::new(database_url).await?)
+        } else if database_url.starts_with("postgres:") || database_url.starts_with("postgresql:") {
+            Arc::new(postgres::PostgresStorage::new(database_url).await?)
+        } else if database_url.starts_with("memory:") {
+            Arc::new(memory::MemoryStorage::default())
+        } else {
+            bail!("unsupported database_url: {:?}", database_url);
+        };
+        let storage = Arc::new(timed::TimedStorage { storage });
+        Ok(Self { storage })
+    }
+
+    pub async fn register(&self, username: &str, password: &str, color: i64) -> Result<()> {
+        if username.is_empty() {
+            bail!("username is empty");
+        }
+        if username.len() > 16 {
+            bail!("username too long");
+        }
+
+        if self.auth_get_one_by_username(username).await.is_ok() {
+            bail!("username already exists");
+        }
+
+        let password =
+            bcrypt::hash(password, bcrypt::DEFAULT_COST).context("password hash error")?;
+
+        // force alpha 0xFFFF
+        let color = color | 0xFFFF;
+
+        self.auth_player_insert(username, &password, color)
+            .await
+            .context("auth player insert error")?;
+
+        Ok(())
+    }
+}
+
+pub async fn migration_status_list<C: Migrate + ?Sized>(
+    migrator: &Migrator,
+    conn: &mut C,
+) -> Result<Vec<MigrationStatus>> {
+    conn.ensure_migrations_table().await?;
+    let applied_migration_list = conn.list_applied_migrations().await?;
+
+    let mut migration_status_list = migrator
+        .iter()
+        .filter(|migration| migration.migration_type.is_up_migration())
+        .map(|migration| MigrationStatus {
+            version: migration.version,
+            description: migration.description.as_ref().into(),
+            applied: applied_migration_list
+                .iter()
+                .any(|applied_migration| applied_migration.version == migration.version),
+            known: true,
+        })
+        .collect::<Vec<_>>();
+
+    applied_migration_list
+        .iter()
+        .filter(|applied_migration| !migrator.version_exists(applied_migration.version))
+        .for_each(|applied_migration| {
+            migration_status_list.push(MigrationStatus {
+                version: applied_migration.version,
+                description: "unknown".into(),
+                applied: true,
+                known: false,
+            })
+        });
+
+    migration_status_list.sort_by_key(|migration_status| migration_status.version);
+
+    Ok(migration_status_list)
+}
+
+pub async fn migrate_check<C: Migrate + ?Sized>(migrator: &Migrator, conn: &mut C) -> Result<()> {
+    let migration_status_list = migration_status_list(migrator, conn).await?;
+
+    if let Some(unknown) = migration_status_list
+        .iter()
+        .rfind(|migration_status| !migration_status.known)
+    {
+        let latest_known_version = migrator
+            .iter()
+            .map(|migration| migration.version)
+            .max()
+            .unwrap_or_default();
+        bail!(
+            "database schema version {} is newer than this binary (latest known migration {}), upgrade the server or roll back the database",
+            unknown.version,
+            latest_known_version
+        );
+    }
+
+    Ok(())
+}
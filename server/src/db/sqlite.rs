@@ -0,0 +1,385 @@
+use super::*;
+use sqlx::migrate::Migrator;
+use sqlx::{Pool, Sqlite, query, query_as};
+use std::str::FromStr;
+
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations/sqlite");
+
+#[derive(Debug, Clone)]
+pub struct SqliteStorage {
+    pub db_pool: Pool<Sqlite>,
+}
+
+impl SqliteStorage {
+    pub async fn new(database_url: &str) -> Result<Self> {
+        let connect_options =
+            sqlx::sqlite::SqliteConnectOptions::from_str(database_url)?.create_if_missing(true);
+        let db_pool = sqlx::sqlite::SqlitePool::connect_with(connect_options).await?;
+        Ok(Self { db_pool })
+    }
+}
+
+#[async_trait]
+impl Storage for SqliteStorage {
+    async fn migrate_run(&self) -> Result<()> {
+        let mut conn = self.db_pool.acquire().await?;
+        migrate_check(&MIGRATOR, &mut *conn).await?;
+        drop(conn);
+
+        MIGRATOR.run(&self.db_pool).await.map_err(|e| e.into())
+    }
+
+    async fn migrate_status(&self) -> Result<Vec<MigrationStatus>> {
+        let mut conn = self.db_pool.acquire().await?;
+        migration_status_list(&MIGRATOR, &mut *conn).await
+    }
+
+    async fn migrate_undo(&self, target_version: i64) -> Result<()> {
+        MIGRATOR
+            .undo(&self.db_pool, target_version)
+            .await
+            .map_err(|e| e.into())
+    }
+
+    async fn ping(&self) -> Result<()> {
+        query(r#"SELECT 1"#).execute(&self.db_pool).await?;
+        Ok(())
+    }
+
+    async fn auth_get_one_by_username(&self, username: &str) -> Result<Auth> {
+        query_as(r#"SELECT * FROM auth WHERE username = ? LIMIT 1"#)
+            .bind(username)
+            .fetch_one(&self.db_pool)
+            .await
+            .map_err(|e| e.into())
+    }
+
+    async fn auth_get_one_by_player_id(&self, player_id: i64) -> Result<Auth> {
+        query_as(
+            r#"SELECT auth.* FROM auth JOIN player ON player.auth_id = auth.id WHERE player.id = ? LIMIT 1"#,
+        )
+        .bind(player_id)
+        .fetch_one(&self.db_pool)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    async fn player_get_one_by_auth_id(&self, auth_id: i64) -> Result<Player> {
+        query_as(r#"SELECT * FROM player WHERE auth_id = ? LIMIT 1"#)
+            .bind(auth_id)
+            .fetch_one(&self.db_pool)
+            .await
+            .map_err(|e| e.into())
+    }
+
+    async fn player_get_one_by_nickname(&self, nickname: &str) -> Result<Player> {
+        query_as(r#"SELECT * FROM player WHERE nickname = ? LIMIT 1"#)
+            .bind(nickname)
+            .fetch_one(&self.db_pool)
+            .await
+            .map_err(|e| e.into())
+    }
+
+    async fn player_get_list(&self, limit: i64) -> Result<Vec<Player>> {
+        query_as(r#"SELECT * FROM player ORDER BY best_score DESC LIMIT ?"#)
+            .bind(limit)
+            .fetch_all(&self.db_pool)
+            .await
+            .map_err(|e| e.into())
+    }
+
+    async fn player_update_best_score_batch(&self, best_score_list: &[(i64, i64)]) -> Result<()> {
+        let mut transaction = self.db_pool.begin().await?;
+
+        for (id, best_score) in best_score_list {
+            query(r#"UPDATE player SET best_score = MAX(best_score, ?) WHERE id = ?"#)
+                .bind(best_score)
+                .bind(id)
+                .execute(&mut *transaction)
+                .await?;
+        }
+
+        transaction.commit().await?;
+
+        Ok(())
+    }
+
+    async fn auth_player_insert(&self, username: &str, password: &str, color: i64) -> Result<i64> {
+        let mut transaction = self.db_pool.begin().await?;
+
+        let auth_id = query(r#"INSERT INTO auth ( username, password ) VALUES ( ?, ? )"#)
+            .bind(username)
+            .bind(password)
+            .execute(&mut *transaction)
+            .await?
+            .last_insert_rowid();
+
+        query(r#"INSERT INTO player ( auth_id, nickname, color ) VALUES ( ?, ?, ? )"#)
+            .bind(auth_id)
+            .bind(username)
+            .bind(color)
+            .execute(&mut *transaction)
+            .await?;
+
+        transaction.commit().await?;
+
+        Ok(auth_id)
+    }
+
+    async fn mute_get_list(&self, now: i64) -> Result<Vec<Mute>> {
+        query_as(r#"SELECT * FROM mute WHERE muted_until > ?"#)
+            .bind(now)
+            .fetch_all(&self.db_pool)
+            .await
+            .map_err(|e| e.into())
+    }
+
+    async fn mute_upsert(&self, mute: &Mute) -> Result<()> {
+        query(
+            r#"INSERT INTO mute ( player_id, reason, muted_until, created_at ) VALUES ( ?, ?, ?, ? )
+            ON CONFLICT ( player_id ) DO UPDATE SET reason = excluded.reason, muted_until = excluded.muted_until, created_at = excluded.created_at"#,
+        )
+        .bind(mute.player_id)
+        .bind(mute.reason.as_ref())
+        .bind(mute.muted_until)
+        .bind(mute.created_at)
+        .execute(&self.db_pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn mute_delete(&self, player_id: i64) -> Result<u64> {
+        let rows_affected = query(r#"DELETE FROM mute WHERE player_id = ?"#)
+            .bind(player_id)
+            .execute(&self.db_pool)
+            .await?
+            .rows_affected();
+        Ok(rows_affected)
+    }
+
+    async fn chat_log_insert_batch(&self, chat_log_list: &[ChatLog]) -> Result<()> {
+        let mut transaction = self.db_pool.begin().await?;
+
+        for chat_log in chat_log_list {
+            query(
+                r#"INSERT INTO chat_log ( player_id, nickname, msg, blocked_reason, channel, target_player_id, created_at ) VALUES ( ?, ?, ?, ?, ?, ?, ? )"#,
+            )
+            .bind(chat_log.player_id)
+            .bind(chat_log.nickname.as_ref())
+            .bind(chat_log.msg.as_ref())
+            .bind(chat_log.blocked_reason.as_ref())
+            .bind(chat_log.channel.as_ref())
+            .bind(chat_log.target_player_id)
+            .bind(chat_log.created_at)
+            .execute(&mut *transaction)
+            .await?;
+        }
+
+        transaction.commit().await?;
+
+        Ok(())
+    }
+
+    async fn chat_log_get_list(&self, player_id: Option<i64>, limit: i64) -> Result<Vec<ChatLog>> {
+        query_as(
+            r#"SELECT * FROM chat_log WHERE ( ? IS NULL OR player_id = ? ) ORDER BY id DESC LIMIT ?"#,
+        )
+        .bind(player_id)
+        .bind(player_id)
+        .bind(limit)
+        .fetch_all(&self.db_pool)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    async fn auth_update_role(&self, username: &str, role: Role) -> Result<u64> {
+        let rows_affected = query(r#"UPDATE auth SET role = ? WHERE username = ?"#)
+            .bind(role.as_str())
+            .bind(username)
+            .execute(&self.db_pool)
+            .await?
+            .rows_affected();
+        Ok(rows_affected)
+    }
+
+    async fn ban_get_list(&self, now: i64) -> Result<Vec<Ban>> {
+        query_as(r#"SELECT * FROM ban WHERE expires_at = 0 OR expires_at > ?"#)
+            .bind(now)
+            .fetch_all(&self.db_pool)
+            .await
+            .map_err(|e| e.into())
+    }
+
+    async fn ban_insert(&self, ban: &Ban) -> Result<()> {
+        query(
+            r#"INSERT INTO ban ( id, player_id, cidr, reason, issuer, expires_at, created_at ) VALUES ( ?, ?, ?, ?, ?, ?, ? )"#,
+        )
+        .bind(ban.id.as_ref())
+        .bind(ban.player_id)
+        .bind(ban.cidr.as_ref())
+        .bind(ban.reason.as_ref())
+        .bind(ban.issuer.as_ref())
+        .bind(ban.expires_at)
+        .bind(ban.created_at)
+        .execute(&self.db_pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn ban_delete(&self, id: &str) -> Result<u64> {
+        let rows_affected = query(r#"DELETE FROM ban WHERE id = ?"#)
+            .bind(id)
+            .execute(&self.db_pool)
+            .await?
+            .rows_affected();
+        Ok(rows_affected)
+    }
+
+    async fn anti_cheat_log_insert_batch(
+        &self,
+        anti_cheat_log_list: &[AntiCheatLog],
+    ) -> Result<()> {
+        let mut transaction = self.db_pool.begin().await?;
+
+        for anti_cheat_log in anti_cheat_log_list {
+            query(
+                r#"INSERT INTO anti_cheat_log ( player_id, nickname, violation, detail, score, action, created_at ) VALUES ( ?, ?, ?, ?, ?, ?, ? )"#,
+            )
+            .bind(anti_cheat_log.player_id)
+            .bind(anti_cheat_log.nickname.as_ref())
+            .bind(anti_cheat_log.violation.as_ref())
+            .bind(anti_cheat_log.detail.as_ref())
+            .bind(anti_cheat_log.score)
+            .bind(anti_cheat_log.action.as_ref())
+            .bind(anti_cheat_log.created_at)
+            .execute(&mut *transaction)
+            .await?;
+        }
+
+        transaction.commit().await?;
+
+        Ok(())
+    }
+
+    async fn anti_cheat_log_get_list(
+        &self,
+        player_id: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<AntiCheatLog>> {
+        query_as(
+            r#"SELECT * FROM anti_cheat_log WHERE ( ? IS NULL OR player_id = ? ) ORDER BY id DESC LIMIT ?"#,
+        )
+        .bind(player_id)
+        .bind(player_id)
+        .bind(limit)
+        .fetch_all(&self.db_pool)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    async fn round_result_insert(&self, round_result: &RoundResult) -> Result<()> {
+        query(
+            r#"INSERT INTO round_result ( room_id, round_number, winner_player_id, winner_nickname, winner_score, winner_team, player_count, started_at, ended_at ) VALUES ( ?, ?, ?, ?, ?, ?, ?, ?, ? )"#,
+        )
+        .bind(round_result.room_id.as_ref())
+        .bind(round_result.round_number)
+        .bind(round_result.winner_player_id)
+        .bind(round_result.winner_nickname.as_ref())
+        .bind(round_result.winner_score)
+        .bind(round_result.winner_team)
+        .bind(round_result.player_count)
+        .bind(round_result.started_at)
+        .bind(round_result.ended_at)
+        .execute(&self.db_pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn round_result_get_list(&self, limit: i64) -> Result<Vec<RoundResult>> {
+        query_as(r#"SELECT * FROM round_result ORDER BY id DESC LIMIT ?"#)
+            .bind(limit)
+            .fetch_all(&self.db_pool)
+            .await
+            .map_err(|e| e.into())
+    }
+
+    async fn friend_get_list(&self, player_id: i64) -> Result<Vec<Friend>> {
+        query_as(
+            r#"SELECT friend.player_id, friend.friend_player_id, player.nickname, CASE WHEN friend.status = 'accepted' THEN 'accepted' ELSE 'outgoing' END AS status, friend.created_at FROM friend JOIN player ON player.id = friend.friend_player_id WHERE friend.player_id = ?
+            UNION ALL
+            SELECT friend.friend_player_id AS player_id, friend.player_id AS friend_player_id, player.nickname, 'incoming' AS status, friend.created_at FROM friend JOIN player ON player.id = friend.player_id WHERE friend.friend_player_id = ? AND friend.status = 'pending'
+            ORDER BY nickname"#,
+        )
+            .bind(player_id)
+            .bind(player_id)
+        .fetch_all(&self.db_pool)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    async fn friend_insert_request(
+        &self,
+        player_id: i64,
+        friend_player_id: i64,
+        created_at: i64,
+    ) -> Result<u64> {
+        let rows_affected = query(
+            r#"INSERT INTO friend ( player_id, friend_player_id, status, created_at ) VALUES ( ?, ?, 'pending', ? ) ON CONFLICT ( player_id, friend_player_id ) DO NOTHING"#,
+        )
+        .bind(player_id)
+        .bind(friend_player_id)
+        .bind(created_at)
+        .execute(&self.db_pool)
+        .await?
+        .rows_affected();
+        Ok(rows_affected)
+    }
+
+    async fn friend_accept(
+        &self,
+        player_id: i64,
+        friend_player_id: i64,
+        created_at: i64,
+    ) -> Result<u64> {
+        let mut transaction = self.db_pool.begin().await?;
+
+        let rows_affected = query(
+            r#"UPDATE friend SET status = 'accepted' WHERE player_id = ? AND friend_player_id = ? AND status = 'pending'"#,
+        )
+        .bind(friend_player_id)
+        .bind(player_id)
+        .execute(&mut *transaction)
+        .await?
+        .rows_affected();
+        if rows_affected == 0 {
+            return Ok(0);
+        }
+
+        query(
+            r#"INSERT INTO friend ( player_id, friend_player_id, status, created_at ) VALUES ( ?, ?, 'accepted', ? ) ON CONFLICT ( player_id, friend_player_id ) DO UPDATE SET status = 'accepted'"#,
+        )
+        .bind(player_id)
+        .bind(friend_player_id)
+        .bind(created_at)
+        .execute(&mut *transaction)
+        .await?;
+
+        transaction.commit().await?;
+
+        Ok(rows_affected)
+    }
+
+    async fn friend_delete(&self, player_id: i64, friend_player_id: i64) -> Result<u64> {
+        let rows_affected = query(
+            r#"DELETE FROM friend WHERE ( player_id = ? AND friend_player_id = ? ) OR ( player_id = ? AND friend_player_id = ? )"#,
+        )
+        .bind(player_id)
+        .bind(friend_player_id)
+        .bind(friend_player_id)
+        .bind(player_id)
+        .execute(&self.db_pool)
+        .await?
+        .rows_affected();
+        Ok(rows_affected)
+    }
+}
@@ -0,0 +1,181 @@
+use super::*;
+
+#[derive(Debug)]
+pub struct TimedStorage {
+    pub storage: Arc<dyn Storage>,
+}
+
+fn start_timer(query: &str) -> prometheus::HistogramTimer {
+    crate::metrics::DB_QUERY_DURATION
+        .with_label_values(&[query])
+        .start_timer()
+}
+
+#[async_trait]
+impl Storage for TimedStorage {
+    async fn migrate_run(&self) -> Result<()> {
+        self.storage.migrate_run().await
+    }
+
+    async fn migrate_status(&self) -> Result<Vec<MigrationStatus>> {
+        self.storage.migrate_status().await
+    }
+
+    async fn migrate_undo(&self, target_version: i64) -> Result<()> {
+        self.storage.migrate_undo(target_version).await
+    }
+
+    async fn ping(&self) -> Result<()> {
+        let _timer = start_timer("ping");
+        self.storage.ping().await
+    }
+
+    async fn auth_get_one_by_username(&self, username: &str) -> Result<Auth> {
+        let _timer = start_timer("auth_get_one_by_username");
+        self.storage.auth_get_one_by_username(username).await
+    }
+
+    async fn auth_get_one_by_player_id(&self, player_id: i64) -> Result<Auth> {
+        let _timer = start_timer("auth_get_one_by_player_id");
+        self.storage.auth_get_one_by_player_id(player_id).await
+    }
+
+    async fn player_get_one_by_auth_id(&self, auth_id: i64) -> Result<Player> {
+        let _timer = start_timer("player_get_one_by_auth_id");
+        self.storage.player_get_one_by_auth_id(auth_id).await
+    }
+
+    async fn player_get_one_by_nickname(&self, nickname: &str) -> Result<Player> {
+        let _timer = start_timer("player_get_one_by_nickname");
+        self.storage.player_get_one_by_nickname(nickname).await
+    }
+
+    async fn player_get_list(&self, limit: i64) -> Result<Vec<Player>> {
+        let _timer = start_timer("player_get_list");
+        self.storage.player_get_list(limit).await
+    }
+
+    async fn player_update_best_score_batch(&self, best_score_list: &[(i64, i64)]) -> Result<()> {
+        let _timer = start_timer("player_update_best_score_batch");
+        self.storage
+            .player_update_best_score_batch(best_score_list)
+            .await
+    }
+
+    async fn auth_player_insert(&self, username: &str, password: &str, color: i64) -> Result<i64> {
+        let _timer = start_timer("auth_player_insert");
+        self.storage
+            .auth_player_insert(username, password, color)
+            .await
+    }
+
+    async fn mute_get_list(&self, now: i64) -> Result<Vec<Mute>> {
+        let _timer = start_timer("mute_get_list");
+        self.storage.mute_get_list(now).await
+    }
+
+    async fn mute_upsert(&self, mute: &Mute) -> Result<()> {
+        let _timer = start_timer("mute_upsert");
+        self.storage.mute_upsert(mute).await
+    }
+
+    async fn mute_delete(&self, player_id: i64) -> Result<u64> {
+        let _timer = start_timer("mute_delete");
+        self.storage.mute_delete(player_id).await
+    }
+
+    async fn chat_log_insert_batch(&self, chat_log_list: &[ChatLog]) -> Result<()> {
+        let _timer = start_timer("chat_log_insert_batch");
+        self.storage.chat_log_insert_batch(chat_log_list).await
+    }
+
+    async fn chat_log_get_list(&self, player_id: Option<i64>, limit: i64) -> Result<Vec<ChatLog>> {
+        let _timer = start_timer("chat_log_get_list");
+        self.storage.chat_log_get_list(player_id, limit).await
+    }
+
+    async fn auth_update_role(&self, username: &str, role: Role) -> Result<u64> {
+        let _timer = start_timer("auth_update_role");
+        self.storage.auth_update_role(username, role).await
+    }
+
+    async fn ban_get_list(&self, now: i64) -> Result<Vec<Ban>> {
+        let _timer = start_timer("ban_get_list");
+        self.storage.ban_get_list(now).await
+    }
+
+    async fn ban_insert(&self, ban: &Ban) -> Result<()> {
+        let _timer = start_timer("ban_insert");
+        self.storage.ban_insert(ban).await
+    }
+
+    async fn ban_delete(&self, id: &str) -> Result<u64> {
+        let _timer = start_timer("ban_delete");
+        self.storage.ban_delete(id).await
+    }
+
+    async fn anti_cheat_log_insert_batch(
+        &self,
+        anti_cheat_log_list: &[AntiCheatLog],
+    ) -> Result<()> {
+        let _timer = start_timer("anti_cheat_log_insert_batch");
+        self.storage
+            .anti_cheat_log_insert_batch(anti_cheat_log_list)
+            .await
+    }
+
+    async fn anti_cheat_log_get_list(
+        &self,
+        player_id: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<AntiCheatLog>> {
+        let _timer = start_timer("anti_cheat_log_get_list");
+        self.storage.anti_cheat_log_get_list(player_id, limit).await
+    }
+
+    async fn round_result_insert(&self, round_result: &RoundResult) -> Result<()> {
+        let _timer = start_timer("round_result_insert");
+        self.storage.round_result_insert(round_result).await
+    }
+
+    async fn round_result_get_list(&self, limit: i64) -> Result<Vec<RoundResult>> {
+        let _timer = start_timer("round_result_get_list");
+        self.storage.round_result_get_list(limit).await
+    }
+
+    async fn friend_get_list(&self, player_id: i64) -> Result<Vec<Friend>> {
+        let _timer = start_timer("friend_get_list");
+        self.storage.friend_get_list(player_id).await
+    }
+
+    async fn friend_insert_request(
+        &self,
+        player_id: i64,
+        friend_player_id: i64,
+        created_at: i64,
+    ) -> Result<u64> {
+        let _timer = start_timer("friend_insert_request");
+        self.storage
+            .friend_insert_request(player_id, friend_player_id, created_at)
+            .await
+    }
+
+    async fn friend_accept(
+        &self,
+        player_id: i64,
+        friend_player_id: i64,
+        created_at: i64,
+    ) -> Result<u64> {
+        let _timer = start_timer("friend_accept");
+        self.storage
+            .friend_accept(player_id, friend_player_id, created_at)
+            .await
+    }
+
+    async fn friend_delete(&self, player_id: i64, friend_player_id: i64) -> Result<u64> {
+        let _timer = start_timer("friend_delete");
+        self.storage
+            .friend_delete(player_id, friend_player_id)
+            .await
+    }
+}
@@ -0,0 +1,220 @@
+use crate::*;
+use hashbrown::HashMap;
+use std::time::Duration;
+use tokio::{
+    select,
+    sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel},
+    time::{Instant, interval},
+};
+use tracing::{error, info, warn};
+
+const MAX_PENDING_CHAT_LOG: usize = 10000;
+const MAX_PENDING_ANTI_CHEAT_LOG: usize = 10000;
+
+#[derive(Debug)]
+pub struct DbWriter {
+    pub db: db::Db,
+    pub flush_duration: Duration,
+    pub best_score_map: HashMap<i64, i64>,
+    pub chat_log_list: Vec<db::ChatLog>,
+    pub anti_cheat_log_list: Vec<db::AntiCheatLog>,
+    pub command_sender: UnboundedSender<command::Command>,
+    pub command_receiver: UnboundedReceiver<command::Command>,
+}
+
+impl DbWriter {
+    pub fn new(db: db::Db, flush_duration: Duration) -> Self {
+        let (command_sender, command_receiver) = unbounded_channel::<command::Command>();
+        Self {
+            db,
+            flush_duration,
+            best_score_map: HashMap::new(),
+            chat_log_list: Vec::new(),
+            anti_cheat_log_list: Vec::new(),
+            command_sender,
+            command_receiver,
+        }
+    }
+
+    pub async fn run(mut self) {
+        let mut flush_interval = interval(self.flush_duration);
+
+        loop {
+            select! {
+                _ = flush_interval.tick() => {
+                    self.flush_best_score(None).await;
+                    self.flush_chat_log().await;
+                    self.flush_anti_cheat_log().await;
+                }
+                Some(command) = self.command_receiver.recv() => {
+                    self.handle_command(command).await;
+                }
+            }
+        }
+    }
+
+    async fn handle_command(&mut self, command: command::Command) {
+        match command {
+            command::Command::PersistPlayerBestScore {
+                player_db_id,
+                best_score,
+            } => {
+                metrics::BEST_SCORE_UPDATE.inc();
+                self.merge_best_score(player_db_id, best_score);
+            }
+            command::Command::FlushPlayerBestScore { player_db_id } => {
+                self.flush_best_score(Some(player_db_id)).await;
+            }
+            command::Command::PersistChatLog { chat_log } => {
+                if self.chat_log_list.len() < MAX_PENDING_CHAT_LOG {
+                    self.chat_log_list.push(chat_log);
+                } else {
+                    warn!("chat_log_list full, drop chat_log: {:?}", chat_log);
+                }
+            }
+            command::Command::PersistAntiCheatLog { anti_cheat_log } => {
+                if self.anti_cheat_log_list.len() < MAX_PENDING_ANTI_CHEAT_LOG {
+                    self.anti_cheat_log_list.push(anti_cheat_log);
+                } else {
+                    warn!(
+                        "anti_cheat_log_list full, drop anti_cheat_log: {:?}",
+                        anti_cheat_log
+                    );
+                }
+            }
+            command::Command::PersistMute { mute } => {
+                if let Err(e) = self.db.mute_upsert(&mute).await {
+                    error!("mute_upsert error: {:?} {:?}", mute, e);
+                }
+            }
+            command::Command::DeleteMute { player_db_id } => {
+                if let Err(e) = self.db.mute_delete(player_db_id).await {
+                    error!("mute_delete error: {:?} {:?}", player_db_id, e);
+                }
+            }
+            command::Command::PersistBan { ban } => {
+                if let Err(e) = self.db.ban_insert(&ban).await {
+                    error!("ban_insert error: {:?} {:?}", ban, e);
+                }
+            }
+            command::Command::PersistRoundResult { round_result } => {
+                if let Err(e) = self.db.round_result_insert(&round_result).await {
+                    error!("round_result_insert error: {:?} {:?}", round_result, e);
+                }
+            }
+            command::Command::DeleteBan { ban_id } => {
+                if let Err(e) = self.db.ban_delete(&ban_id).await {
+                    error!("ban_delete error: {:?} {:?}", ban_id, e);
+                }
+            }
+            command::Command::FlushAll { response_sender } => {
+                self.flush_best_score(None).await;
+                self.flush_chat_log().await;
+                self.flush_anti_cheat_log().await;
+                let _ = response_sender.send(());
+            }
+            _ => {
+                warn!("unknown command: {:?}", command);
+            }
+        }
+    }
+
+    async fn flush_chat_log(&mut self) {
+        if self.chat_log_list.is_empty() {
+            return;
+        }
+
+        let start = Instant::now();
+        let result = self.db.chat_log_insert_batch(&self.chat_log_list).await;
+        let latency = start.elapsed();
+
+        match result {
+            Ok(_) => {
+                info!(
+                    "flush chat_log: {} rows in {:?}",
+                    self.chat_log_list.len(),
+                    latency
+                );
+                self.chat_log_list.clear();
+            }
+            Err(e) => {
+                error!("flush chat_log error: {:?}", e);
+            }
+        }
+    }
+
+    async fn flush_anti_cheat_log(&mut self) {
+        if self.anti_cheat_log_list.is_empty() {
+            return;
+        }
+
+        let start = Instant::now();
+        let result = self
+            .db
+            .anti_cheat_log_insert_batch(&self.anti_cheat_log_list)
+            .await;
+        let latency = start.elapsed();
+
+        match result {
+            Ok(_) => {
+                info!(
+                    "flush anti_cheat_log: {} rows in {:?}",
+                    self.anti_cheat_log_list.len(),
+                    latency
+                );
+                self.anti_cheat_log_list.clear();
+            }
+            Err(e) => {
+                error!("flush anti_cheat_log error: {:?}", e);
+            }
+        }
+    }
+
+    fn merge_best_score(&mut self, player_db_id: i64, best_score: i64) {
+        let entry = self
+            .best_score_map
+            .entry(player_db_id)
+            .or_insert(best_score);
+        *entry = (*entry).max(best_score);
+    }
+
+    async fn flush_best_score(&mut self, player_db_id: Option<i64>) {
+        let best_score_list = match player_db_id {
+            Some(player_db_id) => match self.best_score_map.remove(&player_db_id) {
+                Some(best_score) => vec![(player_db_id, best_score)],
+                None => return,
+            },
+            None => self.best_score_map.drain().collect::<Vec<_>>(),
+        };
+        if best_score_list.is_empty() {
+            return;
+        }
+
+        let start = Instant::now();
+        let result = self
+            .db
+            .player_update_best_score_batch(&best_score_list)
+            .await;
+        let latency = start.elapsed();
+
+        metrics::BEST_SCORE_FLUSH_DURATION.observe(latency.as_secs_f64());
+
+        match result {
+            Ok(_) => {
+                metrics::BEST_SCORE_WRITE.inc_by(best_score_list.len() as u64);
+                info!(
+                    "flush best_score: {} rows in {:?}",
+                    best_score_list.len(),
+                    latency
+                );
+            }
+            Err(e) => {
+                metrics::BEST_SCORE_FLUSH_ERROR.inc();
+                error!("flush best_score error: {:?}", e);
+                for (player_db_id, best_score) in best_score_list {
+                    self.merge_best_score(player_db_id, best_score);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,252 @@
+use crate::*;
+use tracing::warn;
+
+pub const MAX_FRIEND_COUNT: usize = 100;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FriendAction {
+    Request { nickname: Arc<str> },
+    Accept { nickname: Arc<str> },
+    Remove { nickname: Arc<str> },
+}
+
+#[derive(Debug, Clone)]
+pub struct Presence {
+    pub status: proto::PresenceStatus,
+    pub room_id: Arc<str>,
+}
+
+impl Presence {
+    pub fn offline() -> Self {
+        Self {
+            status: proto::PresenceStatus::Offline,
+            room_id: "".into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FriendOutcome {
+    pub msg: Arc<str>,
+    pub notify: Option<(i64, Arc<str>)>,
+}
+
+pub fn friend_status(status: &str) -> proto::FriendStatus {
+    match status {
+        "incoming" => proto::FriendStatus::Incoming,
+        "outgoing" => proto::FriendStatus::Outgoing,
+        _ => proto::FriendStatus::Accepted,
+    }
+}
+
+pub fn presence_name(presence: &proto::Friend) -> String {
+    match presence.presence() {
+        proto::PresenceStatus::Offline => "offline".to_string(),
+        proto::PresenceStatus::Lobby => "in lobby".to_string(),
+        proto::PresenceStatus::InGame => format!("in game ({})", presence.room_id),
+        proto::PresenceStatus::Spectating => format!("spectating ({})", presence.room_id),
+    }
+}
+
+pub fn format_friend_list(friend_list: &[proto::Friend]) -> Arc<str> {
+    if friend_list.is_empty() {
+        return "no friends yet, try /friend add <nickname>".into();
+    }
+    friend_list
+        .iter()
+        .map(|friend| match friend.status() {
+            proto::FriendStatus::Accepted => {
+                format!("{}: {}", friend.nickname, presence_name(friend))
+            }
+            proto::FriendStatus::Incoming => {
+                format!("{}: wants to be your friend", friend.nickname)
+            }
+            proto::FriendStatus::Outgoing => format!("{}: request sent", friend.nickname),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+        .into()
+}
+
+pub async fn apply(
+    db: &db::Db,
+    db_player: &db::Player,
+    friend_action: FriendAction,
+) -> Result<FriendOutcome, Arc<str>> {
+    let nickname = match &friend_action {
+        FriendAction::Request { nickname }
+        | FriendAction::Accept { nickname }
+        | FriendAction::Remove { nickname } => nickname.clone(),
+    };
+    let target = match db.player_get_one_by_nickname(&nickname).await {
+        Ok(target) => target,
+        Err(e) => {
+            warn!("friend target query error: {:?} {:?}", nickname, e);
+            return Err(format!("player not found: {}", nickname).into());
+        }
+    };
+    if target.id == db_player.id {
+        return Err("you can not befriend yourself".into());
+    }
+
+    let friend_list = db.friend_get_list(db_player.id).await.map_err(db_error)?;
+    let existing = friend_list
+        .iter()
+        .find(|friend| friend.friend_player_id == target.id)
+        .map(|friend| friend_status(&friend.status));
+    let now = util::now_millis();
+
+    match (friend_action, existing) {
+        (FriendAction::Request { .. }, Some(proto::FriendStatus::Accepted)) => {
+            Err(format!("{} is already your friend", target.nickname).into())
+        }
+        (FriendAction::Request { .. }, Some(proto::FriendStatus::Outgoing)) => {
+            Err(format!("friend request to {} already sent", target.nickname).into())
+        }
+        (FriendAction::Request { .. }, None) if friend_list.len() >= MAX_FRIEND_COUNT => {
+            Err(format!("friend list is full ({})", MAX_FRIEND_COUNT).into())
+        }
+        (FriendAction::Request { .. }, None) => {
+            db.friend_insert_request(db_player.id, target.id, now)
+                .await
+                .map_err(db_error)?;
+            Ok(FriendOutcome {
+                msg: format!("friend request sent to {}", target.nickname).into(),
+                notify: Some((
+                    target.id,
+                    format!(
+                        "{} wants to be your friend, /friend accept {}",
+                        db_player.nickname, db_player.nickname
+                    )
+                    .into(),
+                )),
+            })
+        }
+        (
+            FriendAction::Request { .. } | FriendAction::Accept { .. },
+            Some(proto::FriendStatus::Incoming),
+        ) => {
+            if accepted_count(&friend_list) >= MAX_FRIEND_COUNT {
+                return Err(format!("friend list is full ({})", MAX_FRIEND_COUNT).into());
+            }
+            let target_friend_list = db.friend_get_list(target.id).await.map_err(db_error)?;
+            if accepted_count(&target_friend_list) >= MAX_FRIEND_COUNT {
+                return Err(format!("{} has a full friend list", target.nickname).into());
+            }
+            db.friend_accept(db_player.id, target.id, now)
+                .await
+                .map_err(db_error)?;
+            Ok(FriendOutcome {
+                msg: format!("you are now friends with {}", target.nickname).into(),
+                notify: Some((
+                    target.id,
+                    format!("{} accepted your friend request", db_player.nickname).into(),
+                )),
+            })
+        }
+        (FriendAction::Accept { .. }, _) => {
+            Err(format!("no friend request from {}", target.nickname).into())
+        }
+        (FriendAction::Remove { .. }, Some(_)) => {
+            db.friend_delete(db_player.id, target.id)
+                .await
+                .map_err(db_error)?;
+            Ok(FriendOutcome {
+                msg: format!("removed {} from your friends", target.nickname).into(),
+                notify: None,
+            })
+        }
+        (FriendAction::Remove { .. }, None) => {
+            Err(format!("{} is not in your friend list", target.nickname).into())
+        }
+    }
+}
+
+fn accepted_count(friend_list: &[db::Friend]) -> usize {
+    friend_list
+        .iter()
+        .filter(|friend| friend_status(&friend.status) == proto::FriendStatus::Accepted)
+        .count()
+}
+
+fn db_error(e: anyhow::Error) -> Arc<str> {
+    warn!("friend query error: {:?}", e);
+    "friend list unavailable, try again later".into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn player(db: &db::Db, nickname: &str) -> db::Player {
+        let auth_id = db.auth_player_insert(nickname, "hash", 0).await.unwrap();
+        db.player_get_one_by_auth_id(auth_id).await.unwrap()
+    }
+
+    async fn fill_friend_list(db: &db::Db, db_player: &db::Player) {
+        for i in 0..MAX_FRIEND_COUNT {
+            let friend = player(db, &format!("{}-{}", db_player.nickname, i)).await;
+            db.friend_insert_request(db_player.id, friend.id, 0)
+                .await
+                .unwrap();
+            db.friend_accept(friend.id, db_player.id, 0).await.unwrap();
+        }
+    }
+
+    fn accept(nickname: &str) -> FriendAction {
+        FriendAction::Accept {
+            nickname: nickname.into(),
+        }
+    }
+
+    fn request(nickname: &str) -> FriendAction {
+        FriendAction::Request {
+            nickname: nickname.into(),
+        }
+    }
+
+    #[tokio::test]
+    async fn accept_checks_acceptor_friend_count() {
+        let db = db::Db::connect("memory:").await.unwrap();
+        let alice = player(&db, "alice").await;
+        let bob = player(&db, "bob").await;
+        db.friend_insert_request(bob.id, alice.id, 0).await.unwrap();
+        fill_friend_list(&db, &alice).await;
+
+        let full: Arc<str> = format!("friend list is full ({})", MAX_FRIEND_COUNT).into();
+        assert_eq!(apply(&db, &alice, accept("bob")).await.unwrap_err(), full);
+        assert_eq!(apply(&db, &alice, request("bob")).await.unwrap_err(), full);
+    }
+
+    #[tokio::test]
+    async fn accept_checks_requester_friend_count() {
+        let db = db::Db::connect("memory:").await.unwrap();
+        let alice = player(&db, "alice").await;
+        let bob = player(&db, "bob").await;
+        db.friend_insert_request(bob.id, alice.id, 0).await.unwrap();
+        fill_friend_list(&db, &bob).await;
+
+        assert_eq!(
+            &*apply(&db, &alice, accept("bob")).await.unwrap_err(),
+            "bob has a full friend list"
+        );
+    }
+
+    #[tokio::test]
+    async fn request_then_accept() {
+        let db = db::Db::connect("memory:").await.unwrap();
+        let alice = player(&db, "alice").await;
+        let bob = player(&db, "bob").await;
+
+        let outcome = apply(&db, &bob, request("alice")).await.unwrap();
+        assert_eq!(&*outcome.msg, "friend request sent to alice");
+        assert_eq!(outcome.notify.map(|(id, _)| id), Some(alice.id));
+
+        let outcome = apply(&db, &alice, request("bob")).await.unwrap();
+        assert_eq!(&*outcome.msg, "you are now friends with bob");
+        assert_eq!(
+            &*apply(&db, &bob, request("alice")).await.unwrap_err(),
+            "alice is already your friend"
+        );
+    }
+}
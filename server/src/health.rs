@@ -0,0 +1,157 @@
+use crate::*;
+use anyhow::Result;
+use axum::{Json, Router, extract::State, http::StatusCode, routing::get};
+use serde::Serialize;
+use std::{
+    sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+    time::Duration,
+};
+use tokio::time::Instant;
+use tracing::info;
+
+const STALE_TICK_COUNT: u32 = 20;
+const MIN_STALE_DURATION: Duration = Duration::from_secs(1);
+
+#[derive(Debug)]
+pub struct Heartbeat {
+    pub started: Instant,
+    pub last_tick_millis: AtomicU64,
+    pub last_tick_duration_micros: AtomicU64,
+    pub tick_budget_micros: AtomicU64,
+    pub over_budget_tick_count: AtomicU32,
+    pub accepting: AtomicBool,
+}
+
+impl Heartbeat {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            last_tick_millis: AtomicU64::new(0),
+            last_tick_duration_micros: AtomicU64::new(0),
+            tick_budget_micros: AtomicU64::new(0),
+            over_budget_tick_count: AtomicU32::new(0),
+            accepting: AtomicBool::new(false),
+        }
+    }
+
+    pub fn beat(&self, tick_duration: Duration, tick_budget: Duration) {
+        self.last_tick_millis
+            .store(self.started.elapsed().as_millis() as u64, Ordering::Relaxed);
+        self.last_tick_duration_micros
+            .store(tick_duration.as_micros() as u64, Ordering::Relaxed);
+        self.tick_budget_micros
+            .store(tick_budget.as_micros() as u64, Ordering::Relaxed);
+        if tick_duration > tick_budget {
+            self.over_budget_tick_count.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.over_budget_tick_count.store(0, Ordering::Relaxed);
+        }
+    }
+
+    pub fn set_accepting(&self, accepting: bool) {
+        self.accepting.store(accepting, Ordering::Relaxed);
+    }
+
+    fn liveness(&self) -> Liveness {
+        let last_tick_millis = self.last_tick_millis.load(Ordering::Relaxed);
+        let last_tick_age = self.started.elapsed() - Duration::from_millis(last_tick_millis);
+        let last_tick_duration =
+            Duration::from_micros(self.last_tick_duration_micros.load(Ordering::Relaxed));
+        let tick_budget = Duration::from_micros(self.tick_budget_micros.load(Ordering::Relaxed));
+
+        let stale_duration = (tick_budget * STALE_TICK_COUNT).max(MIN_STALE_DURATION);
+        let ticking = last_tick_millis > 0 && last_tick_age <= stale_duration;
+        let over_budget_tick_count = self.over_budget_tick_count.load(Ordering::Relaxed);
+        let stuck_over_budget = over_budget_tick_count >= STALE_TICK_COUNT;
+
+        Liveness {
+            alive: ticking && !stuck_over_budget,
+            last_tick_age_ms: last_tick_age.as_millis() as u64,
+            last_tick_duration_us: last_tick_duration.as_micros() as u64,
+            tick_budget_us: tick_budget.as_micros() as u64,
+            over_budget: last_tick_duration > tick_budget,
+            over_budget_tick_count,
+        }
+    }
+}
+
+impl Default for Heartbeat {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Liveness {
+    pub alive: bool,
+    pub last_tick_age_ms: u64,
+    pub last_tick_duration_us: u64,
+    pub tick_budget_us: u64,
+    pub over_budget: bool,
+    pub over_budget_tick_count: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Readiness {
+    pub ready: bool,
+    pub hub_alive: bool,
+    pub db_reachable: bool,
+    pub accepting: bool,
+}
+
+#[derive(Debug, Clone)]
+struct HealthState {
+    heartbeat: Arc<Heartbeat>,
+    db: db::Db,
+}
+
+pub fn router(heartbeat: Arc<Heartbeat>, db: db::Db) -> Router {
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .with_state(HealthState { heartbeat, db })
+}
+
+pub async fn run(bind_addr: &str, router: Router) -> Result<()> {
+    let tcp_listener = tokio::net::TcpListener::bind(bind_addr).await?;
+    info!("health listening on {:?}", bind_addr);
+
+    axum::serve(tcp_listener, router).await?;
+
+    Ok(())
+}
+
+async fn healthz(State(health_state): State<HealthState>) -> (StatusCode, Json<Liveness>) {
+    let liveness = health_state.heartbeat.liveness();
+    let status_code = if liveness.alive {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status_code, Json(liveness))
+}
+
+async fn readyz(State(health_state): State<HealthState>) -> (StatusCode, Json<Readiness>) {
+    let hub_alive = health_state.heartbeat.liveness().alive;
+    let accepting = health_state.heartbeat.accepting.load(Ordering::Relaxed);
+    let db_reachable = match health_state.db.ping().await {
+        Ok(_) => true,
+        Err(e) => {
+            tracing::warn!("readyz db ping error: {:?}", e);
+            false
+        }
+    };
+
+    let readiness = Readiness {
+        ready: hub_alive && accepting && db_reachable,
+        hub_alive,
+        db_reachable,
+        accepting,
+    };
+    let status_code = if readiness.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status_code, Json(readiness))
+}
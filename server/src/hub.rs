@@ -1,19 +1,30 @@
 use crate::*;
+use anyhow::{Result, anyhow, bail};
 use bytes::Bytes;
-use hashbrown::HashMap;
+use hashbrown::HashSet;
+use indexmap::IndexMap;
 use nanoid::nanoid;
 use prost::Message;
-use std::time::Duration;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::time::{Duration, SystemTime};
 use tokio::{
     select,
     sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel},
-    time::{Instant, interval},
+    time::{Instant, Interval, MissedTickBehavior, interval},
 };
 use tracing::{error, info, warn};
 
-const TICK_DURATION: Duration = Duration::from_millis(50);
-const SPAWN_SPORE_DURATION: Duration = Duration::from_millis(2000);
-const MAX_SPORE_COUNT: usize = 1000;
+const BALANCE_BOT_DURATION: Duration = Duration::from_millis(1000);
+const WATCH_CONFIG_DURATION: Duration = Duration::from_millis(2000);
+const LEADERBOARD_SIZE: usize = 10;
+
+#[derive(Debug, Clone)]
+pub struct Account {
+    pub player_db_id: i64,
+    pub nickname: Arc<str>,
+    pub color: i64,
+}
 
 #[derive(Debug)]
 pub struct Client {
@@ -21,58 +32,272 @@ pub struct Client {
     pub connection_id: Arc<str>,
     pub client_agent_command_sender: UnboundedSender<command::Command>,
     pub player: Option<player::Player>,
+    pub spectator: Option<spectator::Spectator>,
+    pub account: Option<Account>,
+    pub role: role::Role,
+    pub room_id: Arc<str>,
+}
+
+impl Client {
+    fn detached(connection_id: Arc<str>, player: Option<player::Player>) -> Self {
+        let (client_agent_command_sender, _) = unbounded_channel::<command::Command>();
+        Self {
+            socket_addr: SocketAddr::from(([0, 0, 0, 0], 0)),
+            connection_id,
+            client_agent_command_sender,
+            player,
+            spectator: None,
+            account: None,
+            role: role::Role::default(),
+            room_id: room::MAIN_ROOM_ID.into(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct TickTiming {
+    pub lateness: Duration,
+    pub simulation: Duration,
+    pub collision: Duration,
+    pub serialization: Duration,
+    pub total: Duration,
+    pub command_count: usize,
+}
+
+impl TickTiming {
+    fn observe(&self, budget: Duration) {
+        metrics::TICK_LATENESS.observe(self.lateness.as_secs_f64());
+        metrics::TICK_PHASE_DURATION
+            .with_label_values(&["simulation"])
+            .observe(self.simulation.as_secs_f64());
+        metrics::TICK_PHASE_DURATION
+            .with_label_values(&["collision"])
+            .observe(self.collision.as_secs_f64());
+        metrics::TICK_PHASE_DURATION
+            .with_label_values(&["serialization"])
+            .observe(self.serialization.as_secs_f64());
+        metrics::TICK_DURATION.observe(self.total.as_secs_f64());
+        metrics::HUB_COMMAND_PER_TICK.observe(self.command_count as f64);
+
+        if self.total > budget || self.lateness > budget {
+            metrics::TICK_OVERRUN.inc();
+            warn!(
+                "tick over budget {:?}: total {:?}, lateness {:?}, simulation {:?}, collision {:?}, serialization {:?}, commands since last tick {}",
+                budget,
+                self.total,
+                self.lateness,
+                self.simulation,
+                self.collision,
+                self.serialization,
+                self.command_count
+            );
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct Hub {
-    pub client_map: HashMap<Arc<str>, Client>,
-    pub spore_map: HashMap<Arc<str>, spore::Spore>,
+    pub client_map: IndexMap<Arc<str>, Client>,
+    pub spore_map: IndexMap<Arc<str>, spore::Spore>,
+    pub room_map: IndexMap<Arc<str>, room::Room>,
+    pub round_map: IndexMap<Arc<str>, round::Round>,
     pub command_sender: UnboundedSender<command::Command>,
     pub command_receiver: UnboundedReceiver<command::Command>,
     pub db: db::Db,
+    pub db_writer_command_sender: UnboundedSender<command::Command>,
+    pub server_config: config::ServerConfig,
+    pub game_config: config::GameConfig,
+    pub config_modified: Option<SystemTime>,
+    pub bot_count: usize,
+    pub ban_list: ban::BanList,
+    pub chat_moderator: chat::ChatModerator,
+    pub anti_cheat: anti_cheat::AntiCheat,
+    pub party_book: party::PartyBook,
+    pub heartbeat: Arc<health::Heartbeat>,
+    pub rng_seed: u64,
+    pub rng: ChaCha8Rng,
+    pub step_count: u64,
+    pub spore_count: u64,
+    pub recorder: Option<replay::Recorder>,
 }
 
 impl Hub {
-    pub fn new(db: db::Db) -> Self {
+    pub fn new(
+        db: db::Db,
+        config: config::Config,
+        db_writer_command_sender: UnboundedSender<command::Command>,
+    ) -> Self {
         let (command_sender, command_receiver) = unbounded_channel::<command::Command>();
+        let rng_seed = match config.server.rng_seed {
+            0 => rand::random(),
+            rng_seed => rng_seed,
+        };
+        info!("rng_seed: {}", rng_seed);
         Self {
-            client_map: HashMap::new(),
-            spore_map: HashMap::new(),
+            client_map: IndexMap::new(),
+            spore_map: IndexMap::new(),
+            room_map: IndexMap::new(),
+            round_map: IndexMap::new(),
             command_sender,
             command_receiver,
             db,
+            db_writer_command_sender,
+            server_config: config.server,
+            game_config: config.game,
+            config_modified: config_modified(),
+            bot_count: 0,
+            ban_list: ban::BanList::default(),
+            chat_moderator: chat::ChatModerator::default(),
+            anti_cheat: anti_cheat::AntiCheat::default(),
+            party_book: party::PartyBook::default(),
+            heartbeat: Arc::new(health::Heartbeat::new()),
+            rng_seed,
+            rng: ChaCha8Rng::seed_from_u64(rng_seed),
+            step_count: 0,
+            spore_count: 0,
+            recorder: None,
         }
     }
 
     pub async fn run(mut self) {
-        for _ in 0..MAX_SPORE_COUNT {
-            self.spawn_spore();
+        match self.db.mute_get_list(util::now_millis()).await {
+            Ok(mute_list) => {
+                info!("mute_list: {}", mute_list.len());
+                self.chat_moderator.mute_map = mute_list
+                    .into_iter()
+                    .map(|mute| (mute.player_id, mute))
+                    .collect();
+            }
+            Err(e) => error!("mute_get_list error: {:?}", e),
+        }
+
+        match self.db.ban_get_list(util::now_millis()).await {
+            Ok(ban_list) => {
+                info!("ban_list: {}", ban_list.len());
+                ban_list
+                    .into_iter()
+                    .for_each(|ban| self.ban_list.insert(ban));
+            }
+            Err(e) => error!("ban_get_list error: {:?}", e),
+        }
+
+        for _ in 0..self.game_config.max_spore_count {
+            self.spawn_spore(room::MAIN_ROOM_ID.into());
         }
 
-        let mut tick_interval = interval(TICK_DURATION);
-        let mut last_tick = Instant::now();
+        if self.server_config.replay_record
+            && let Err(e) = self.start_replay()
+        {
+            error!("start replay error: {:?}", e);
+        }
+
+        let mut tick_interval = self.tick_interval();
+        let mut command_count = 0;
+
+        let mut spawn_spore_interval = interval(self.game_config.spawn_spore_duration());
+
+        let mut balance_bot_interval = interval(BALANCE_BOT_DURATION);
 
-        let mut spawn_spore_interval = interval(SPAWN_SPORE_DURATION);
+        let mut watch_config_interval = interval(WATCH_CONFIG_DURATION);
 
         loop {
+            if tick_interval.period() != self.game_config.tick_duration()
+                || tick_interval.missed_tick_behavior()
+                    != MissedTickBehavior::from(self.game_config.missed_tick)
+            {
+                tick_interval = self.tick_interval();
+            }
+            if spawn_spore_interval.period() != self.game_config.spawn_spore_duration() {
+                spawn_spore_interval = interval(self.game_config.spawn_spore_duration());
+            }
+
             select! {
-                _ = tick_interval.tick() => {
-                    let delta = last_tick.elapsed();
-                    self.tick_player(delta);
-                    last_tick = Instant::now();
+                biased;
+
+                scheduled = tick_interval.tick() => {
+                    let lateness = Instant::now().saturating_duration_since(scheduled);
+                    let timing = self.tick(lateness, command_count);
+                    command_count = 0;
+                    self.record_metrics();
+                    timing.observe(self.game_config.tick_duration());
+                    self.heartbeat.beat(timing.total, self.game_config.tick_duration());
                 }
                 _ = spawn_spore_interval.tick() => {
-                    if self.spore_map.len() < MAX_SPORE_COUNT {
-                        self.spawn_spore();
+                    self.spawn_room_spore();
+                }
+                _ = balance_bot_interval.tick() => {
+                    self.record(proto::replay_input::Data::BalanceBot(proto::ReplayBalanceBot {}));
+                    self.balance_bot();
+                    self.close_idle_room();
+                }
+                _ = watch_config_interval.tick() => {
+                    let modified = config_modified();
+                    if modified != self.config_modified {
+                        self.config_modified = modified;
+                        let _ = self.reload_config();
                     }
                 }
-                Some(command) = self.command_receiver.recv() => {
+                Some(command) = self.command_receiver.recv(),
+                    if command_count < self.game_config.max_command_per_tick =>
+                {
+                    command_count += 1;
                     self.handle_command(command).await;
                 }
             }
         }
     }
 
+    fn tick_interval(&self) -> Interval {
+        let mut tick_interval = interval(self.game_config.tick_duration());
+        tick_interval.set_missed_tick_behavior(self.game_config.missed_tick.into());
+        tick_interval
+    }
+
+    pub fn tick(&mut self, lateness: Duration, command_count: usize) -> TickTiming {
+        let tick_start = Instant::now();
+
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.write_frame(self.step_count + 1);
+        }
+
+        self.step_count += 1;
+        let decision_list = self.think_bot();
+        let spore_packet_list = self.step_player();
+        let simulation_end = Instant::now();
+
+        self.collide_bot(decision_list);
+        self.sync_team();
+        self.sync_round();
+        let collision_end = Instant::now();
+
+        self.sync_player();
+        self.sync_spectator();
+        for (room_id, spore_packet) in spore_packet_list {
+            self.broadcast_room_packet(&room_id, &spore_packet);
+        }
+        let serialization_end = Instant::now();
+
+        if let Some(recorder) = self.recorder.as_ref()
+            && self
+                .step_count
+                .is_multiple_of(recorder.snapshot_interval_step)
+        {
+            let snapshot = self.replay_snapshot();
+            if let Some(recorder) = self.recorder.as_mut() {
+                recorder.write_snapshot(snapshot);
+            }
+        }
+
+        TickTiming {
+            lateness,
+            simulation: simulation_end - tick_start,
+            collision: collision_end - simulation_end,
+            serialization: serialization_end - collision_end,
+            total: serialization_end - tick_start,
+            command_count,
+        }
+    }
+
     async fn handle_command(&mut self, command: command::Command) {
         match command {
             command::Command::RegisterClientAgent {
@@ -85,11 +310,18 @@ impl Hub {
                 let connection_id: Arc<str> = nanoid!().into();
                 info!("connection_id: {:?}", connection_id);
 
+                self.record(proto::replay_input::Data::Register(proto::ReplayRegister {
+                    connection_id: connection_id.to_string(),
+                }));
                 let client = Client {
                     socket_addr,
                     connection_id: connection_id.clone(),
                     client_agent_command_sender,
                     player: None,
+                    spectator: None,
+                    account: None,
+                    role: role::Role::default(),
+                    room_id: room::MAIN_ROOM_ID.into(),
                 };
                 self.client_map.insert(connection_id.clone(), client);
 
@@ -98,10 +330,11 @@ impl Hub {
             command::Command::UnregisterClientAgent { connection_id } => {
                 info!("UnregisterClient: {:?}", connection_id);
 
-                self.client_map.remove(&connection_id);
-
-                let packet = proto_util::disconnect_packet(connection_id, "unregister".into());
-                self.broadcast_packet(&packet);
+                self.record(proto::replay_input::Data::Leave(proto::ReplayLeave {
+                    connection_id: connection_id.to_string(),
+                    reason: "unregister".to_string(),
+                }));
+                self.leave(connection_id, "unregister".into());
             }
             command::Command::Join {
                 connection_id,
@@ -113,51 +346,104 @@ impl Hub {
                     "PlayerJoin: {:?} {:?} {:?} {:#x?}",
                     connection_id, player_db_id, nickname, color
                 );
-
-                self.client_map.values().for_each(|client| {
-                    let player = match &client.player {
-                        Some(player) => player,
-                        None => return,
-                    };
-                    if player.db_id == player_db_id {
-                        let _ = client
-                            .client_agent_command_sender
-                            .send(command::Command::DisconnectClinet);
-                    }
-                });
-
-                let client = match self.client_map.get_mut(&connection_id) {
-                    Some(client) => client,
-                    None => {
-                        error!("client not found: {:?}", connection_id);
-                        return;
-                    }
+                let room_id: Arc<str> = room::MAIN_ROOM_ID.into();
+                if self.handle_join(
+                    connection_id.clone(),
+                    player_db_id,
+                    nickname,
+                    color,
+                    room_id.clone(),
+                    None,
+                ) {
+                    self.join_party(&connection_id, player_db_id, room_id);
+                }
+            }
+            command::Command::Login {
+                connection_id,
+                player_db_id,
+                nickname,
+                color,
+                role,
+                friend_player_db_id_list,
+            } => {
+                info!(
+                    "Login: {:?} {:?} {:?}",
+                    connection_id, player_db_id, nickname
+                );
+                let account = Account {
+                    player_db_id,
+                    nickname,
+                    color,
                 };
-
-                let player = player::Player::random(player_db_id, connection_id, nickname, color);
-
-                let player_x = player.x;
-                let player_y = player.y;
-
-                client.player = Some(player);
-
-                let mut spore_batch = self.spore_map.values().cloned().collect::<Vec<_>>();
-                spore_batch.sort_by_cached_key(|spore| {
-                    ((player_x - spore.x).powi(2) + (player_y - spore.y).powi(2)) as i64
-                });
-
-                let _ = client
-                    .client_agent_command_sender
-                    .send(command::Command::UpdateSporeBatch { spore_batch });
+                self.login(connection_id, account, role, friend_player_db_id_list);
             }
-            command::Command::Chat { connection_id, msg } => {
-                let packet = proto_util::chat_packet(connection_id, msg);
-                self.broadcast_packet(&packet);
+            command::Command::Presence {
+                player_db_id_list,
+                response_sender,
+            } => {
+                let presence_list = player_db_id_list
+                    .iter()
+                    .map(|player_db_id| self.presence(*player_db_id))
+                    .collect();
+                let _ = response_sender.send(presence_list);
+            }
+            command::Command::FindPlayer {
+                nickname,
+                response_sender,
+            } => {
+                let player_db_id = self
+                    .find_player_by_nickname(&nickname)
+                    .map(|target| target.db_id);
+                let _ = response_sender.send(player_db_id);
+            }
+            command::Command::NotifyPlayer { player_db_id, msg } => {
+                for connection_id in self.account_connection_id_list(player_db_id) {
+                    self.send_system_chat(&connection_id, msg.clone());
+                }
+            }
+            command::Command::Spectate {
+                connection_id,
+                mode,
+            } => {
+                info!("Spectate: {:?} {:?}", connection_id, mode);
+                self.spectate(connection_id, mode);
+            }
+            command::Command::Party {
+                connection_id,
+                party_action,
+            } => {
+                info!("Party: {:?} {:?}", connection_id, party_action);
+                self.party(connection_id, party_action, false);
+            }
+            command::Command::Room {
+                connection_id,
+                room_action,
+            } => {
+                info!("Room: {:?} {:?}", connection_id, room_action);
+                self.room(connection_id, room_action, false);
+            }
+            command::Command::Chat {
+                connection_id,
+                player_db_id,
+                nickname,
+                role,
+                chat_command,
+            } => {
+                self.chat(connection_id, player_db_id, nickname, role, chat_command);
             }
             command::Command::UpdatePlayerDirectionAngle {
                 connection_id,
                 direction_angle,
             } => {
+                if !self.check_direction_angle(&connection_id, direction_angle) {
+                    return;
+                }
+                self.record(proto::replay_input::Data::DirectionAngle(
+                    proto::ReplayDirectionAngle {
+                        connection_id: connection_id.to_string(),
+                        direction_angle,
+                    },
+                ));
                 if let Some(client) = self.client_map.get_mut(&connection_id)
                     && let Some(player) = client.player.as_mut()
                 {
@@ -168,184 +454,3011 @@ impl Hub {
                 connection_id,
                 spore_id,
             } => {
-                if let (Some(client), Some(spore)) = (
-                    self.client_map.get_mut(&connection_id),
-                    self.spore_map.get_mut(&spore_id),
-                ) && let Some(player) = client.player.as_mut()
-                {
-                    let is_close = util::check_distance_is_close(
-                        player.x,
-                        player.y,
-                        player.radius,
-                        spore.x,
-                        spore.y,
-                        spore.radius,
-                    );
-
-                    if !is_close {
-                        warn!("consume spore error, distance too far");
-                        return;
-                    }
-
-                    let spore_mass = util::radius_to_mass(spore.radius);
-                    player.increase_mass(spore_mass);
-
-                    self.spore_map.remove(&spore_id);
-
-                    let current_score = util::radius_to_mass(player.radius) as i64;
-
-                    let client_agent_command_sender = client.client_agent_command_sender.clone();
-
-                    self.broadcast_packet(&proto_util::consume_spore_packet(
-                        connection_id,
-                        spore_id,
-                    ));
-
-                    let _ = client_agent_command_sender
-                        .send(command::Command::SyncPlayerBestScore { current_score });
+                if !self.check_consume_spore(&connection_id, &spore_id) {
+                    return;
                 }
+                self.record(proto::replay_input::Data::ConsumeSpore(
+                    proto::ReplayConsumeSpore {
+                        connection_id: connection_id.to_string(),
+                        spore_id: spore_id.to_string(),
+                    },
+                ));
+                self.consume_spore(connection_id, spore_id);
             }
             command::Command::ConsumePlayer {
                 connection_id,
                 victim_connection_id,
             } => {
-                if let [Some(player_client), Some(victim_client)] = self
-                    .client_map
-                    .get_many_mut([&connection_id, &victim_connection_id])
-                    && let (Some(player), Some(victim)) =
-                        (&mut player_client.player, &mut victim_client.player)
-                {
-                    let player_mass = util::radius_to_mass(player.radius);
-                    let victim_mass = util::radius_to_mass(victim.radius);
-
-                    if player_mass < victim_mass * 1.2 {
-                        warn!("consume player error, too small");
-                        return;
-                    }
-
-                    let is_close = util::check_distance_is_close(
-                        player.x,
-                        player.y,
-                        player.radius,
-                        victim.x,
-                        victim.y,
-                        victim.radius,
+                if self.is_party_protected(&connection_id, &victim_connection_id) {
+                    warn!(
+                        "consume player error, friendly fire protection: {:?} {:?}",
+                        connection_id, victim_connection_id
                     );
-
-                    if !is_close {
-                        warn!("consume player error, distance too far");
-                        return;
-                    }
-
-                    player.increase_mass(victim_mass);
-
-                    victim.respawn();
+                    return;
+                }
+                if self.is_teammate(&connection_id, &victim_connection_id) {
+                    warn!(
+                        "consume player error, teammate: {:?} {:?}",
+                        connection_id, victim_connection_id
+                    );
+                    return;
+                }
+                if !self.check_consume_player(&connection_id, &victim_connection_id) {
+                    return;
                 }
+                self.record(proto::replay_input::Data::ConsumePlayer(
+                    proto::ReplayConsumePlayer {
+                        connection_id: connection_id.to_string(),
+                        victim_connection_id: victim_connection_id.to_string(),
+                    },
+                ));
+                self.consume_player(connection_id, victim_connection_id);
             }
             command::Command::Rush { connection_id } => {
-                if let Some(client) = self.client_map.get_mut(&connection_id)
-                    && let Some(player) = client.player.as_mut()
-                {
-                    if player.radius < 20.0 {
-                        return;
-                    }
-                    if player.rush_instant.is_some() {
-                        return;
-                    }
-                    let player_mass = util::radius_to_mass(player.radius);
-                    let drop_mass = player_mass * 0.2;
-                    if let Some(mass) = player.try_drop_mass(drop_mass) {
-                        player.rush();
-
-                        let mut spore = spore::Spore::random();
-                        spore.x = player.x;
-                        spore.y = player.y;
-                        spore.radius = util::mass_to_radius(mass);
-
-                        let packet = proto_util::update_spore_pack(&spore);
-
-                        self.spore_map.insert(spore.id.clone(), spore);
-
-                        self.broadcast_packet(&packet);
-                    }
+                self.record(proto::replay_input::Data::Rush(proto::ReplayRush {
+                    connection_id: connection_id.to_string(),
+                }));
+                self.rush(connection_id);
+            }
+            command::Command::Violation {
+                connection_id,
+                player_db_id,
+                nickname,
+                violation,
+                detail,
+            } => {
+                self.report_violation(&connection_id, player_db_id, nickname, violation, detail);
+            }
+            command::Command::Pardon {
+                player_db_id,
+                issuer,
+                response_sender,
+            } => {
+                let affected = self.pardon(player_db_id, issuer);
+                let _ = response_sender.send(affected);
+            }
+            command::Command::AdminHubState { response_sender } => {
+                let _ = response_sender.send(self.admin_hub_state());
+            }
+            command::Command::Kick {
+                target,
+                reason,
+                response_sender,
+            } => {
+                let connection_id_list = self.resolve_target(&target);
+                let affected = connection_id_list
+                    .iter()
+                    .filter(|connection_id| self.kick(connection_id, reason.clone()))
+                    .count();
+                let _ = response_sender.send(affected);
+            }
+            command::Command::Ban {
+                target,
+                reason,
+                issuer,
+                duration,
+                response_sender,
+            } => {
+                let affected = self.ban(&target, reason, issuer, duration);
+                let _ = response_sender.send(affected);
+            }
+            command::Command::Unban {
+                unban_request,
+                response_sender,
+            } => {
+                let affected = self.unban(&unban_request);
+                let _ = response_sender.send(affected);
+            }
+            command::Command::SetRole { player_db_id, role } => {
+                self.client_map
+                    .values_mut()
+                    .filter(|client| {
+                        client
+                            .account
+                            .as_ref()
+                            .is_some_and(|account| account.player_db_id == player_db_id)
+                    })
+                    .for_each(|client| client.role = role);
+            }
+            command::Command::CheckBan {
+                socket_addr,
+                player_db_id,
+                response_sender,
+            } => {
+                let now = util::now_millis();
+                let ban_message = self
+                    .ban_list
+                    .find(socket_addr.ip(), player_db_id, now)
+                    .map(|ban| ban::ban_message(ban, now));
+                let _ = response_sender.send(ban_message);
+            }
+            command::Command::Mute {
+                target,
+                duration,
+                reason,
+                response_sender,
+            } => {
+                let affected = self.mute(&target, duration, reason);
+                let _ = response_sender.send(affected);
+            }
+            command::Command::Unmute {
+                player_db_id,
+                response_sender,
+            } => {
+                let affected = self.unmute(player_db_id);
+                let _ = response_sender.send(affected);
+            }
+            command::Command::BroadcastServerMessage {
+                msg,
+                response_sender,
+            } => {
+                info!("broadcast server message: {:?}", msg);
+                self.record(proto::replay_input::Data::Chat(proto::ReplayChat {
+                    connection_id: String::new(),
+                    msg: msg.to_string(),
+                    nickname: String::new(),
+                }));
+                let packet = proto_util::system_chat_packet(msg);
+                self.broadcast_packet(&packet);
+                let affected = self
+                    .client_map
+                    .values()
+                    .filter(|client| client.player.as_ref().is_some_and(|player| !player.is_bot))
+                    .count();
+                let _ = response_sender.send(affected);
+            }
+            command::Command::SetMaxSporeCount {
+                max_spore_count,
+                response_sender,
+            } => {
+                let game_config = config::GameConfig {
+                    max_spore_count,
+                    ..self.game_config.clone()
+                };
+                if let Err(e) = game_config.validate() {
+                    warn!("set max_spore_count rejected: {:?}", e);
+                    let _ = response_sender.send(Err(format!("{:#}", e).into()));
+                    return;
+                }
+                info!(
+                    "set max_spore_count: {:?} -> {:?}",
+                    self.game_config.max_spore_count, max_spore_count
+                );
+                self.set_game_config(game_config);
+                self.record_game_config();
+                let _ = response_sender.send(Ok(self.game_config.clone()));
+            }
+            command::Command::Shutdown { reason, countdown } => {
+                info!("Shutdown: {:?} {:?}", reason, countdown);
+                let packet = proto_util::shutdown_packet(reason, countdown.as_secs());
+                let bytes: Bytes = packet.encode_to_vec().into();
+                self.client_map
+                    .values()
+                    .filter(|client| !client.player.as_ref().is_some_and(|player| player.is_bot))
+                    .for_each(|client| {
+                        let _ =
+                            client
+                                .client_agent_command_sender
+                                .send(command::Command::SendBytes {
+                                    packet_type: proto_util::packet_type(&packet),
+                                    bytes: bytes.clone(),
+                                });
+                    });
+            }
+            command::Command::DisconnectAll => {
+                info!("DisconnectAll");
+                if let Some(path) = self.stop_replay().await {
+                    info!("replay saved: {:?}", path);
+                }
+                self.client_map.values().for_each(|client| {
+                    let _ = client
+                        .client_agent_command_sender
+                        .send(command::Command::DisconnectClinet);
+                });
+            }
+            command::Command::ReloadConfig { response_sender } => {
+                let result = self.reload_config();
+                if let Some(response_sender) = response_sender {
+                    let _ = response_sender.send(result);
                 }
             }
+            command::Command::StartReplay { response_sender } => {
+                let result = self
+                    .start_replay()
+                    .map_err(|e| Arc::from(format!("{:#}", e)));
+                let _ = response_sender.send(result);
+            }
+            command::Command::StopReplay { response_sender } => {
+                let path = self.stop_replay().await;
+                let _ = response_sender.send(path);
+            }
             _ => {
                 warn!("unknown command: {:?}", command);
             }
         }
     }
 
-    fn broadcast_packet(&self, packet: &proto::Packet) {
-        let bytes = packet.encode_to_vec().into();
-        self.broadcast_bytes(bytes);
+    fn record_metrics(&self) {
+        let (mut connected_client, mut human, mut bot, mut spectator) = (0, 0, 0, 0);
+        for client in self.client_map.values() {
+            if client.spectator.is_some() {
+                spectator += 1;
+            }
+            match &client.player {
+                Some(player) if player.is_bot => bot += 1,
+                Some(_) => {
+                    connected_client += 1;
+                    human += 1;
+                }
+                None => connected_client += 1,
+            }
+        }
+        metrics::CONNECTED_CLIENT.set(connected_client);
+        metrics::JOINED_PLAYER
+            .with_label_values(&["human"])
+            .set(human);
+        metrics::JOINED_PLAYER.with_label_values(&["bot"]).set(bot);
+        metrics::SPECTATOR.set(spectator);
+        metrics::SPORE.set(self.spore_map.len() as i64);
+        metrics::HUB_COMMAND_CHANNEL_DEPTH.set(self.command_receiver.len() as i64);
     }
 
-    fn broadcast_bytes(&self, bytes: Bytes) {
-        self.client_map
+    fn admin_hub_state(&self) -> admin::HubState {
+        let now = Instant::now();
+        let client_list = self
+            .client_map
             .values()
-            .filter(|client| client.player.is_some())
-            .for_each(|client| {
-                let bytes = bytes.clone();
-                let _ = client
-                    .client_agent_command_sender
-                    .send(command::Command::SendBytes { bytes });
-            });
+            .map(|client| admin::ClientInfo {
+                connection_id: client.connection_id.clone(),
+                socket_addr: client.socket_addr,
+                spectator: client.spectator.is_some(),
+                player: client.player.as_ref().map(|player| admin::PlayerInfo {
+                    db_id: player.db_id,
+                    nickname: player.nickname.clone(),
+                    x: player.x,
+                    y: player.y,
+                    radius: player.radius,
+                    is_bot: player.is_bot,
+                }),
+            })
+            .collect::<Vec<_>>();
+
+        let room_list = std::iter::once((Arc::from(room::MAIN_ROOM_ID), None))
+            .chain(
+                self.room_map
+                    .values()
+                    .map(|room| (room.id.clone(), Some(room))),
+            )
+            .map(|(room_id, room)| {
+                let room_client_list = self
+                    .client_map
+                    .values()
+                    .filter(|client| client.room_id == room_id)
+                    .collect::<Vec<_>>();
+                admin::RoomInfo {
+                    code: room.map(|room| room.code.clone()).unwrap_or_default(),
+                    settings: room.map(|room| room.settings.clone()),
+                    client_count: room_client_list.len(),
+                    player_count: room_client_list
+                        .iter()
+                        .filter(|client| client.player.is_some())
+                        .count(),
+                    bot_count: room_client_list
+                        .iter()
+                        .filter(|client| client.player.as_ref().is_some_and(|player| player.is_bot))
+                        .count(),
+                    spectator_count: room_client_list
+                        .iter()
+                        .filter(|client| client.spectator.is_some())
+                        .count(),
+                    spore_count: self.room_spore_count(&room_id),
+                    id: room_id,
+                }
+            })
+            .collect();
+
+        admin::HubState {
+            room_list,
+            client_list,
+            ban_list: self
+                .ban_list
+                .ban_list()
+                .map(|ban| admin::BanInfo {
+                    id: ban.id.clone(),
+                    player_db_id: ban.player_id,
+                    cidr: ban.cidr.clone(),
+                    reason: ban.reason.clone(),
+                    issuer: ban.issuer.clone(),
+                    expires_at: ban.expires_at,
+                    created_at: ban.created_at,
+                })
+                .collect(),
+            suspicion_list: self
+                .anti_cheat
+                .suspicion_map
+                .iter()
+                .map(|(player_db_id, suspicion)| admin::SuspicionInfo {
+                    player_db_id: *player_db_id,
+                    score: suspicion.score_at(now, &self.game_config),
+                    restricted: suspicion.restricted,
+                })
+                .collect(),
+            game_config: self.game_config.clone(),
+        }
+    }
+
+    fn resolve_target(&self, target: &admin::Target) -> Vec<Arc<str>> {
+        match target {
+            admin::Target::ConnectionId(connection_id) => self
+                .client_map
+                .get(connection_id)
+                .map(|client| vec![client.connection_id.clone()])
+                .unwrap_or_default(),
+            admin::Target::PlayerDbId(player_db_id) => self
+                .client_map
+                .values()
+                .filter(|client| {
+                    client
+                        .player
+                        .as_ref()
+                        .is_some_and(|player| !player.is_bot && player.db_id == *player_db_id)
+                })
+                .map(|client| client.connection_id.clone())
+                .collect(),
+            admin::Target::Ip(cidr) => {
+                let ip_net = match ban::parse_cidr(cidr) {
+                    Ok(ip_net) => ip_net,
+                    Err(e) => {
+                        warn!("resolve target error: {:?}", e);
+                        return Vec::new();
+                    }
+                };
+                self.client_map
+                    .values()
+                    .filter(|client| !client.player.as_ref().is_some_and(|player| player.is_bot))
+                    .filter(|client| ip_net.contains(&client.socket_addr.ip().to_canonical()))
+                    .map(|client| client.connection_id.clone())
+                    .collect()
+            }
+        }
     }
 
-    fn spawn_spore(&mut self) {
-        let spore = spore::Spore::random();
+    fn ban(
+        &mut self,
+        target: &admin::Target,
+        reason: Arc<str>,
+        issuer: Arc<str>,
+        duration: Option<Duration>,
+    ) -> usize {
+        let connection_id_list = self.resolve_target(target);
+        let target_list = match target {
+            admin::Target::Ip(cidr) => match ban::parse_cidr(cidr) {
+                Ok(ip_net) => vec![(0, ip_net.to_string())],
+                Err(e) => {
+                    warn!("ban error: {:?}", e);
+                    return 0;
+                }
+            },
+            _ => {
+                let player_db_id_list = self.resolve_player_db_id(target, &connection_id_list);
+                if player_db_id_list.is_empty() {
+                    connection_id_list
+                        .iter()
+                        .filter_map(|connection_id| self.client_map.get(connection_id))
+                        .map(|client| (0, ipnet::IpNet::from(client.socket_addr.ip()).to_string()))
+                        .collect()
+                } else {
+                    player_db_id_list
+                        .into_iter()
+                        .map(|player_db_id| (player_db_id, String::new()))
+                        .collect::<Vec<_>>()
+                }
+            }
+        };
 
-        let packet = proto_util::update_spore_pack(&spore);
+        let now = util::now_millis();
+        let expires_at = duration
+            .map(|duration| util::expires_at(now, duration))
+            .unwrap_or(0);
+        for (player_id, cidr) in target_list.iter() {
+            let ban = db::Ban {
+                id: nanoid!().into(),
+                player_id: *player_id,
+                cidr: cidr.as_str().into(),
+                reason: reason.clone(),
+                issuer: issuer.clone(),
+                expires_at,
+                created_at: now,
+            };
+            info!("ban: {:?}", ban);
+            self.ban_list.insert(ban.clone());
+            let _ = self
+                .db_writer_command_sender
+                .send(command::Command::PersistBan { ban });
+        }
 
-        self.spore_map.insert(spore.id.clone(), spore);
+        let kick_list = self
+            .client_map
+            .values()
+            .filter(|client| !client.player.as_ref().is_some_and(|player| player.is_bot))
+            .map(|client| {
+                (
+                    client.connection_id.clone(),
+                    client.socket_addr.ip(),
+                    client.player.as_ref().map(|player| player.db_id),
+                )
+            })
+            .collect::<Vec<_>>();
+        for (connection_id, ip, player_db_id) in kick_list {
+            let ban_message = self
+                .ban_list
+                .find(ip, player_db_id, now)
+                .map(|ban| ban::ban_message(ban, now));
+            if let Some(ban_message) = ban_message {
+                self.kick(&connection_id, ban_message);
+            }
+        }
 
-        self.broadcast_packet(&packet);
+        target_list.len()
     }
 
-    fn tick_player(&mut self, delta: Duration) {
-        let mut spore_packet_list = vec![];
+    fn unban(&mut self, unban_request: &admin::UnbanRequest) -> usize {
+        let cidr = unban_request
+            .ip
+            .as_ref()
+            .and_then(|ip| ban::parse_cidr(ip).ok())
+            .map(|ip_net| ip_net.to_string());
+        let ban_id_list = self
+            .ban_list
+            .ban_list()
+            .filter(|ban| {
+                unban_request.ban_id.as_ref() == Some(&ban.id)
+                    || unban_request.player_db_id == Some(ban.player_id)
+                    || cidr.as_deref() == Some(ban.cidr.as_ref())
+            })
+            .map(|ban| ban.id.clone())
+            .collect::<Vec<_>>();
+        for ban_id in ban_id_list.iter() {
+            info!("unban: {:?}", self.ban_list.remove(ban_id));
+            let _ = self
+                .db_writer_command_sender
+                .send(command::Command::DeleteBan {
+                    ban_id: ban_id.clone(),
+                });
+        }
+        ban_id_list.len()
+    }
 
-        let player_list = self
-            .client_map
-            .values_mut()
-            .flat_map(|client| client.player.as_mut());
+    fn resolve_player_db_id(
+        &self,
+        target: &admin::Target,
+        connection_id_list: &[Arc<str>],
+    ) -> Vec<i64> {
+        let mut player_db_id_list = connection_id_list
+            .iter()
+            .filter_map(|connection_id| self.client_map.get(connection_id))
+            .filter_map(|client| client.player.as_ref())
+            .filter(|player| !player.is_bot)
+            .map(|player| player.db_id)
+            .collect::<Vec<_>>();
+        if let admin::Target::PlayerDbId(player_db_id) = target
+            && !player_db_id_list.contains(player_db_id)
+        {
+            player_db_id_list.push(*player_db_id);
+        }
+        player_db_id_list
+    }
 
-        for player in player_list {
-            player.tick(delta);
+    fn kick(&mut self, connection_id: &Arc<str>, reason: Arc<str>) -> bool {
+        let client = match self.client_map.get(connection_id) {
+            Some(client) => client,
+            None => return false,
+        };
+        info!("kick: {:?} {:?}", connection_id, reason);
 
-            let drop_mass_probability = player.radius / (MAX_SPORE_COUNT as f64 * 4.0);
-            if rand::random::<f64>() < drop_mass_probability {
-                let drop_mass = util::radius_to_mass((5.0 + player.radius / 50.0).min(15.0));
-                if let Some(mass) = player.try_drop_mass(drop_mass) {
-                    let mut spore = spore::Spore::random();
-                    spore.x = player.x;
-                    spore.y = player.y;
-                    spore.radius = util::mass_to_radius(mass);
+        if client.player.as_ref().is_some_and(|player| player.is_bot) {
+            self.record(proto::replay_input::Data::Leave(proto::ReplayLeave {
+                connection_id: connection_id.to_string(),
+                reason: reason.to_string(),
+            }));
+            self.leave(connection_id.clone(), reason);
+            return true;
+        }
 
-                    let packet = proto_util::update_spore_pack(&spore);
-                    spore_packet_list.push(packet);
+        let packet = proto_util::disconnect_packet(connection_id.clone(), reason);
+        let _ = client
+            .client_agent_command_sender
+            .send(command::Command::SendPacket { packet });
+        let _ = client
+            .client_agent_command_sender
+            .send(command::Command::DisconnectClinet);
 
-                    self.spore_map.insert(spore.id.clone(), spore);
-                }
+        true
+    }
+
+    fn reload_config(&mut self) -> Result<config::ConfigReload, Arc<str>> {
+        let config = match config::Config::load() {
+            Ok(config) => config,
+            Err(e) => {
+                error!("config reload rejected: {:?}", e);
+                return Err(format!("{:#}", e).into());
             }
-        }
+        };
 
-        self.sync_player();
+        let config_reload = match (
+            config::diff("server", &self.server_config, &config.server),
+            config::diff("game", &self.game_config, &config.game),
+        ) {
+            (Ok(restart_required_list), Ok(changed_list)) => config::ConfigReload {
+                changed_list,
+                restart_required_list,
+            },
+            (Err(e), _) | (_, Err(e)) => {
+                error!("config reload diff error: {:?}", e);
+                return Err(format!("{:#}", e).into());
+            }
+        };
 
-        for spore_packet in spore_packet_list {
-            self.broadcast_packet(&spore_packet);
+        for changed in config_reload.changed_list.iter() {
+            info!("config reload: {}", changed);
+        }
+        for restart_required in config_reload.restart_required_list.iter() {
+            warn!("config reload: {} (restart required)", restart_required);
+        }
+        if config_reload.changed_list.is_empty() && config_reload.restart_required_list.is_empty() {
+            info!("config reload: no change");
         }
-    }
 
-    fn sync_player(&self) {
-        let player_list = self
-            .client_map
-            .values()
-            .filter_map(|client| client.player.as_ref())
-            .collect::<Vec<_>>();
+        self.set_game_config(config.game);
+        if !config_reload.changed_list.is_empty() {
+            self.record_game_config();
+        }
 
-        let packet = proto_util::update_player_batch_packet(&player_list);
+        Ok(config_reload)
+    }
+
+    fn login(
+        &mut self,
+        connection_id: Arc<str>,
+        account: Account,
+        role: role::Role,
+        friend_player_db_id_list: Vec<i64>,
+    ) {
+        let already_online = !self
+            .account_connection_id_list(account.player_db_id)
+            .is_empty();
+        let nickname = account.nickname.clone();
+
+        let client = match self.client_map.get_mut(&connection_id) {
+            Some(client) => client,
+            None => {
+                error!("client not found: {:?}", connection_id);
+                return;
+            }
+        };
+        client.account = Some(account);
+        client.role = role;
+
+        if already_online {
+            return;
+        }
+        let msg: Arc<str> = format!("your friend {} is online", nickname).into();
+        for friend_player_db_id in friend_player_db_id_list {
+            for friend_connection_id in self.account_connection_id_list(friend_player_db_id) {
+                self.send_system_chat(&friend_connection_id, msg.clone());
+            }
+        }
+    }
+
+    fn account_connection_id_list(&self, player_db_id: i64) -> Vec<Arc<str>> {
+        self.client_map
+            .values()
+            .filter(|client| {
+                client
+                    .account
+                    .as_ref()
+                    .is_some_and(|account| account.player_db_id == player_db_id)
+            })
+            .map(|client| client.connection_id.clone())
+            .collect()
+    }
+
+    fn presence(&self, player_db_id: i64) -> friend::Presence {
+        let client_list = self
+            .account_connection_id_list(player_db_id)
+            .iter()
+            .filter_map(|connection_id| self.client_map.get(connection_id))
+            .collect::<Vec<_>>();
+        let status = if client_list.iter().any(|client| client.player.is_some()) {
+            proto::PresenceStatus::InGame
+        } else if client_list.iter().any(|client| client.spectator.is_some()) {
+            proto::PresenceStatus::Spectating
+        } else if !client_list.is_empty() {
+            proto::PresenceStatus::Lobby
+        } else {
+            return friend::Presence::offline();
+        };
+        let room_id = client_list
+            .iter()
+            .find(|client| client.player.is_some() || client.spectator.is_some())
+            .map(|client| client.room_id.clone())
+            .unwrap_or_else(|| "".into());
+        friend::Presence { status, room_id }
+    }
+
+    fn handle_join(
+        &mut self,
+        connection_id: Arc<str>,
+        player_db_id: i64,
+        nickname: Arc<str>,
+        color: i64,
+        room_id: Arc<str>,
+        anchor_connection_id: Option<Arc<str>>,
+    ) -> bool {
+        if let Err(reason) = self.check_room_capacity(&connection_id, &room_id) {
+            self.send_system_chat(&connection_id, reason);
+            return false;
+        }
+
+        if let Some(client) = self.client_map.get(&connection_id) {
+            let now = util::now_millis();
+            let ban_message = self
+                .ban_list
+                .find(client.socket_addr.ip(), Some(player_db_id), now)
+                .map(|ban| ban::ban_message(ban, now));
+            if let Some(ban_message) = ban_message {
+                warn!("banned player join: {:?} {:?}", player_db_id, ban_message);
+                self.kick(&connection_id, ban_message);
+                return false;
+            }
+        }
+
+        self.client_map.values().for_each(|client| {
+            let player = match &client.player {
+                Some(player) => player,
+                None => return,
+            };
+            if player.db_id == player_db_id && client.connection_id != connection_id {
+                let _ = client
+                    .client_agent_command_sender
+                    .send(command::Command::DisconnectClinet);
+            }
+        });
+
+        if !self.client_map.contains_key(&connection_id) {
+            error!("client not found: {:?}", connection_id);
+            return false;
+        }
+
+        self.record(proto::replay_input::Data::Join(proto::ReplayJoin {
+            connection_id: connection_id.to_string(),
+            player_db_id,
+            nickname: nickname.to_string(),
+            color,
+            anchor_connection_id: anchor_connection_id
+                .as_ref()
+                .map(|anchor_connection_id| anchor_connection_id.to_string())
+                .unwrap_or_default(),
+            room_id: room_id.to_string(),
+        }));
+        match self.join(
+            connection_id.clone(),
+            player_db_id,
+            nickname,
+            color,
+            room_id.clone(),
+            anchor_connection_id.as_ref(),
+        ) {
+            Some((player_x, player_y)) => {
+                if let Some(room) = self.room_map.get(&room_id) {
+                    self.send_packet(&connection_id, proto_util::room_packet(room.to_proto()));
+                }
+                if let Some(round) = self.round_map.get(&room_id) {
+                    let round = round.to_proto(self.room_game_config(&room_id));
+                    self.send_packet(&connection_id, proto_util::round_packet(round));
+                }
+                self.send_spore_batch(&connection_id, player_x, player_y);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn join(
+        &mut self,
+        connection_id: Arc<str>,
+        player_db_id: i64,
+        nickname: Arc<str>,
+        color: i64,
+        room_id: Arc<str>,
+        anchor_connection_id: Option<&Arc<str>>,
+    ) -> Option<(f64, f64)> {
+        let anchor = anchor_connection_id
+            .and_then(|anchor_connection_id| self.client_map.get(anchor_connection_id))
+            .filter(|client| client.room_id == room_id)
+            .and_then(|client| client.player.clone());
+        let game_config = room::game_config(&self.room_map, &self.game_config, &room_id);
+
+        let mut player = player::Player::random(
+            &mut self.rng,
+            player_db_id,
+            connection_id.clone(),
+            nickname,
+            color,
+            game_config,
+        );
+        if let Some(anchor) = anchor {
+            player.place_near(&mut self.rng, &anchor, game_config);
+        }
+        team::assign(
+            &mut player,
+            self.client_map
+                .values()
+                .filter(|client| client.room_id == room_id)
+                .filter_map(|client| client.player.as_ref()),
+            game_config.team_count,
+        );
+        let player_xy = (player.x, player.y);
+
+        let client = self.client_map.get_mut(&connection_id)?;
+
+        let left_room_id = match client.room_id != room_id && client.player.is_some() {
+            true => Some(client.room_id.clone()),
+            false => None,
+        };
+        client.player = Some(player);
+        client.spectator = None;
+        client.room_id = room_id;
+
+        if let Some(left_room_id) = left_room_id {
+            let packet = proto_util::disconnect_packet(connection_id, "left room".into());
+            self.broadcast_room_packet(&left_room_id, &packet);
+        }
+
+        Some(player_xy)
+    }
+
+    fn spectate(&mut self, connection_id: Arc<str>, mode: spectator::SpectateMode) {
+        let spectator_count = self
+            .client_map
+            .values()
+            .filter(|client| client.spectator.is_some() && client.connection_id != connection_id)
+            .count();
+        let target_exists = match &mode {
+            spectator::SpectateMode::FollowPlayer { connection_id } => {
+                self.client_map.get(connection_id).is_some_and(|client| {
+                    client.player.is_some() && &*client.room_id == room::MAIN_ROOM_ID
+                })
+            }
+            _ => true,
+        };
+
+        let client = match self.client_map.get_mut(&connection_id) {
+            Some(client) => client,
+            None => {
+                error!("client not found: {:?}", connection_id);
+                return;
+            }
+        };
+
+        let reason = if client.player.is_some() {
+            Some("already joined")
+        } else if spectator_count >= self.game_config.max_spectator_count {
+            Some("spectator limit reached")
+        } else if !target_exists {
+            Some("player not found")
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            let packet = proto_util::spectate_err_packet(reason.into());
+            let _ = client
+                .client_agent_command_sender
+                .send(command::Command::SendPacket { packet });
+            return;
+        }
+
+        client.spectator = Some(spectator::Spectator::new(mode));
+        client.room_id = room::MAIN_ROOM_ID.into();
+        let packet = proto_util::spectate_ok_packet();
+        let _ = client
+            .client_agent_command_sender
+            .send(command::Command::SendPacket { packet });
+
+        self.send_spore_batch(&connection_id, 0.0, 0.0);
+        self.sync_spectator();
+    }
+
+    fn chat(
+        &mut self,
+        connection_id: Arc<str>,
+        player_db_id: i64,
+        nickname: Arc<str>,
+        role: role::Role,
+        chat_command: chat::ChatCommand,
+    ) {
+        if let Some(client) = self.client_map.get_mut(&connection_id) {
+            client.role = role;
+        }
+        if role < chat_command.required_role() {
+            warn!(
+                "chat command permission denied: {:?} {:?} {:?}",
+                player_db_id, role, chat_command
+            );
+            self.send_system_chat(&connection_id, "permission denied".into());
+            return;
+        }
+
+        match chat_command {
+            chat::ChatCommand::Global { msg } => {
+                self.player_chat(
+                    proto::ChatChannel::Global,
+                    connection_id,
+                    player_db_id,
+                    nickname,
+                    None,
+                    msg,
+                );
+            }
+            chat::ChatCommand::Whisper {
+                target_nickname,
+                msg,
+            } => match self.find_player_by_nickname(&target_nickname) {
+                Some(target) => {
+                    self.player_chat(
+                        proto::ChatChannel::Whisper,
+                        connection_id,
+                        player_db_id,
+                        nickname,
+                        Some(target),
+                        msg,
+                    );
+                }
+                None => {
+                    let msg = format!("player not found: {}", target_nickname);
+                    self.send_system_chat(&connection_id, msg.into());
+                }
+            },
+            chat::ChatCommand::Party { msg } => {
+                if self.party_connection_id_list(&connection_id).is_empty() {
+                    self.send_system_chat(&connection_id, "you are not in a party".into());
+                    return;
+                }
+                self.player_chat(
+                    proto::ChatChannel::Party,
+                    connection_id,
+                    player_db_id,
+                    nickname,
+                    None,
+                    msg,
+                );
+            }
+            chat::ChatCommand::PartyAction { party_action } => {
+                self.party(connection_id, party_action, true);
+            }
+            chat::ChatCommand::RoomAction { room_action } => {
+                self.room(connection_id, room_action, true);
+            }
+            chat::ChatCommand::Friend { .. } | chat::ChatCommand::FriendList => {
+                warn!("friend chat command reached hub: {:?}", chat_command);
+            }
+            chat::ChatCommand::Help => {
+                self.send_system_chat(&connection_id, chat::HELP.into());
+                if role.can_moderate() {
+                    self.send_system_chat(&connection_id, chat::MODERATOR_HELP.into());
+                }
+                if role.can_administrate() {
+                    self.send_system_chat(&connection_id, chat::ADMIN_HELP.into());
+                }
+            }
+            chat::ChatCommand::Stats => {
+                let msg = self.player_stats(&connection_id);
+                self.send_system_chat(&connection_id, msg);
+            }
+            chat::ChatCommand::Leaderboard => {
+                let msg = self.room_leaderboard(&connection_id);
+                self.send_system_chat(&connection_id, msg);
+            }
+            chat::ChatCommand::Round => {
+                let room_id = self.client_room_id(&connection_id);
+                let msg = match self.round_map.get(&room_id) {
+                    Some(round) => round.describe(
+                        self.room_player_list(&room_id)
+                            .filter(|player| !player.is_bot)
+                            .count(),
+                        self.room_game_config(&room_id),
+                    ),
+                    None => "rounds are off, the game runs forever".into(),
+                };
+                self.send_system_chat(&connection_id, msg);
+            }
+            chat::ChatCommand::Kick {
+                target_nickname,
+                reason,
+            } => {
+                let msg = match self.find_player_by_nickname(&target_nickname) {
+                    Some(target) if !self.outranks(role, &target) => {
+                        format!("cannot kick {}, equal or higher role", target_nickname)
+                    }
+                    Some(target) => {
+                        info!("chat kick by {:?}: {:?}", player_db_id, target);
+                        self.kick(&target.connection_id, reason.unwrap_or("kicked".into()));
+                        format!("kicked {}", target_nickname)
+                    }
+                    None => format!("player not found: {}", target_nickname),
+                };
+                self.send_system_chat(&connection_id, msg.into());
+            }
+            chat::ChatCommand::Mute {
+                target_nickname,
+                duration,
+                reason,
+            } => {
+                let msg = match self.find_player_by_nickname(&target_nickname) {
+                    Some(target) if !self.outranks(role, &target) => {
+                        format!("cannot mute {}, equal or higher role", target_nickname)
+                    }
+                    Some(target) => {
+                        info!("chat mute by {:?}: {:?}", player_db_id, target);
+                        self.mute(
+                            &admin::Target::PlayerDbId(target.db_id),
+                            duration,
+                            reason.unwrap_or("muted".into()),
+                        );
+                        format!("muted {} for {}s", target_nickname, duration.as_secs())
+                    }
+                    None => format!("player not found: {}", target_nickname),
+                };
+                self.send_system_chat(&connection_id, msg.into());
+            }
+            chat::ChatCommand::Unmute { target_nickname } => {
+                let msg = match self.find_player_by_nickname(&target_nickname) {
+                    Some(target) => {
+                        info!("chat unmute by {:?}: {:?}", player_db_id, target);
+                        self.unmute(target.db_id);
+                        format!("unmuted {}", target_nickname)
+                    }
+                    None => format!("player not found: {}", target_nickname),
+                };
+                self.send_system_chat(&connection_id, msg.into());
+            }
+            chat::ChatCommand::Ban {
+                target_nickname,
+                duration,
+                reason,
+            } => {
+                let msg = match self.find_player_by_nickname(&target_nickname) {
+                    Some(target) if !self.outranks(role, &target) => {
+                        format!("cannot ban {}, equal or higher role", target_nickname)
+                    }
+                    Some(target) => {
+                        info!("chat ban by {:?}: {:?}", player_db_id, target);
+                        self.ban(
+                            &admin::Target::PlayerDbId(target.db_id),
+                            reason.unwrap_or("banned".into()),
+                            nickname,
+                            duration,
+                        );
+                        format!("banned {}", target_nickname)
+                    }
+                    None => format!("player not found: {}", target_nickname),
+                };
+                self.send_system_chat(&connection_id, msg.into());
+            }
+            chat::ChatCommand::Suspicion { target_nickname } => {
+                let msg = match self.find_player_by_nickname(&target_nickname) {
+                    Some(target) => {
+                        let score = self.anti_cheat.score(target.db_id, &self.game_config);
+                        format!(
+                            "{}: suspicion {:.0}/{:.0}{}",
+                            target.nickname,
+                            score,
+                            self.game_config.anti_cheat_threshold,
+                            if self.anti_cheat.is_restricted(target.db_id) {
+                                ", restricted"
+                            } else {
+                                ""
+                            }
+                        )
+                    }
+                    None => format!("player not found: {}", target_nickname),
+                };
+                self.send_system_chat(&connection_id, msg.into());
+            }
+            chat::ChatCommand::Pardon { target_nickname } => {
+                let msg = match self.find_player_by_nickname(&target_nickname) {
+                    Some(target) => {
+                        info!("chat pardon by {:?}: {:?}", player_db_id, target);
+                        self.pardon(target.db_id, nickname);
+                        format!("pardoned {}", target_nickname)
+                    }
+                    None => format!("player not found: {}", target_nickname),
+                };
+                self.send_system_chat(&connection_id, msg.into());
+            }
+            chat::ChatCommand::Spawn { spore_count } => {
+                info!("chat spawn by {:?}: {:?}", player_db_id, spore_count);
+                let room_id = self.client_room_id(&connection_id);
+                for _ in 0..spore_count {
+                    self.record(proto::replay_input::Data::SpawnSpore(
+                        proto::ReplaySpawnSpore {
+                            room_id: room_id.to_string(),
+                        },
+                    ));
+                    self.spawn_spore(room_id.clone());
+                }
+                let msg = format!("spawned {} spores", spore_count);
+                self.send_system_chat(&connection_id, msg.into());
+            }
+            chat::ChatCommand::Set { key, value } => {
+                let msg = match self.game_config.with_setting(&key, &value) {
+                    Ok(game_config) => {
+                        info!("chat set by {:?}: {} = {}", player_db_id, key, value);
+                        self.set_game_config(game_config);
+                        self.record_game_config();
+                        format!("game.{} = {}", key, value)
+                    }
+                    Err(e) => format!("{:#}", e),
+                };
+                self.send_system_chat(&connection_id, msg.into());
+            }
+        }
+    }
+
+    fn player_chat(
+        &mut self,
+        channel: proto::ChatChannel,
+        connection_id: Arc<str>,
+        player_db_id: i64,
+        nickname: Arc<str>,
+        target: Option<chat::ChatTarget>,
+        msg: Arc<str>,
+    ) {
+        let result = self
+            .chat_moderator
+            .check(player_db_id, &msg, &self.game_config);
+
+        let _ = self
+            .db_writer_command_sender
+            .send(command::Command::PersistChatLog {
+                chat_log: db::ChatLog {
+                    id: 0,
+                    player_id: player_db_id,
+                    nickname: nickname.clone(),
+                    msg,
+                    blocked_reason: result.as_ref().err().cloned().unwrap_or_default(),
+                    channel: chat::channel_name(channel).into(),
+                    target_player_id: target.as_ref().map(|target| target.db_id).unwrap_or(0),
+                    created_at: util::now_millis(),
+                },
+            });
+
+        let msg = match result {
+            Ok(msg) => msg,
+            Err(reason) => {
+                metrics::CHAT_MESSAGE.with_label_values(&["blocked"]).inc();
+                info!("chat blocked: {:?} {:?}", player_db_id, reason);
+                self.send_system_chat(&connection_id, reason);
+                return;
+            }
+        };
+        metrics::CHAT_MESSAGE
+            .with_label_values(&["delivered"])
+            .inc();
+
+        let target_nickname = target
+            .as_ref()
+            .map(|target| target.nickname.clone())
+            .unwrap_or_default();
+        let packet = proto_util::chat_packet(
+            channel,
+            connection_id.clone(),
+            nickname.clone(),
+            target_nickname,
+            msg.clone(),
+        );
+        match channel {
+            proto::ChatChannel::Whisper => {
+                if let Some(target) = target {
+                    self.send_packet(&target.connection_id, packet.clone());
+                }
+                self.send_packet(&connection_id, packet);
+            }
+            proto::ChatChannel::Party => {
+                for member_connection_id in self.party_connection_id_list(&connection_id) {
+                    self.send_packet(&member_connection_id, packet.clone());
+                }
+            }
+            _ => {
+                self.record(proto::replay_input::Data::Chat(proto::ReplayChat {
+                    connection_id: connection_id.to_string(),
+                    msg: msg.to_string(),
+                    nickname: nickname.to_string(),
+                }));
+                self.broadcast_packet(&packet);
+            }
+        }
+    }
+
+    fn find_player_by_nickname(&self, nickname: &str) -> Option<chat::ChatTarget> {
+        self.client_map
+            .values()
+            .filter_map(|client| client.player.as_ref())
+            .find(|player| !player.is_bot && player.nickname.eq_ignore_ascii_case(nickname))
+            .map(|player| chat::ChatTarget {
+                connection_id: player.connection_id.clone(),
+                db_id: player.db_id,
+                nickname: player.nickname.clone(),
+            })
+    }
+
+    fn outranks(&self, role: role::Role, target: &chat::ChatTarget) -> bool {
+        self.client_map
+            .values()
+            .filter(|client| {
+                client
+                    .account
+                    .as_ref()
+                    .is_some_and(|account| account.player_db_id == target.db_id)
+            })
+            .all(|client| client.role < role)
+    }
+
+    fn room(&mut self, connection_id: Arc<str>, room_action: room::RoomAction, chat_reply: bool) {
+        let account = self
+            .client_map
+            .get(&connection_id)
+            .and_then(|client| client.account.clone());
+        let result = match account {
+            Some(account) => self.apply_room_action(&connection_id, account, room_action),
+            None => Err("login first".into()),
+        };
+        match result {
+            Ok(msg) => self.send_system_chat(&connection_id, msg),
+            Err(reason) if chat_reply => self.send_system_chat(&connection_id, reason),
+            Err(reason) => self.send_packet(&connection_id, proto_util::room_err_packet(reason)),
+        }
+    }
+
+    fn apply_room_action(
+        &mut self,
+        connection_id: &Arc<str>,
+        account: Account,
+        room_action: room::RoomAction,
+    ) -> Result<Arc<str>, Arc<str>> {
+        match room_action {
+            room::RoomAction::Create { settings } => {
+                if self.room_map.len() >= self.game_config.max_room_count {
+                    return Err("too many rooms, try again later".into());
+                }
+                let settings = room::RoomSettings::new(&settings, &self.game_config)?;
+                let mut code = room::random_code();
+                while self.room_map.values().any(|room| room.code == code) {
+                    code = room::random_code();
+                }
+                let room = room::Room::new(
+                    nanoid!().into(),
+                    code.clone(),
+                    account.player_db_id,
+                    settings,
+                    &self.game_config,
+                );
+                info!(
+                    "create room: {:?} {:?} by {:?} {:?}",
+                    room.id, room.code, account.nickname, room.settings
+                );
+                let room_id = room.id.clone();
+                self.record(proto::replay_input::Data::CreateRoom(replay::replay_room(
+                    &room,
+                )));
+                self.open_room(room);
+                self.join_room(connection_id, account, room_id)?;
+                Ok(format!("room {} created, share the code to let others join", code).into())
+            }
+            room::RoomAction::Join { code } => {
+                let room_id = self
+                    .room_map
+                    .values()
+                    .find(|room| room.code == code)
+                    .map(|room| room.id.clone())
+                    .ok_or_else(|| Arc::from(format!("room not found: {}", code)))?;
+                self.join_room(connection_id, account, room_id)?;
+                Ok(format!("joined room {}", code).into())
+            }
+            room::RoomAction::Info => {
+                let room_id = self.client_room_id(connection_id);
+                match self.room_map.get(&room_id) {
+                    Some(room) => Ok(room.describe(self.room_player_list(&room_id).count())),
+                    None => Ok(
+                        "you are in the main room, try /room create or /room join <code>".into(),
+                    ),
+                }
+            }
+        }
+    }
+
+    fn join_room(
+        &mut self,
+        connection_id: &Arc<str>,
+        account: Account,
+        room_id: Arc<str>,
+    ) -> Result<(), Arc<str>> {
+        self.check_room_capacity(connection_id, &room_id)?;
+        if self.handle_join(
+            connection_id.clone(),
+            account.player_db_id,
+            account.nickname,
+            account.color,
+            room_id.clone(),
+            None,
+        ) {
+            self.join_party(connection_id, account.player_db_id, room_id);
+        }
+        Ok(())
+    }
+
+    fn check_room_capacity(
+        &self,
+        connection_id: &Arc<str>,
+        room_id: &Arc<str>,
+    ) -> Result<(), Arc<str>> {
+        if &**room_id == room::MAIN_ROOM_ID {
+            return Ok(());
+        }
+        let room = self
+            .room_map
+            .get(room_id)
+            .ok_or_else(|| Arc::from("room not found"))?;
+        let player_count = self
+            .client_map
+            .values()
+            .filter(|client| {
+                &client.room_id == room_id
+                    && client.player.is_some()
+                    && &client.connection_id != connection_id
+            })
+            .count();
+        if player_count >= room.settings.max_player_count {
+            return Err(format!("room {} is full", room.code).into());
+        }
+        Ok(())
+    }
+
+    fn open_room(&mut self, room: room::Room) {
+        let room_id = room.id.clone();
+        let max_spore_count = room.game_config.max_spore_count;
+        self.room_map.insert(room_id.clone(), room);
+        for _ in 0..max_spore_count {
+            self.spawn_spore(room_id.clone());
+        }
+    }
+
+    fn close_idle_room(&mut self) {
+        let now = Instant::now();
+        let mut idle_room_id_list = vec![];
+        for room in self.room_map.values_mut() {
+            if self
+                .client_map
+                .values()
+                .any(|client| client.room_id == room.id)
+            {
+                room.empty_since = None;
+                continue;
+            }
+            let empty_since = *room.empty_since.get_or_insert(now);
+            if now.duration_since(empty_since) >= room::ROOM_IDLE_TIMEOUT {
+                idle_room_id_list.push(room.id.clone());
+            }
+        }
+        for room_id in idle_room_id_list {
+            info!("close idle room: {:?}", room_id);
+            self.record(proto::replay_input::Data::CloseRoom(
+                proto::ReplayCloseRoom {
+                    room_id: room_id.to_string(),
+                },
+            ));
+            self.close_room(&room_id);
+        }
+    }
+
+    fn close_room(&mut self, room_id: &str) {
+        self.room_map.shift_remove(room_id);
+        self.spore_map.retain(|_, spore| &*spore.room_id != room_id);
+    }
+
+    fn set_game_config(&mut self, game_config: config::GameConfig) {
+        let team_count_list = std::iter::once(Arc::from(room::MAIN_ROOM_ID))
+            .chain(self.room_map.keys().cloned())
+            .map(|room_id| {
+                let team_count = self.room_game_config(&room_id).team_count;
+                (room_id, team_count)
+            })
+            .collect::<Vec<_>>();
+
+        self.game_config = game_config;
+        self.refresh_room_game_config();
+
+        for (room_id, old_team_count) in team_count_list {
+            let team_count = self.room_game_config(&room_id).team_count;
+            if team_count == old_team_count {
+                continue;
+            }
+            info!(
+                "rebalance team: {:?} {} -> {}",
+                room_id, old_team_count, team_count
+            );
+            team::rebalance(
+                self.client_map
+                    .values_mut()
+                    .filter(|client| client.room_id == room_id)
+                    .filter_map(|client| client.player.as_mut()),
+                team_count,
+            );
+        }
+    }
+
+    fn refresh_room_game_config(&mut self) {
+        for room in self.room_map.values_mut() {
+            room.game_config = room.settings.game_config(&self.game_config);
+        }
+    }
+
+    fn room_game_config(&self, room_id: &str) -> &config::GameConfig {
+        room::game_config(&self.room_map, &self.game_config, room_id)
+    }
+
+    fn room_player_list(&self, room_id: &str) -> impl Iterator<Item = &player::Player> {
+        self.client_map
+            .values()
+            .filter(move |client| &*client.room_id == room_id)
+            .filter_map(|client| client.player.as_ref())
+    }
+
+    fn room_spore_count(&self, room_id: &str) -> usize {
+        self.spore_map
+            .values()
+            .filter(|spore| &*spore.room_id == room_id)
+            .count()
+    }
+
+    fn client_room_id(&self, connection_id: &Arc<str>) -> Arc<str> {
+        self.client_map
+            .get(connection_id)
+            .map_or(room::MAIN_ROOM_ID.into(), |client| client.room_id.clone())
+    }
+
+    fn find_account_by_nickname(&self, nickname: &str) -> Option<Account> {
+        self.client_map
+            .values()
+            .filter_map(|client| client.account.as_ref())
+            .find(|account| account.nickname.eq_ignore_ascii_case(nickname))
+            .cloned()
+    }
+
+    fn party(
+        &mut self,
+        connection_id: Arc<str>,
+        party_action: party::PartyAction,
+        chat_reply: bool,
+    ) {
+        let account = self
+            .client_map
+            .get(&connection_id)
+            .and_then(|client| client.account.clone());
+        let result = match account {
+            Some(account) => self.apply_party_action(account, party_action),
+            None => Err("login first".into()),
+        };
+        match result {
+            Ok(Some(msg)) => self.send_system_chat(&connection_id, msg),
+            Ok(None) => {}
+            Err(reason) if chat_reply => self.send_system_chat(&connection_id, reason),
+            Err(reason) => self.send_packet(&connection_id, proto_util::party_err_packet(reason)),
+        }
+    }
+
+    fn apply_party_action(
+        &mut self,
+        account: Account,
+        party_action: party::PartyAction,
+    ) -> Result<Option<Arc<str>>, Arc<str>> {
+        let max_party_size = self.game_config.max_party_size;
+        match party_action {
+            party::PartyAction::Invite { nickname } => {
+                let target = self
+                    .find_account_by_nickname(&nickname)
+                    .ok_or_else(|| Arc::from(format!("player not found: {}", nickname)))?;
+                self.party_book.invite(&account, &target, max_party_size)?;
+                let msg: Arc<str> = format!(
+                    "{} invited you to a party, /party accept {}",
+                    account.nickname, account.nickname
+                )
+                .into();
+                for target_connection_id in self.account_connection_id_list(target.player_db_id) {
+                    self.send_system_chat(&target_connection_id, msg.clone());
+                }
+                Ok(Some(
+                    format!("party invite sent to {}", target.nickname).into(),
+                ))
+            }
+            party::PartyAction::Accept { nickname } => {
+                let party_id = self
+                    .party_book
+                    .accept(&account, &nickname, max_party_size)?
+                    .id
+                    .clone();
+                self.send_party_chat(
+                    &party_id,
+                    format!("{} joined the party", account.nickname).into(),
+                );
+                self.sync_party(&party_id);
+                Ok(None)
+            }
+            party::PartyAction::Leave => {
+                self.leave_party(&account);
+                Ok(Some("you left the party".into()))
+            }
+            party::PartyAction::Kick { nickname } => {
+                let member = self.party_book.kick(account.player_db_id, &nickname)?;
+                if let Some(party_id) = self.party_book.member_map.get(&account.player_db_id) {
+                    let party_id = party_id.clone();
+                    self.send_party_chat(
+                        &party_id,
+                        format!("{} was kicked from the party", member.nickname).into(),
+                    );
+                    self.sync_party(&party_id);
+                }
+                let msg: Arc<str> =
+                    format!("you were kicked from the party by {}", account.nickname).into();
+                for member_connection_id in self.account_connection_id_list(member.player_db_id) {
+                    self.send_system_chat(&member_connection_id, msg.clone());
+                    self.send_packet(
+                        &member_connection_id,
+                        proto_util::party_packet(proto::Party::default()),
+                    );
+                }
+                Ok(None)
+            }
+            party::PartyAction::Protect {
+                friendly_fire_protection,
+            } => {
+                let party_id = self
+                    .party_book
+                    .protect(account.player_db_id, friendly_fire_protection)?
+                    .id
+                    .clone();
+                let msg = match friendly_fire_protection {
+                    true => "friendly fire protection on, party members can not eat each other",
+                    false => "friendly fire protection off",
+                };
+                self.send_party_chat(&party_id, msg.into());
+                self.sync_party(&party_id);
+                Ok(None)
+            }
+            party::PartyAction::Info => match self.party_book.get(account.player_db_id) {
+                Some(party) => Ok(Some(party.describe())),
+                None => Err("you are not in a party, try /party invite <nickname>".into()),
+            },
+        }
+    }
+
+    fn leave_party(&mut self, account: &Account) {
+        let party_id = match self.party_book.leave(account.player_db_id) {
+            Some(party_id) => party_id,
+            None => return,
+        };
+        info!("party leave: {:?} {:?}", account.nickname, party_id);
+        self.send_party_chat(
+            &party_id,
+            format!("{} left the party", account.nickname).into(),
+        );
+        self.sync_party(&party_id);
+        for connection_id in self.account_connection_id_list(account.player_db_id) {
+            self.send_packet(
+                &connection_id,
+                proto_util::party_packet(proto::Party::default()),
+            );
+        }
+    }
+
+    fn join_party(&mut self, connection_id: &Arc<str>, player_db_id: i64, room_id: Arc<str>) {
+        let party = match self.party_book.get(player_db_id) {
+            Some(party) if party.leader_player_db_id == player_db_id => party,
+            _ => return,
+        };
+        let msg: Arc<str> = match party.leader() {
+            Some(leader) => format!("joined the game with your party leader {}", leader.nickname),
+            None => "joined the game with your party".to_string(),
+        }
+        .into();
+        let member_list = party
+            .member_list
+            .iter()
+            .filter(|member| member.player_db_id != player_db_id)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for member in member_list {
+            let member_client_list = self
+                .account_connection_id_list(member.player_db_id)
+                .iter()
+                .filter_map(|member_connection_id| self.client_map.get(member_connection_id))
+                .collect::<Vec<_>>();
+            let member_client = match member_client_list
+                .iter()
+                .find(|client| client.player.is_some())
+                .or(member_client_list.first())
+            {
+                Some(member_client) => member_client,
+                None => continue,
+            };
+            if member_client.player.is_some() && member_client.room_id == room_id {
+                continue;
+            }
+            let member_connection_id = member_client.connection_id.clone();
+            info!(
+                "party join: {:?} {:?} with {:?}",
+                member_connection_id, member.nickname, connection_id
+            );
+            if self.handle_join(
+                member_connection_id.clone(),
+                member.player_db_id,
+                member.nickname,
+                member.color,
+                room_id.clone(),
+                Some(connection_id.clone()),
+            ) {
+                self.send_system_chat(&member_connection_id, msg.clone());
+            }
+        }
+    }
+
+    fn sync_party(&self, party_id: &Arc<str>) {
+        let party = match self.party_book.party_map.get(party_id) {
+            Some(party) => party,
+            None => return,
+        };
+        let packet = proto_util::party_packet(party.to_proto());
+        for member in party.member_list.iter() {
+            for connection_id in self.account_connection_id_list(member.player_db_id) {
+                self.send_packet(&connection_id, packet.clone());
+            }
+        }
+    }
+
+    fn send_party_chat(&self, party_id: &Arc<str>, msg: Arc<str>) {
+        let party = match self.party_book.party_map.get(party_id) {
+            Some(party) => party,
+            None => return,
+        };
+        for member in party.member_list.iter() {
+            for connection_id in self.account_connection_id_list(member.player_db_id) {
+                self.send_system_chat(&connection_id, msg.clone());
+            }
+        }
+    }
+
+    fn party_connection_id_list(&self, connection_id: &Arc<str>) -> Vec<Arc<str>> {
+        let party = match self
+            .client_map
+            .get(connection_id)
+            .and_then(|client| client.account.as_ref())
+            .and_then(|account| self.party_book.get(account.player_db_id))
+        {
+            Some(party) => party,
+            None => return Vec::new(),
+        };
+        party
+            .member_list
+            .iter()
+            .flat_map(|member| self.account_connection_id_list(member.player_db_id))
+            .collect()
+    }
+
+    fn is_party_protected(
+        &self,
+        connection_id: &Arc<str>,
+        victim_connection_id: &Arc<str>,
+    ) -> bool {
+        let player_db_id = |connection_id: &Arc<str>| {
+            self.client_map
+                .get(connection_id)
+                .and_then(|client| client.player.as_ref())
+                .filter(|player| !player.is_bot)
+                .map(|player| player.db_id)
+        };
+        match (
+            player_db_id(connection_id),
+            player_db_id(victim_connection_id),
+        ) {
+            (Some(player_db_id), Some(victim_player_db_id)) => self
+                .party_book
+                .is_protected(player_db_id, victim_player_db_id),
+            _ => false,
+        }
+    }
+
+    fn is_teammate(&self, connection_id: &Arc<str>, victim_connection_id: &Arc<str>) -> bool {
+        match (
+            self.client_map.get(connection_id),
+            self.client_map.get(victim_connection_id),
+        ) {
+            (Some(client), Some(victim_client)) if client.room_id == victim_client.room_id => {
+                match (client.player.as_ref(), victim_client.player.as_ref()) {
+                    (Some(player), Some(victim)) => {
+                        team::is_teammate(player, victim, self.room_game_config(&client.room_id))
+                    }
+                    _ => false,
+                }
+            }
+            _ => false,
+        }
+    }
+
+    fn player_stats(&self, connection_id: &Arc<str>) -> Arc<str> {
+        let (player, room_id) = match self.client_map.get(connection_id) {
+            Some(client) => match client.player.as_ref() {
+                Some(player) => (player, &client.room_id),
+                None => return "join the game to see your stats".into(),
+            },
+            None => return "join the game to see your stats".into(),
+        };
+        let rank = self
+            .room_player_list(room_id)
+            .filter(|other| other.radius > player.radius)
+            .count()
+            + 1;
+        let player_count = self.room_player_list(room_id).count();
+        let team = match player.team {
+            0 => String::new(),
+            team => format!(", team {}", team::name(team)),
+        };
+        format!(
+            "score {}, radius {:.1}, rank {}/{}{}",
+            util::radius_to_mass(player.radius) as i64,
+            player.radius,
+            rank,
+            player_count,
+            team
+        )
+        .into()
+    }
+
+    fn room_leaderboard(&self, connection_id: &Arc<str>) -> Arc<str> {
+        let room_id = self
+            .client_map
+            .get(connection_id)
+            .map_or(room::MAIN_ROOM_ID.into(), |client| client.room_id.clone());
+        let mut player_list = self.room_player_list(&room_id).collect::<Vec<_>>();
+        player_list.sort_by(|a, b| b.radius.total_cmp(&a.radius));
+        let line_list = player_list
+            .iter()
+            .take(LEADERBOARD_SIZE)
+            .enumerate()
+            .map(|(index, player)| {
+                format!(
+                    "{}. {} {}",
+                    index + 1,
+                    player.nickname,
+                    util::radius_to_mass(player.radius) as i64
+                )
+            })
+            .collect::<Vec<_>>();
+        if line_list.is_empty() {
+            return "no players in the room".into();
+        }
+        line_list.join("\n").into()
+    }
+
+    fn mute(&mut self, target: &admin::Target, duration: Duration, reason: Arc<str>) -> usize {
+        let connection_id_list = self.resolve_target(target);
+        let player_db_id_list = self.resolve_player_db_id(target, &connection_id_list);
+        info!("mute: {:?} {:?} {:?}", player_db_id_list, duration, reason);
+
+        let now = util::now_millis();
+        for player_db_id in player_db_id_list.iter().copied() {
+            let mute = db::Mute {
+                player_id: player_db_id,
+                reason: reason.clone(),
+                muted_until: util::expires_at(now, duration),
+                created_at: now,
+            };
+            self.chat_moderator
+                .mute_map
+                .insert(player_db_id, mute.clone());
+            let _ = self
+                .db_writer_command_sender
+                .send(command::Command::PersistMute { mute });
+        }
+
+        let msg: Arc<str> = format!("you are muted for {}s: {}", duration.as_secs(), reason).into();
+        connection_id_list.iter().for_each(|connection_id| {
+            self.send_system_chat(connection_id, msg.clone());
+        });
+
+        player_db_id_list.len()
+    }
+
+    fn unmute(&mut self, player_db_id: i64) -> usize {
+        info!("unmute: {:?}", player_db_id);
+        let _ = self
+            .db_writer_command_sender
+            .send(command::Command::DeleteMute { player_db_id });
+        self.chat_moderator.mute_map.remove(&player_db_id).is_some() as usize
+    }
+
+    fn check_direction_angle(&mut self, connection_id: &Arc<str>, direction_angle: f64) -> bool {
+        let (player_db_id, nickname) = match self.human_player(connection_id) {
+            Some(player) => (player.db_id, player.nickname.clone()),
+            None => return true,
+        };
+
+        if !direction_angle.is_finite() {
+            let detail = format!("direction_angle {}", direction_angle);
+            self.report_violation(
+                connection_id,
+                player_db_id,
+                nickname,
+                anti_cheat::Violation::BadPacket,
+                detail.into(),
+            );
+            return false;
+        }
+
+        let direction_count = self.anti_cheat.count_direction(player_db_id);
+        let max_direction_count = self.game_config.anti_cheat_max_direction_per_sec;
+        if direction_count <= max_direction_count {
+            return true;
+        }
+        if direction_count == max_direction_count + 1 {
+            let detail = format!(
+                "more than {} direction changes per second",
+                max_direction_count
+            );
+            self.report_violation(
+                connection_id,
+                player_db_id,
+                nickname,
+                anti_cheat::Violation::DirectionFlood,
+                detail.into(),
+            );
+        }
+        false
+    }
+
+    fn check_consume_spore(&mut self, connection_id: &Arc<str>, spore_id: &Arc<str>) -> bool {
+        let room_id = self.client_room_id(connection_id);
+        let player = match self.human_player(connection_id) {
+            Some(player) => player,
+            None => return true,
+        };
+        if self.anti_cheat.is_restricted(player.db_id) {
+            return false;
+        }
+
+        let detail = match self.spore_map.get(spore_id) {
+            Some(spore) if spore.room_id != room_id => return false,
+            Some(spore) => {
+                let distance = (player.x - spore.x).hypot(player.y - spore.y);
+                let max_distance = player.radius
+                    + spore.radius
+                    + self.game_config.anti_cheat_consume_distance_slack;
+                (distance > max_distance).then(|| {
+                    format!(
+                        "spore {} at distance {:.0} > {:.0}",
+                        spore_id, distance, max_distance
+                    )
+                })
+            }
+            None => match self.anti_cheat.spore_tombstone(spore_id) {
+                Some(tombstone) if tombstone.room_id != room_id => return false,
+                Some(_) => None,
+                None => Some(format!("unknown spore {:?}", spore_id)),
+            },
+        };
+
+        match detail {
+            Some(detail) => {
+                let (player_db_id, nickname) = (player.db_id, player.nickname.clone());
+                self.report_violation(
+                    connection_id,
+                    player_db_id,
+                    nickname,
+                    anti_cheat::Violation::ImpossibleConsume,
+                    detail.into(),
+                );
+                false
+            }
+            None => true,
+        }
+    }
+
+    fn check_consume_player(
+        &mut self,
+        connection_id: &Arc<str>,
+        victim_connection_id: &Arc<str>,
+    ) -> bool {
+        let player = match self.human_player(connection_id) {
+            Some(player) => player,
+            None => return connection_id != victim_connection_id,
+        };
+        if self.anti_cheat.is_restricted(player.db_id) {
+            return false;
+        }
+
+        let victim_client = self.client_map.get(victim_connection_id);
+        if victim_client.is_some_and(|victim_client| {
+            Some(&victim_client.room_id)
+                != self
+                    .client_map
+                    .get(connection_id)
+                    .map(|client| &client.room_id)
+        }) {
+            return false;
+        }
+        let victim = victim_client.and_then(|client| client.player.as_ref());
+        let detail = match victim {
+            _ if connection_id == victim_connection_id => Some("consume self".to_string()),
+            Some(victim) => {
+                let player_mass = util::radius_to_mass(player.radius);
+                let victim_mass = util::radius_to_mass(victim.radius);
+                let distance = (player.x - victim.x).hypot(player.y - victim.y);
+                let max_distance = player.radius
+                    + victim.radius
+                    + self.game_config.anti_cheat_consume_distance_slack;
+                if player_mass < victim_mass {
+                    Some(format!(
+                        "victim {} mass {:.0} > {:.0}",
+                        victim_connection_id, victim_mass, player_mass
+                    ))
+                } else if distance > max_distance {
+                    Some(format!(
+                        "victim {} at distance {:.0} > {:.0}",
+                        victim_connection_id, distance, max_distance
+                    ))
+                } else {
+                    None
+                }
+            }
+            None => None,
+        };
+
+        match detail {
+            Some(detail) => {
+                let (player_db_id, nickname) = (player.db_id, player.nickname.clone());
+                self.report_violation(
+                    connection_id,
+                    player_db_id,
+                    nickname,
+                    anti_cheat::Violation::ImpossibleConsume,
+                    detail.into(),
+                );
+                false
+            }
+            None => true,
+        }
+    }
+
+    fn report_violation(
+        &mut self,
+        connection_id: &Arc<str>,
+        player_db_id: i64,
+        nickname: Arc<str>,
+        violation: anti_cheat::Violation,
+        detail: Arc<str>,
+    ) {
+        let (score, action) = self
+            .anti_cheat
+            .report(player_db_id, violation, &self.game_config);
+        warn!(
+            "anti-cheat violation: {:?} {:?} {} {:?}, score {:.1}",
+            player_db_id,
+            nickname,
+            violation.as_str(),
+            detail,
+            score
+        );
+        metrics::ANTI_CHEAT_VIOLATION
+            .with_label_values(&[violation.as_str()])
+            .inc();
+
+        let _ = self
+            .db_writer_command_sender
+            .send(command::Command::PersistAntiCheatLog {
+                anti_cheat_log: db::AntiCheatLog {
+                    id: 0,
+                    player_id: player_db_id,
+                    nickname: nickname.clone(),
+                    violation: violation.as_str().into(),
+                    detail,
+                    score,
+                    action: action
+                        .map(|action| action.as_str())
+                        .unwrap_or_default()
+                        .into(),
+                    created_at: util::now_millis(),
+                },
+            });
+
+        let action = match action {
+            Some(action) => action,
+            None => return,
+        };
+        warn!(
+            "anti-cheat action: {} {:?} {:?}, score {:.1}",
+            action.as_str(),
+            player_db_id,
+            nickname,
+            score
+        );
+        metrics::ANTI_CHEAT_ACTION
+            .with_label_values(&[action.as_str()])
+            .inc();
+
+        let msg = format!(
+            "anti-cheat {}: {} (suspicion {:.0}, last {})",
+            action.as_str(),
+            nickname,
+            score,
+            violation.as_str()
+        );
+        self.send_moderator_chat(msg.into());
+
+        if action == config::AntiCheatAction::Kick {
+            self.kick(connection_id, "kicked by anti-cheat".into());
+        }
+    }
+
+    fn pardon(&mut self, player_db_id: i64, issuer: Arc<str>) -> usize {
+        info!("pardon: {:?} by {:?}", player_db_id, issuer);
+        let affected = self.anti_cheat.pardon(player_db_id) as usize;
+        let nickname = self
+            .client_map
+            .values()
+            .filter_map(|client| client.player.as_ref())
+            .find(|player| !player.is_bot && player.db_id == player_db_id)
+            .map(|player| player.nickname.clone())
+            .unwrap_or_default();
+        let _ = self
+            .db_writer_command_sender
+            .send(command::Command::PersistAntiCheatLog {
+                anti_cheat_log: db::AntiCheatLog {
+                    id: 0,
+                    player_id: player_db_id,
+                    nickname,
+                    violation: "".into(),
+                    detail: format!("by {}", issuer).into(),
+                    score: 0.0,
+                    action: "pardon".into(),
+                    created_at: util::now_millis(),
+                },
+            });
+        affected
+    }
+
+    fn human_player(&self, connection_id: &Arc<str>) -> Option<&player::Player> {
+        self.client_map
+            .get(connection_id)?
+            .player
+            .as_ref()
+            .filter(|player| !player.is_bot)
+    }
+
+    fn send_spore_batch(&self, connection_id: &Arc<str>, x: f64, y: f64) {
+        let client = match self.client_map.get(connection_id) {
+            Some(client) => client,
+            None => return,
+        };
+
+        let mut spore_batch = self
+            .spore_map
+            .values()
+            .filter(|spore| spore.room_id == client.room_id)
+            .cloned()
+            .collect::<Vec<_>>();
+        spore_batch
+            .sort_by_cached_key(|spore| ((x - spore.x).powi(2) + (y - spore.y).powi(2)) as i64);
+
+        let _ = client
+            .client_agent_command_sender
+            .send(command::Command::UpdateSporeBatch { spore_batch });
+    }
+
+    fn leave(&mut self, connection_id: Arc<str>, reason: Arc<str>) {
+        if let Some(client) = self.client_map.shift_remove(&connection_id) {
+            if let Some(player) = client.player {
+                self.chat_moderator.forget(player.db_id);
+                self.anti_cheat.forget(player.db_id, &self.game_config);
+            }
+            if let Some(account) = client.account
+                && self
+                    .account_connection_id_list(account.player_db_id)
+                    .is_empty()
+            {
+                self.leave_party(&account);
+            }
+        }
+
+        let packet = proto_util::disconnect_packet(connection_id, reason);
         self.broadcast_packet(&packet);
     }
+
+    fn record(&mut self, data: proto::replay_input::Data) {
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.record(data);
+        }
+    }
+
+    fn record_game_config(&mut self) {
+        if self.recorder.is_none() {
+            return;
+        }
+        match toml::to_string(&self.game_config) {
+            Ok(game_config) => {
+                self.record(proto::replay_input::Data::GameConfig(
+                    proto::ReplayGameConfig { game_config },
+                ));
+            }
+            Err(e) => error!("record game config error: {:?}", e),
+        }
+    }
+
+    fn start_replay(&mut self) -> Result<Arc<str>> {
+        if let Some(recorder) = self.recorder.as_ref() {
+            bail!("replay already recording: {:?}", recorder.path);
+        }
+
+        let header = proto::ReplayHeader {
+            version: replay::VERSION,
+            rng_seed: self.rng_seed,
+            started_at: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)?
+                .as_millis() as i64,
+        };
+        let mut recorder = replay::Recorder::start(
+            &self.server_config.replay_directory,
+            self.server_config.replay_snapshot_interval_step,
+            header,
+        )?;
+        recorder.write_snapshot(self.replay_snapshot());
+        info!("replay recording: {:?}", recorder.path);
+
+        let path = recorder.path.clone();
+        self.recorder = Some(recorder);
+
+        Ok(path)
+    }
+
+    async fn stop_replay(&mut self) -> Option<Arc<str>> {
+        let recorder = self.recorder.take()?;
+        let path = recorder.finish(self.step_count).await;
+        info!("replay stopped: {:?}", path);
+        Some(path)
+    }
+
+    pub fn replay_snapshot(&self) -> proto::ReplaySnapshot {
+        let rng_word_pos = self.rng.get_word_pos();
+        proto::ReplaySnapshot {
+            step: self.step_count,
+            rng_seed: self.rng.get_seed().to_vec(),
+            rng_word_pos_high: (rng_word_pos >> 64) as u64,
+            rng_word_pos_low: rng_word_pos as u64,
+            spore_count: self.spore_count,
+            bot_count: self.bot_count as u64,
+            game_config: toml::to_string(&self.game_config).unwrap_or_default(),
+            client_list: self
+                .client_map
+                .values()
+                .map(|client| proto::ReplayClient {
+                    connection_id: client.connection_id.to_string(),
+                    player: client.player.as_ref().map(replay::replay_player),
+                    room_id: client.room_id.to_string(),
+                })
+                .collect(),
+            spore_list: self.spore_map.values().map(replay::replay_spore).collect(),
+            room_list: self.room_map.values().map(replay::replay_room).collect(),
+            round_list: self.round_map.values().map(replay::replay_round).collect(),
+        }
+    }
+
+    pub fn restore_replay_snapshot(&mut self, snapshot: proto::ReplaySnapshot) -> Result<()> {
+        let rng_seed = <ChaCha8Rng as SeedableRng>::Seed::try_from(snapshot.rng_seed.as_slice())
+            .map_err(|_| anyhow!("invalid rng seed length {}", snapshot.rng_seed.len()))?;
+        self.game_config = toml::from_str(&snapshot.game_config)?;
+        self.rng = ChaCha8Rng::from_seed(rng_seed);
+        self.rng.set_word_pos(
+            ((snapshot.rng_word_pos_high as u128) << 64) | snapshot.rng_word_pos_low as u128,
+        );
+        self.step_count = snapshot.step;
+        self.spore_count = snapshot.spore_count;
+        self.bot_count = snapshot.bot_count as usize;
+
+        self.client_map = snapshot
+            .client_list
+            .into_iter()
+            .map(|replay_client| {
+                let connection_id: Arc<str> = replay_client.connection_id.into();
+                let player = replay_client
+                    .player
+                    .map(|replay_player| replay::player(connection_id.clone(), replay_player));
+                let mut client = Client::detached(connection_id.clone(), player);
+                client.room_id = room::room_id(replay_client.room_id);
+                (connection_id, client)
+            })
+            .collect();
+        self.room_map = snapshot
+            .room_list
+            .into_iter()
+            .map(|replay_room| {
+                let room = replay::room(replay_room, &self.game_config)?;
+                Ok((room.id.clone(), room))
+            })
+            .collect::<Result<_>>()?;
+        self.round_map = snapshot
+            .round_list
+            .into_iter()
+            .map(|replay_round| {
+                let round = replay::round(replay_round);
+                (round.room_id.clone(), round)
+            })
+            .collect();
+        self.spore_map = snapshot
+            .spore_list
+            .into_iter()
+            .map(|replay_spore| {
+                let spore = replay::spore(replay_spore);
+                (spore.id.clone(), spore)
+            })
+            .collect();
+
+        Ok(())
+    }
+
+    pub fn apply_replay_input(&mut self, data: proto::replay_input::Data) {
+        match data {
+            proto::replay_input::Data::Register(register) => {
+                let connection_id: Arc<str> = register.connection_id.into();
+                self.client_map
+                    .insert(connection_id.clone(), Client::detached(connection_id, None));
+            }
+            proto::replay_input::Data::Leave(leave) => {
+                self.leave(leave.connection_id.into(), leave.reason.into());
+            }
+            proto::replay_input::Data::Join(join) => {
+                let anchor_connection_id: Option<Arc<str>> =
+                    (!join.anchor_connection_id.is_empty())
+                        .then(|| join.anchor_connection_id.into());
+                self.join(
+                    join.connection_id.into(),
+                    join.player_db_id,
+                    join.nickname.into(),
+                    join.color,
+                    room::room_id(join.room_id),
+                    anchor_connection_id.as_ref(),
+                );
+            }
+            proto::replay_input::Data::Chat(chat) => {
+                let packet = if chat.connection_id.is_empty() {
+                    proto_util::system_chat_packet(chat.msg.into())
+                } else {
+                    proto_util::chat_packet(
+                        proto::ChatChannel::Global,
+                        chat.connection_id.into(),
+                        chat.nickname.into(),
+                        "".into(),
+                        chat.msg.into(),
+                    )
+                };
+                self.broadcast_packet(&packet);
+            }
+            proto::replay_input::Data::DirectionAngle(direction_angle) => {
+                if let Some(client) = self
+                    .client_map
+                    .get_mut(direction_angle.connection_id.as_str())
+                    && let Some(player) = client.player.as_mut()
+                {
+                    player.direction_angle = direction_angle.direction_angle;
+                }
+            }
+            proto::replay_input::Data::ConsumeSpore(consume_spore) => {
+                self.consume_spore(
+                    consume_spore.connection_id.into(),
+                    consume_spore.spore_id.into(),
+                );
+            }
+            proto::replay_input::Data::ConsumePlayer(consume_player) => {
+                self.consume_player(
+                    consume_player.connection_id.into(),
+                    consume_player.victim_connection_id.into(),
+                );
+            }
+            proto::replay_input::Data::Rush(rush) => {
+                self.rush(rush.connection_id.into());
+            }
+            proto::replay_input::Data::SpawnSpore(spawn_spore) => {
+                self.spawn_spore(room::room_id(spawn_spore.room_id));
+            }
+            proto::replay_input::Data::BalanceBot(_) => {
+                self.balance_bot();
+            }
+            proto::replay_input::Data::GameConfig(game_config) => {
+                match toml::from_str(&game_config.game_config) {
+                    Ok(game_config) => {
+                        self.set_game_config(game_config);
+                    }
+                    Err(e) => error!("replay game config error: {:?}", e),
+                }
+            }
+            proto::replay_input::Data::CreateRoom(replay_room) => {
+                match replay::room(replay_room, &self.game_config) {
+                    Ok(room) => self.open_room(room),
+                    Err(e) => error!("replay room error: {:?}", e),
+                }
+            }
+            proto::replay_input::Data::CloseRoom(close_room) => {
+                self.close_room(&close_room.room_id);
+            }
+        }
+    }
+
+    fn consume_spore(&mut self, connection_id: Arc<str>, spore_id: Arc<str>) {
+        if let (Some(client), Some(spore)) = (
+            self.client_map.get_mut(&connection_id),
+            self.spore_map.get_mut(&spore_id),
+        ) && let Some(player) = client.player.as_mut()
+            && spore.room_id == client.room_id
+        {
+            let is_close = util::check_distance_is_close(
+                player.x,
+                player.y,
+                player.radius,
+                spore.x,
+                spore.y,
+                spore.radius,
+            );
+
+            if !is_close {
+                warn!("consume spore error, distance too far");
+                return;
+            }
+
+            let spore_mass = util::radius_to_mass(spore.radius);
+            player.increase_mass(spore_mass);
+
+            self.spore_map.shift_remove(&spore_id);
+            self.anti_cheat
+                .tombstone_spore(spore_id.clone(), client.room_id.clone());
+
+            let current_score = util::radius_to_mass(player.radius) as i64;
+
+            let is_ranked = !player.is_bot
+                && self
+                    .room_map
+                    .get(&client.room_id)
+                    .is_none_or(|room| room.settings.is_ranked(&self.game_config))
+                && self
+                    .round_map
+                    .get(&client.room_id)
+                    .is_none_or(|round| round.state == proto::RoundState::Running);
+
+            let client_agent_command_sender = client.client_agent_command_sender.clone();
+            let room_id = client.room_id.clone();
+
+            self.broadcast_room_packet(
+                &room_id,
+                &proto_util::consume_spore_packet(connection_id, spore_id),
+            );
+
+            if is_ranked {
+                let _ = client_agent_command_sender
+                    .send(command::Command::SyncPlayerBestScore { current_score });
+            }
+        }
+    }
+
+    fn consume_player(&mut self, connection_id: Arc<str>, victim_connection_id: Arc<str>) {
+        if let [Some(player_client), Some(victim_client)] = self
+            .client_map
+            .get_disjoint_mut([&connection_id, &victim_connection_id])
+            && player_client.room_id == victim_client.room_id
+            && let (Some(player), Some(victim)) =
+                (&mut player_client.player, &mut victim_client.player)
+        {
+            let game_config =
+                room::game_config(&self.room_map, &self.game_config, &player_client.room_id);
+            if team::is_teammate(player, victim, game_config) {
+                warn!("consume player error, teammate");
+                return;
+            }
+
+            let player_mass = util::radius_to_mass(player.radius);
+            let victim_mass = util::radius_to_mass(victim.radius);
+
+            if player_mass < victim_mass * game_config.eat_ratio {
+                warn!("consume player error, too small");
+                return;
+            }
+
+            let is_close = util::check_distance_is_close(
+                player.x,
+                player.y,
+                player.radius,
+                victim.x,
+                victim.y,
+                victim.radius,
+            );
+
+            if !is_close {
+                warn!("consume player error, distance too far");
+                return;
+            }
+
+            player.increase_mass(victim_mass);
+
+            victim.respawn(&mut self.rng, game_config);
+        }
+    }
+
+    fn rush(&mut self, connection_id: Arc<str>) {
+        if let Some(client) = self.client_map.get_mut(&connection_id)
+            && let Some(player) = client.player.as_mut()
+        {
+            let game_config = room::game_config(&self.room_map, &self.game_config, &client.room_id);
+            if player.radius < game_config.rush_min_radius {
+                return;
+            }
+            if player.rush_elapsed.is_some() {
+                return;
+            }
+            let player_mass = util::radius_to_mass(player.radius);
+            let drop_mass = player_mass * game_config.rush_cost_ratio;
+            if let Some(mass) = player.try_drop_mass(drop_mass) {
+                player.rush(game_config);
+
+                self.spore_count += 1;
+                let spore_id = self.spore_count.to_string().into();
+                let mut spore = spore::Spore::random(
+                    spore_id,
+                    client.room_id.clone(),
+                    &mut self.rng,
+                    game_config,
+                );
+                spore.x = player.x;
+                spore.y = player.y;
+                spore.radius = util::mass_to_radius(mass);
+
+                let packet = proto_util::update_spore_pack(&spore);
+
+                self.spore_map.insert(spore.id.clone(), spore);
+
+                let room_id = client.room_id.clone();
+                self.broadcast_room_packet(&room_id, &packet);
+            }
+        }
+    }
+
+    fn send_packet(&self, connection_id: &Arc<str>, packet: proto::Packet) {
+        if let Some(client) = self.client_map.get(connection_id) {
+            let _ = client
+                .client_agent_command_sender
+                .send(command::Command::SendPacket { packet });
+        }
+    }
+
+    fn send_system_chat(&self, connection_id: &Arc<str>, msg: Arc<str>) {
+        self.send_packet(connection_id, proto_util::system_chat_packet(msg));
+    }
+
+    fn send_moderator_chat(&self, msg: Arc<str>) {
+        let packet = proto_util::system_chat_packet(msg);
+        self.client_map
+            .values()
+            .filter(|client| client.role.can_moderate())
+            .for_each(|client| self.send_packet(&client.connection_id, packet.clone()));
+    }
+
+    fn broadcast_packet(&self, packet: &proto::Packet) {
+        let bytes = packet.encode_to_vec().into();
+        self.broadcast_bytes(proto_util::packet_type(packet), bytes, None);
+    }
+
+    fn broadcast_room_packet(&self, room_id: &str, packet: &proto::Packet) {
+        let bytes = packet.encode_to_vec().into();
+        self.broadcast_bytes(proto_util::packet_type(packet), bytes, Some(room_id));
+    }
+
+    fn broadcast_bytes(&self, packet_type: &'static str, bytes: Bytes, room_id: Option<&str>) {
+        self.client_map
+            .values()
+            .filter(|client| {
+                client.spectator.is_some()
+                    || client.player.as_ref().is_some_and(|player| !player.is_bot)
+            })
+            .filter(|client| room_id.is_none_or(|room_id| &*client.room_id == room_id))
+            .for_each(|client| {
+                let bytes = bytes.clone();
+                let _ = client
+                    .client_agent_command_sender
+                    .send(command::Command::SendBytes { packet_type, bytes });
+            });
+    }
+
+    fn spawn_spore(&mut self, room_id: Arc<str>) {
+        self.spore_count += 1;
+        let spore_id = self.spore_count.to_string().into();
+        let game_config = room::game_config(&self.room_map, &self.game_config, &room_id);
+        let spore = spore::Spore::random(spore_id, room_id.clone(), &mut self.rng, game_config);
+
+        let packet = proto_util::update_spore_pack(&spore);
+
+        self.spore_map.insert(spore.id.clone(), spore);
+
+        self.broadcast_room_packet(&room_id, &packet);
+    }
+
+    fn spawn_room_spore(&mut self) {
+        let room_id_list = std::iter::once(Arc::from(room::MAIN_ROOM_ID))
+            .chain(self.room_map.keys().cloned())
+            .collect::<Vec<_>>();
+        for room_id in room_id_list {
+            if self.room_spore_count(&room_id) < self.room_game_config(&room_id).max_spore_count {
+                self.record(proto::replay_input::Data::SpawnSpore(
+                    proto::ReplaySpawnSpore {
+                        room_id: room_id.to_string(),
+                    },
+                ));
+                self.spawn_spore(room_id);
+            }
+        }
+    }
+
+    fn balance_bot(&mut self) {
+        let player_count = self.room_player_list(room::MAIN_ROOM_ID).count();
+
+        if player_count < self.game_config.bot_target_population {
+            self.spawn_bot();
+        } else if player_count > self.game_config.bot_target_population {
+            self.despawn_bot();
+        }
+    }
+
+    fn sync_team(&mut self) {
+        let room_id_list = std::iter::once(Arc::from(room::MAIN_ROOM_ID))
+            .chain(self.room_map.keys().cloned())
+            .collect::<Vec<_>>();
+        for room_id in room_id_list {
+            let game_config = self.room_game_config(&room_id);
+            if game_config.team_count == 0 {
+                continue;
+            }
+            let score_list =
+                team::score_list(self.room_player_list(&room_id), game_config.team_count);
+            match team::winner(&score_list, game_config) {
+                Some(winner_team) if !game_config.round_mode => {
+                    let msg = team::describe_result(winner_team, &score_list);
+                    info!("team match end: {:?} {}", room_id, msg);
+                    self.broadcast_room_packet(
+                        &room_id,
+                        &proto_util::team_result_packet(winner_team, score_list),
+                    );
+                    self.broadcast_room_packet(&room_id, &proto_util::system_chat_packet(msg));
+                    self.respawn_room_player(&room_id);
+                }
+                _ if team::is_score_step(self.step_count, game_config) => {
+                    self.broadcast_room_packet(
+                        &room_id,
+                        &proto_util::team_score_batch_packet(score_list),
+                    );
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn sync_round(&mut self) {
+        let room_id_list = std::iter::once(Arc::from(room::MAIN_ROOM_ID))
+            .chain(self.room_map.keys().cloned())
+            .collect::<Vec<_>>();
+        self.round_map
+            .retain(|room_id, _| room_id_list.contains(room_id));
+        for room_id in room_id_list {
+            let game_config = room::game_config(&self.room_map, &self.game_config, &room_id);
+            if !game_config.round_mode {
+                self.round_map.shift_remove(&room_id);
+                continue;
+            }
+            let player_count = self
+                .client_map
+                .values()
+                .filter(|client| client.room_id == room_id)
+                .filter_map(|client| client.player.as_ref())
+                .filter(|player| !player.is_bot)
+                .count();
+            let round = self
+                .round_map
+                .entry(room_id.clone())
+                .or_insert_with(|| round::Round::new(room_id.clone()));
+            let next_state = round.step(player_count, game_config);
+            let is_sync = next_state.is_some() || round::is_sync_step(self.step_count, game_config);
+            let packet = proto_util::round_packet(round.to_proto(game_config));
+
+            match next_state {
+                Some(proto::RoundState::Running) => {
+                    round.started_at = util::now_millis();
+                    info!("round start: {:?} {}", room_id, round.round_number);
+                    self.respawn_room_player(&room_id);
+                }
+                Some(proto::RoundState::Results) => self.end_round(&room_id),
+                Some(proto::RoundState::Reset) => self.reset_room(&room_id),
+                _ => {}
+            }
+            if is_sync {
+                self.broadcast_room_packet(&room_id, &packet);
+            }
+        }
+    }
+
+    fn end_round(&mut self, room_id: &Arc<str>) {
+        let (round_number, started_at) = match self.round_map.get(room_id) {
+            Some(round) => (round.round_number, round.started_at),
+            None => return,
+        };
+        let game_config = self.room_game_config(room_id);
+        let score_list = round::score_list(
+            self.room_player_list(room_id)
+                .filter(|player| !player.is_bot),
+        );
+        let player_count = self
+            .room_player_list(room_id)
+            .filter(|player| !player.is_bot)
+            .count();
+        let msg = round::describe_result(round_number, &score_list);
+        info!("round end: {:?} {}", room_id, msg);
+
+        let mut winner_team = 0;
+        if game_config.team_count > 0 {
+            let team_score_list =
+                team::score_list(self.room_player_list(room_id), game_config.team_count);
+            if let Some(team) = team::leader(&team_score_list) {
+                winner_team = team;
+                self.broadcast_room_packet(
+                    room_id,
+                    &proto_util::team_result_packet(team, team_score_list),
+                );
+            }
+        }
+
+        let winner = score_list.first();
+        let winner_player_id = winner
+            .and_then(|winner| self.client_map.get(winner.connection_id.as_str()))
+            .and_then(|client| client.player.as_ref())
+            .map_or(0, |player| player.db_id);
+        let round_result = db::RoundResult {
+            id: 0,
+            room_id: room_id.clone(),
+            round_number: round_number as i64,
+            winner_player_id,
+            winner_nickname: winner.map_or("", |winner| &winner.nickname).into(),
+            winner_score: winner.map_or(0, |winner| winner.score),
+            winner_team: winner_team as i64,
+            player_count: player_count as i64,
+            started_at,
+            ended_at: util::now_millis(),
+        };
+        let _ = self
+            .db_writer_command_sender
+            .send(command::Command::PersistRoundResult { round_result });
+
+        self.broadcast_room_packet(
+            room_id,
+            &proto_util::round_result_packet(round_number, score_list),
+        );
+        self.broadcast_room_packet(room_id, &proto_util::system_chat_packet(msg));
+    }
+
+    fn reset_room(&mut self, room_id: &Arc<str>) {
+        info!("round reset: {:?}", room_id);
+        self.respawn_room_player(room_id);
+        let spore_id_list = self
+            .spore_map
+            .values()
+            .filter(|spore| spore.room_id == *room_id)
+            .map(|spore| spore.id.clone())
+            .collect::<Vec<_>>();
+        for spore_id in spore_id_list {
+            self.spore_map.shift_remove(&spore_id);
+            self.anti_cheat
+                .tombstone_spore(spore_id.clone(), room_id.clone());
+            self.broadcast_room_packet(
+                room_id,
+                &proto_util::consume_spore_packet("".into(), spore_id),
+            );
+        }
+        for _ in 0..self.room_game_config(room_id).max_spore_count {
+            self.spawn_spore(room_id.clone());
+        }
+    }
+
+    fn respawn_room_player(&mut self, room_id: &str) {
+        let game_config = room::game_config(&self.room_map, &self.game_config, room_id);
+        for client in self.client_map.values_mut() {
+            if &*client.room_id == room_id
+                && let Some(player) = client.player.as_mut()
+            {
+                player.respawn(&mut self.rng, game_config);
+            }
+        }
+    }
+
+    fn spawn_bot(&mut self) {
+        self.bot_count += 1;
+
+        let connection_id: Arc<str> = format!("bot-{}", self.bot_count).into();
+        let nickname = bot::random_nickname(&mut self.rng, self.bot_count);
+        let color = bot::random_color(&mut self.rng);
+        info!("spawn bot: {:?} {:?}", connection_id, nickname);
+
+        let mut player = player::Player::random(
+            &mut self.rng,
+            0,
+            connection_id.clone(),
+            nickname,
+            color,
+            &self.game_config,
+        );
+        player.is_bot = true;
+        team::assign(
+            &mut player,
+            self.room_player_list(room::MAIN_ROOM_ID),
+            self.game_config.team_count,
+        );
+
+        let client = Client::detached(connection_id.clone(), Some(player));
+        self.client_map.insert(connection_id, client);
+    }
+
+    fn despawn_bot(&mut self) {
+        let connection_id = match self
+            .client_map
+            .values()
+            .filter_map(|client| client.player.as_ref())
+            .filter(|player| player.is_bot)
+            .min_by(|a, b| a.radius.total_cmp(&b.radius))
+        {
+            Some(player) => player.connection_id.clone(),
+            None => return,
+        };
+        info!("despawn bot: {:?}", connection_id);
+
+        self.leave(connection_id, "despawn bot".into());
+    }
+
+    fn think_bot(&mut self) -> Vec<(Arc<str>, bot::Decision)> {
+        let decision_list = self
+            .client_map
+            .values()
+            .filter_map(|client| client.player.as_ref())
+            .filter(|player| player.is_bot)
+            .map(|bot| {
+                let decision = bot::think(
+                    &mut self.rng,
+                    bot,
+                    self.client_map
+                        .values()
+                        .filter(|client| &*client.room_id == room::MAIN_ROOM_ID)
+                        .filter_map(|client| client.player.as_ref())
+                        .filter(|player| !team::is_teammate(bot, player, &self.game_config)),
+                    self.spore_map
+                        .values()
+                        .filter(|spore| &*spore.room_id == room::MAIN_ROOM_ID),
+                    &self.game_config,
+                );
+                (bot.connection_id.clone(), decision)
+            })
+            .collect::<Vec<_>>();
+
+        for (connection_id, decision) in decision_list.iter() {
+            if let Some(client) = self.client_map.get_mut(connection_id)
+                && let Some(bot) = client.player.as_mut()
+            {
+                bot.direction_angle = decision.direction_angle;
+            }
+
+            if decision.rush {
+                self.rush(connection_id.clone());
+            }
+        }
+
+        decision_list
+    }
+
+    fn collide_bot(&mut self, decision_list: Vec<(Arc<str>, bot::Decision)>) {
+        for (connection_id, decision) in decision_list {
+            let (bot_x, bot_y, bot_radius) = match self
+                .client_map
+                .get(&connection_id)
+                .and_then(|client| client.player.as_ref())
+            {
+                Some(bot) => (bot.x, bot.y, bot.radius),
+                None => continue,
+            };
+
+            let close_spore_id_list = self
+                .spore_map
+                .values()
+                .filter(|spore| &*spore.room_id == room::MAIN_ROOM_ID)
+                .filter(|spore| {
+                    util::check_distance_is_close(
+                        bot_x,
+                        bot_y,
+                        bot_radius,
+                        spore.x,
+                        spore.y,
+                        spore.radius,
+                    )
+                })
+                .map(|spore| spore.id.clone())
+                .collect::<Vec<_>>();
+            for spore_id in close_spore_id_list {
+                self.consume_spore(connection_id.clone(), spore_id);
+            }
+
+            if let bot::Behavior::Hunt { prey_connection_id } = decision.behavior
+                && let Some(prey) = self
+                    .client_map
+                    .get(&prey_connection_id)
+                    .and_then(|client| client.player.as_ref())
+                && util::check_distance_is_close(
+                    bot_x,
+                    bot_y,
+                    bot_radius,
+                    prey.x,
+                    prey.y,
+                    prey.radius,
+                )
+            {
+                self.consume_player(connection_id, prey_connection_id);
+            }
+        }
+    }
+
+    fn step_player(&mut self) -> Vec<(Arc<str>, proto::Packet)> {
+        let mut spore_packet_list = vec![];
+
+        for client in self.client_map.values_mut() {
+            let player = match client.player.as_mut() {
+                Some(player) => player,
+                None => continue,
+            };
+            let game_config = room::game_config(&self.room_map, &self.game_config, &client.room_id);
+            player.step(game_config);
+
+            let drop_mass_probability = player.radius * game_config.decay_probability_per_radius;
+            if self.rng.random::<f64>() < drop_mass_probability {
+                let drop_radius = (game_config.decay_drop_radius_base
+                    + player.radius * game_config.decay_drop_radius_per_radius)
+                    .min(game_config.decay_drop_radius_max);
+                let drop_mass = util::radius_to_mass(drop_radius);
+                if let Some(mass) = player.try_drop_mass(drop_mass) {
+                    self.spore_count += 1;
+                    let spore_id = self.spore_count.to_string().into();
+                    let mut spore = spore::Spore::random(
+                        spore_id,
+                        client.room_id.clone(),
+                        &mut self.rng,
+                        game_config,
+                    );
+                    spore.x = player.x;
+                    spore.y = player.y;
+                    spore.radius = util::mass_to_radius(mass);
+
+                    let packet = proto_util::update_spore_pack(&spore);
+                    spore_packet_list.push((client.room_id.clone(), packet));
+
+                    self.spore_map.insert(spore.id.clone(), spore);
+                }
+            }
+        }
+
+        spore_packet_list
+    }
+
+    fn sync_spectator(&mut self) {
+        let top_connection_id = spectator::top_player(self.room_player_list(room::MAIN_ROOM_ID))
+            .map(|player| player.connection_id.clone());
+
+        let joined_connection_id_set = self
+            .room_player_list(room::MAIN_ROOM_ID)
+            .map(|player| player.connection_id.clone())
+            .collect::<HashSet<_>>();
+
+        for client in self.client_map.values_mut() {
+            let spectator = match client.spectator.as_mut() {
+                Some(spectator) => spectator,
+                None => continue,
+            };
+
+            if let spectator::SpectateMode::FollowPlayer { connection_id } = &spectator.mode
+                && !joined_connection_id_set.contains(connection_id)
+            {
+                spectator.mode = spectator::SpectateMode::FollowTop;
+            }
+
+            let target_connection_id = match &spectator.mode {
+                spectator::SpectateMode::FollowTop => top_connection_id.clone(),
+                spectator::SpectateMode::FollowPlayer { connection_id } => {
+                    Some(connection_id.clone())
+                }
+                spectator::SpectateMode::FreeRoam => None,
+            };
+            if spectator.target_connection_id == target_connection_id {
+                continue;
+            }
+            spectator.target_connection_id = target_connection_id.clone();
+
+            let packet = proto_util::spectate_target_packet(target_connection_id);
+            let _ = client
+                .client_agent_command_sender
+                .send(command::Command::SendPacket { packet });
+        }
+    }
+
+    fn sync_player(&self) {
+        let room_id_list = std::iter::once(room::MAIN_ROOM_ID)
+            .chain(self.room_map.keys().map(|room_id| &**room_id))
+            .collect::<Vec<_>>();
+        for room_id in room_id_list {
+            let player_list = self.room_player_list(room_id).collect::<Vec<_>>();
+            if player_list.is_empty() && room_id != room::MAIN_ROOM_ID {
+                continue;
+            }
+
+            let packet = proto_util::update_player_batch_packet(&player_list);
+            self.broadcast_room_packet(room_id, &packet);
+        }
+    }
+}
+
+fn config_modified() -> Option<SystemTime> {
+    std::fs::metadata(config::Config::path())
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn test_hub(config: config::Config) -> Hub {
+        let db = db::Db::connect("memory:").await.unwrap();
+        let (db_writer_command_sender, _) = unbounded_channel::<command::Command>();
+        let mut hub = Hub::new(db, config, db_writer_command_sender);
+
+        for _ in 0..hub.game_config.max_spore_count {
+            hub.spawn_spore(room::MAIN_ROOM_ID.into());
+        }
+        for _ in 0..4 {
+            hub.spawn_bot();
+        }
+
+        hub
+    }
+
+    async fn simulate(rng_seed: u64) -> proto::ReplaySnapshot {
+        let mut config = config::Config::default();
+        config.server.rng_seed = rng_seed;
+        let mut hub = test_hub(config).await;
+
+        let connection_id: Arc<str> = "test".into();
+        hub.apply_replay_input(proto::replay_input::Data::Register(proto::ReplayRegister {
+            connection_id: connection_id.to_string(),
+        }));
+        hub.handle_command(command::Command::Join {
+            connection_id: connection_id.clone(),
+            player_db_id: 1,
+            nickname: "test".into(),
+            color: 0xff0000,
+        })
+        .await;
+
+        for step in 0..300 {
+            if step % 30 == 0 {
+                hub.handle_command(command::Command::UpdatePlayerDirectionAngle {
+                    connection_id: connection_id.clone(),
+                    direction_angle: step as f64 / 100.0,
+                })
+                .await;
+                hub.spawn_room_spore();
+            }
+            hub.tick(Duration::ZERO, 0);
+        }
+
+        hub.replay_snapshot()
+    }
+
+    #[tokio::test]
+    async fn tick_is_deterministic_for_same_seed() {
+        let a = simulate(42).await;
+        let b = simulate(42).await;
+
+        assert_eq!(a.step, 300);
+        assert_eq!(a.client_list.len(), 5);
+        assert!(a.client_list.iter().all(|client| client.player.is_some()));
+        assert!(!a.spore_list.is_empty());
+        assert_eq!(a.client_list, b.client_list);
+        assert_eq!(a.spore_list, b.spore_list);
+        assert_eq!(a, b);
+
+        let c = simulate(43).await;
+        assert_ne!(a.spore_list, c.spore_list);
+    }
+
+    #[tokio::test]
+    async fn recorded_replay_matches_live_hub() {
+        let replay_directory = std::env::temp_dir().join(format!("agarust-replay-{}", nanoid!()));
+        let mut config = config::Config::default();
+        config.server.rng_seed = 42;
+        config.server.replay_directory = replay_directory.to_string_lossy().into_owned();
+        config.server.replay_snapshot_interval_step = 50;
+        let mut hub = test_hub(config.clone()).await;
+        hub.start_replay().unwrap();
+
+        let (client_agent_command_sender, _) = unbounded_channel::<command::Command>();
+        let (response_sender, response_receiver) = tokio::sync::oneshot::channel();
+        hub.handle_command(command::Command::RegisterClientAgent {
+            socket_addr: SocketAddr::from(([127, 0, 0, 1], 0)),
+            client_agent_command_sender,
+            response_sender,
+        })
+        .await;
+        let connection_id = response_receiver.await.unwrap();
+        hub.handle_command(command::Command::Join {
+            connection_id: connection_id.clone(),
+            player_db_id: 1,
+            nickname: "test".into(),
+            color: 0xff0000,
+        })
+        .await;
+
+        for step in 0..300 {
+            if step % 30 == 0 {
+                hub.handle_command(command::Command::UpdatePlayerDirectionAngle {
+                    connection_id: connection_id.clone(),
+                    direction_angle: step as f64 / 100.0,
+                })
+                .await;
+                hub.spawn_room_spore();
+            }
+            hub.tick(Duration::ZERO, 0);
+        }
+        let live_snapshot = hub.replay_snapshot();
+        let path = hub.stop_replay().await.unwrap();
+
+        let replay = replay::Replay::load(&path).unwrap();
+        assert_eq!(replay.first_step(), 0);
+        assert_eq!(replay.last_step(), 300);
+        assert_eq!(replay.snapshot_list.len(), 7);
+        assert!(replay.frame_list.iter().any(|frame| {
+            frame
+                .input_list
+                .iter()
+                .any(|input| matches!(input.data, Some(proto::replay_input::Data::Join(_))))
+        }));
+
+        let db = db::Db::connect("memory:").await.unwrap();
+        let (db_writer_command_sender, _) = unbounded_channel::<command::Command>();
+        let mut replay_hub = Hub::new(db, config::Config::default(), db_writer_command_sender);
+        replay_hub
+            .restore_replay_snapshot(replay.snapshot_list[0].clone())
+            .unwrap();
+        let mut frame_iter = replay.frame_list.iter().peekable();
+        let mut snapshot_iter = replay.snapshot_list.iter().skip(1).peekable();
+        while replay_hub.step_count < replay.last_step() {
+            replay::play_step(&mut replay_hub, &mut frame_iter, &mut snapshot_iter);
+        }
+        assert!(snapshot_iter.next().is_none());
+        assert_eq!(replay_hub.replay_snapshot(), live_snapshot);
+
+        std::fs::remove_dir_all(replay_directory).unwrap();
+    }
 }
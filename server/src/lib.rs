@@ -1,27 +1,75 @@
+pub mod admin;
+pub mod anti_cheat;
+pub mod ban;
+pub mod bot;
+pub mod chat;
+pub mod cli;
 pub mod client_agent;
 pub mod command;
+pub mod config;
 pub mod db;
+pub mod db_writer;
+pub mod friend;
+pub mod health;
 pub mod hub;
+pub mod metrics;
+pub mod party;
 pub mod player;
 pub mod proto;
 pub mod proto_util;
+pub mod replay;
+pub mod role;
+pub mod room;
+pub mod round;
+pub mod shutdown;
+pub mod spectator;
 pub mod spore;
+pub mod team;
 pub mod util;
 
-use anyhow::Result;
+use anyhow::{Result, bail};
+use futures_util::SinkExt;
+use prost::Message as _;
 use std::{net::SocketAddr, sync::Arc};
-use tokio::{net::TcpStream, sync::mpsc::UnboundedSender};
+use tokio::{
+    net::TcpStream,
+    sync::{mpsc::UnboundedSender, oneshot},
+};
+use tokio_tungstenite::tungstenite::Message;
 
 pub async fn handle_tcp_stream(
     tcp_stream: TcpStream,
     socket_addr: SocketAddr,
     db: db::Db,
     hub_command_sender: UnboundedSender<command::Command>,
+    db_writer_command_sender: UnboundedSender<command::Command>,
 ) -> Result<()> {
-    let ws_stream = tokio_tungstenite::accept_async(tcp_stream).await?;
+    let mut ws_stream = tokio_tungstenite::accept_async(tcp_stream).await?;
 
-    let client_agent =
-        client_agent::ClientAgent::new(ws_stream, socket_addr, db, hub_command_sender).await?;
+    let (response_sender, response_receiver) = oneshot::channel();
+    hub_command_sender.send(command::Command::CheckBan {
+        socket_addr,
+        player_db_id: None,
+        response_sender,
+    })?;
+    if let Some(ban_message) = response_receiver.await? {
+        metrics::BANNED_CONNECTION.inc();
+        let packet = proto_util::disconnect_packet("".into(), ban_message.clone());
+        ws_stream
+            .send(Message::Binary(packet.encode_to_vec().into()))
+            .await?;
+        ws_stream.close(None).await?;
+        bail!("{}", ban_message);
+    }
+
+    let client_agent = client_agent::ClientAgent::new(
+        ws_stream,
+        socket_addr,
+        db,
+        hub_command_sender,
+        db_writer_command_sender,
+    )
+    .await?;
 
     client_agent.run().await;
 
@@ -1,37 +1,121 @@
-const DEFAULT_LOG_DIRECTORY: &str = "./";
-const DEFAULT_LOG_FILE_NAME_PREFIX: &str = "agarust_server.log";
-const DEFAULT_BIND_ADDR: &str = "127.0.0.1:8080";
-const DEFAULT_DATABASE_URL: &str = "sqlite:agarust_db.sqlite";
-
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     dotenv::dotenv().ok();
 
-    let _tracing_guard = init_tracing();
+    let config = agarust_server::config::Config::load()?;
+
+    let _tracing_guard = init_tracing(&config.server);
 
-    let bind_addr = std::env::var("BIND_ADDR").unwrap_or(DEFAULT_BIND_ADDR.to_string());
-    tracing::info!("bind_addr: {:?}", bind_addr);
+    tracing::info!("config: {:?}", config);
+
+    let arg_list = std::env::args().skip(1).collect::<Vec<_>>();
+    if !arg_list.is_empty() {
+        return agarust_server::cli::run(&arg_list, &config.server.database_url).await;
+    }
 
-    let tcp_listener = tokio::net::TcpListener::bind(&bind_addr).await?;
+    let tcp_listener = tokio::net::TcpListener::bind(&config.server.bind_addr).await?;
 
-    let database_url = std::env::var("DATABASE_URL").unwrap_or(DEFAULT_DATABASE_URL.to_string());
-    tracing::info!("database_url: {:?}", database_url);
+    let db = agarust_server::db::Db::new(&config.server.database_url).await?;
 
-    let db = agarust_server::db::Db::new(&database_url).await?;
+    let db_writer = agarust_server::db_writer::DbWriter::new(
+        db.clone(),
+        config.server.best_score_flush_duration(),
+    );
+    let db_writer_command_sender = db_writer.command_sender.clone();
 
-    let hub = agarust_server::hub::Hub::new(db.clone());
+    let db_writer_run_future = db_writer.run();
+    tokio::spawn(db_writer_run_future);
+
+    let hub =
+        agarust_server::hub::Hub::new(db.clone(), config.clone(), db_writer_command_sender.clone());
     let hub_command_sender = hub.command_sender.clone();
+    let heartbeat = hub.heartbeat.clone();
+
+    let health_router = agarust_server::health::router(heartbeat.clone(), db.clone());
+    let is_health_standalone = !config.server.health_bind_addr.is_empty()
+        && config.server.health_bind_addr != config.server.metrics_bind_addr;
+
+    if is_health_standalone {
+        let health_bind_addr = config.server.health_bind_addr.clone();
+        let health_router = health_router.clone();
+        tokio::spawn(async move {
+            let health_result = agarust_server::health::run(&health_bind_addr, health_router).await;
+            tracing::error!("health_result: {:?}", health_result);
+        });
+    }
+
+    if config.server.metrics_bind_addr.is_empty() {
+        tracing::warn!("metrics_bind_addr is empty, metrics disabled");
+    } else {
+        let metrics_bind_addr = config.server.metrics_bind_addr.clone();
+        let extra_router = if is_health_standalone {
+            axum::Router::new()
+        } else {
+            health_router
+        };
+        tokio::spawn(async move {
+            let metrics_result =
+                agarust_server::metrics::run(&metrics_bind_addr, extra_router).await;
+            tracing::error!("metrics_result: {:?}", metrics_result);
+        });
+    }
+
+    if config.server.admin_token.is_empty() {
+        tracing::warn!("admin_token is empty, admin api disabled");
+    } else {
+        let admin_bind_addr = config.server.admin_bind_addr.clone();
+        let admin_token = (*config.server.admin_token).into();
+        let hub_command_sender = hub_command_sender.clone();
+        let db = db.clone();
+        tokio::spawn(async move {
+            let admin_result =
+                agarust_server::admin::run(&admin_bind_addr, admin_token, hub_command_sender, db)
+                    .await;
+            tracing::error!("admin_result: {:?}", admin_result);
+        });
+    }
+
+    #[cfg(unix)]
+    {
+        let hub_command_sender = hub_command_sender.clone();
+        let mut hangup_signal =
+            tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())?;
+        tokio::spawn(async move {
+            while hangup_signal.recv().await.is_some() {
+                tracing::info!("SIGHUP received, reload config");
+                let _ = hub_command_sender.send(agarust_server::command::Command::ReloadConfig {
+                    response_sender: None,
+                });
+            }
+        });
+    }
 
     let hub_run_future = hub.run();
     tokio::spawn(hub_run_future);
 
-    while let Ok((tcp_stream, socket_addr)) = tcp_listener.accept().await {
+    let shutdown_signal = agarust_server::shutdown::signal();
+    tokio::pin!(shutdown_signal);
+
+    heartbeat.set_accepting(true);
+
+    loop {
+        let (tcp_stream, socket_addr) = tokio::select! {
+            accept_result = tcp_listener.accept() => match accept_result {
+                Ok(accept) => accept,
+                Err(e) => {
+                    tracing::error!("tcp_listener accept error: {:?}", e);
+                    break;
+                }
+            },
+            _ = &mut shutdown_signal => break,
+        };
         tracing::info!("tcp_listener accept: {:?}", socket_addr);
         let tcp_stream_future = agarust_server::handle_tcp_stream(
             tcp_stream,
             socket_addr,
             db.clone(),
             hub_command_sender.clone(),
+            db_writer_command_sender.clone(),
         );
         tokio::spawn(async move {
             let tcp_stream_result = tcp_stream_future.await;
@@ -43,15 +127,26 @@ async fn main() -> anyhow::Result<()> {
         });
     }
 
+    heartbeat.set_accepting(false);
+    drop(tcp_listener);
+
+    agarust_server::shutdown::run(
+        hub_command_sender,
+        db_writer_command_sender,
+        config.server.shutdown_countdown(),
+    )
+    .await;
+
     Ok(())
 }
 
-fn init_tracing() -> tracing_appender::non_blocking::WorkerGuard {
-    let log_directory = std::env::var("LOG_DIRECTORY").unwrap_or(DEFAULT_LOG_DIRECTORY.to_string());
+fn init_tracing(
+    server_config: &agarust_server::config::ServerConfig,
+) -> tracing_appender::non_blocking::WorkerGuard {
+    let log_directory = &server_config.log_directory;
     println!("log_directory: {:?}", log_directory);
 
-    let log_file_name_prefix =
-        std::env::var("LOG_FILE_NAME_PREFIX").unwrap_or(DEFAULT_LOG_FILE_NAME_PREFIX.to_string());
+    let log_file_name_prefix = &server_config.log_file_name_prefix;
     println!("log_file_name_prefix: {:?}", log_file_name_prefix);
 
     let file_appender = tracing_appender::rolling::daily(log_directory, log_file_name_prefix);
@@ -0,0 +1,268 @@
+use anyhow::Result;
+use axum::{Router, http::header, response::IntoResponse, routing::get};
+use prometheus::{
+    Encoder, Histogram, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec,
+    TextEncoder, exponential_buckets, register_histogram, register_histogram_vec,
+    register_int_counter, register_int_counter_vec, register_int_gauge, register_int_gauge_vec,
+};
+use std::sync::LazyLock;
+use tracing::info;
+
+pub static CONNECTED_CLIENT: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!(
+        "agarust_connected_clients",
+        "WebSocket clients registered in the hub"
+    )
+    .unwrap()
+});
+
+pub static JOINED_PLAYER: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register_int_gauge_vec!("agarust_joined_players", "Players in the world", &["kind"]).unwrap()
+});
+
+pub static SPECTATOR: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!("agarust_spectators", "Clients spectating the world").unwrap()
+});
+
+pub static SPORE: LazyLock<IntGauge> =
+    LazyLock::new(|| register_int_gauge!("agarust_spores", "Spores in the world").unwrap());
+
+pub static TICK_DURATION: LazyLock<Histogram> = LazyLock::new(|| {
+    register_histogram!(
+        "agarust_tick_duration_seconds",
+        "Time spent in one hub tick",
+        exponential_buckets(0.0001, 2.0, 12).unwrap()
+    )
+    .unwrap()
+});
+
+pub static TICK_PHASE_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        "agarust_tick_phase_duration_seconds",
+        "Time spent in one hub tick phase",
+        &["phase"],
+        exponential_buckets(0.00005, 2.0, 12).unwrap()
+    )
+    .unwrap()
+});
+
+pub static TICK_LATENESS: LazyLock<Histogram> = LazyLock::new(|| {
+    register_histogram!(
+        "agarust_tick_lateness_seconds",
+        "Delay between the scheduled and the actual start of a hub tick",
+        exponential_buckets(0.0001, 2.0, 12).unwrap()
+    )
+    .unwrap()
+});
+
+pub static TICK_OVERRUN: LazyLock<IntCounter> = LazyLock::new(|| {
+    register_int_counter!(
+        "agarust_tick_overruns_total",
+        "Hub ticks that started late or ran over the tick budget"
+    )
+    .unwrap()
+});
+
+pub static HUB_COMMAND_PER_TICK: LazyLock<Histogram> = LazyLock::new(|| {
+    register_histogram!(
+        "agarust_hub_commands_per_tick",
+        "Commands handled by the hub between two ticks",
+        exponential_buckets(1.0, 2.0, 14).unwrap()
+    )
+    .unwrap()
+});
+
+pub static HUB_COMMAND_CHANNEL_DEPTH: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!(
+        "agarust_hub_command_channel_depth",
+        "Commands waiting in the hub command channel"
+    )
+    .unwrap()
+});
+
+pub static PACKET_IN: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "agarust_packets_in_total",
+        "Packets received from clients",
+        &["packet_type"]
+    )
+    .unwrap()
+});
+
+pub static BYTES_IN: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "agarust_bytes_in_total",
+        "Bytes received from clients",
+        &["packet_type"]
+    )
+    .unwrap()
+});
+
+pub static PACKET_OUT: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "agarust_packets_out_total",
+        "Packets sent to clients",
+        &["packet_type"]
+    )
+    .unwrap()
+});
+
+pub static BYTES_OUT: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "agarust_bytes_out_total",
+        "Bytes sent to clients",
+        &["packet_type"]
+    )
+    .unwrap()
+});
+
+pub static DB_QUERY_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        "agarust_db_query_duration_seconds",
+        "Storage call latency",
+        &["query"],
+        exponential_buckets(0.0001, 2.0, 14).unwrap()
+    )
+    .unwrap()
+});
+
+pub static LOGIN: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!("agarust_logins_total", "Login attempts", &["result"]).unwrap()
+});
+
+pub static BANNED_CONNECTION: LazyLock<IntCounter> = LazyLock::new(|| {
+    register_int_counter!(
+        "agarust_banned_connections_total",
+        "Connections refused by an ip ban"
+    )
+    .unwrap()
+});
+
+pub static CHAT_MESSAGE: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!("agarust_chat_messages_total", "Chat messages", &["result"]).unwrap()
+});
+
+pub static ANTI_CHEAT_VIOLATION: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "agarust_anti_cheat_violations_total",
+        "Anti-cheat violations",
+        &["violation"]
+    )
+    .unwrap()
+});
+
+pub static ANTI_CHEAT_ACTION: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "agarust_anti_cheat_actions_total",
+        "Automatic anti-cheat actions",
+        &["action"]
+    )
+    .unwrap()
+});
+
+pub static BEST_SCORE_UPDATE: LazyLock<IntCounter> = LazyLock::new(|| {
+    register_int_counter!(
+        "agarust_best_score_updates_total",
+        "Best score updates merged by the db writer"
+    )
+    .unwrap()
+});
+
+pub static BEST_SCORE_WRITE: LazyLock<IntCounter> = LazyLock::new(|| {
+    register_int_counter!(
+        "agarust_best_score_writes_total",
+        "Best score rows written by the db writer"
+    )
+    .unwrap()
+});
+
+pub static BEST_SCORE_FLUSH_ERROR: LazyLock<IntCounter> = LazyLock::new(|| {
+    register_int_counter!(
+        "agarust_best_score_flush_errors_total",
+        "Failed best score flushes"
+    )
+    .unwrap()
+});
+
+pub static BEST_SCORE_FLUSH_DURATION: LazyLock<Histogram> = LazyLock::new(|| {
+    register_histogram!(
+        "agarust_best_score_flush_duration_seconds",
+        "Best score flush transaction latency",
+        exponential_buckets(0.0001, 2.0, 14).unwrap()
+    )
+    .unwrap()
+});
+
+pub fn init() {
+    LazyLock::force(&CONNECTED_CLIENT);
+    LazyLock::force(&JOINED_PLAYER);
+    LazyLock::force(&SPECTATOR);
+    LazyLock::force(&SPORE);
+    LazyLock::force(&TICK_DURATION);
+    LazyLock::force(&TICK_PHASE_DURATION);
+    LazyLock::force(&TICK_LATENESS);
+    LazyLock::force(&TICK_OVERRUN);
+    LazyLock::force(&HUB_COMMAND_PER_TICK);
+    LazyLock::force(&HUB_COMMAND_CHANNEL_DEPTH);
+    LazyLock::force(&PACKET_IN);
+    LazyLock::force(&BYTES_IN);
+    LazyLock::force(&PACKET_OUT);
+    LazyLock::force(&BYTES_OUT);
+    LazyLock::force(&DB_QUERY_DURATION);
+    LazyLock::force(&LOGIN);
+    LazyLock::force(&CHAT_MESSAGE);
+    LazyLock::force(&BANNED_CONNECTION);
+    LazyLock::force(&ANTI_CHEAT_VIOLATION);
+    LazyLock::force(&ANTI_CHEAT_ACTION);
+    LazyLock::force(&BEST_SCORE_UPDATE);
+    LazyLock::force(&BEST_SCORE_WRITE);
+    LazyLock::force(&BEST_SCORE_FLUSH_ERROR);
+    LazyLock::force(&BEST_SCORE_FLUSH_DURATION);
+}
+
+pub fn record_packet_in(packet_type: &str, bytes_len: usize) {
+    PACKET_IN.with_label_values(&[packet_type]).inc();
+    BYTES_IN
+        .with_label_values(&[packet_type])
+        .inc_by(bytes_len as u64);
+}
+
+pub fn record_packet_out(packet_type: &str, bytes_len: usize) {
+    PACKET_OUT.with_label_values(&[packet_type]).inc();
+    BYTES_OUT
+        .with_label_values(&[packet_type])
+        .inc_by(bytes_len as u64);
+}
+
+pub fn render() -> String {
+    let mut buffer = vec![];
+    if let Err(e) = TextEncoder::new().encode(&prometheus::gather(), &mut buffer) {
+        tracing::error!("metrics encode error: {:?}", e);
+    }
+    String::from_utf8(buffer).unwrap_or_default()
+}
+
+pub fn router() -> Router {
+    Router::new().route("/metrics", get(metrics))
+}
+
+pub async fn run(bind_addr: &str, extra_router: Router) -> Result<()> {
+    init();
+
+    let tcp_listener = tokio::net::TcpListener::bind(bind_addr).await?;
+    info!("metrics listening on {:?}", bind_addr);
+
+    axum::serve(tcp_listener, router().merge(extra_router)).await?;
+
+    Ok(())
+}
+
+async fn metrics() -> impl IntoResponse {
+    (
+        [(
+            header::CONTENT_TYPE,
+            TextEncoder::new().format_type().to_string(),
+        )],
+        render(),
+    )
+}
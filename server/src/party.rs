@@ -0,0 +1,245 @@
+use crate::*;
+use hashbrown::{HashMap, HashSet};
+use nanoid::nanoid;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PartyAction {
+    Invite { nickname: Arc<str> },
+    Accept { nickname: Arc<str> },
+    Leave,
+    Kick { nickname: Arc<str> },
+    Protect { friendly_fire_protection: bool },
+    Info,
+}
+
+#[derive(Debug)]
+pub struct Party {
+    pub id: Arc<str>,
+    pub leader_player_db_id: i64,
+    pub member_list: Vec<hub::Account>,
+    pub friendly_fire_protection: bool,
+}
+
+#[derive(Debug)]
+pub struct Invite {
+    pub leader: hub::Account,
+    pub target_set: HashSet<i64>,
+}
+
+impl Party {
+    pub fn leader(&self) -> Option<&hub::Account> {
+        self.member_list
+            .iter()
+            .find(|member| member.player_db_id == self.leader_player_db_id)
+    }
+
+    pub fn find_member(&self, nickname: &str) -> Option<&hub::Account> {
+        self.member_list
+            .iter()
+            .find(|member| member.nickname.eq_ignore_ascii_case(nickname))
+    }
+
+    pub fn to_proto(&self) -> proto::Party {
+        proto::Party {
+            leader_nickname: self
+                .leader()
+                .map(|leader| leader.nickname.to_string())
+                .unwrap_or_default(),
+            member_nickname_list: self
+                .member_list
+                .iter()
+                .map(|member| member.nickname.to_string())
+                .collect(),
+            friendly_fire_protection: self.friendly_fire_protection,
+        }
+    }
+
+    pub fn describe(&self) -> Arc<str> {
+        let member_list = self
+            .member_list
+            .iter()
+            .map(
+                |member| match member.player_db_id == self.leader_player_db_id {
+                    true => format!("{} (leader)", member.nickname),
+                    false => member.nickname.to_string(),
+                },
+            )
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!(
+            "party: {}, friendly fire protection {}",
+            member_list,
+            match self.friendly_fire_protection {
+                true => "on",
+                false => "off",
+            }
+        )
+        .into()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct PartyBook {
+    pub party_map: HashMap<Arc<str>, Party>,
+    pub member_map: HashMap<i64, Arc<str>>,
+    pub invite_map: HashMap<i64, Invite>,
+}
+
+impl PartyBook {
+    pub fn get(&self, player_db_id: i64) -> Option<&Party> {
+        let party_id = self.member_map.get(&player_db_id)?;
+        self.party_map.get(party_id)
+    }
+
+    pub fn is_protected(&self, player_db_id: i64, victim_player_db_id: i64) -> bool {
+        match (
+            self.member_map.get(&player_db_id),
+            self.member_map.get(&victim_player_db_id),
+        ) {
+            (Some(party_id), Some(victim_party_id)) if party_id == victim_party_id => self
+                .party_map
+                .get(party_id)
+                .is_some_and(|party| party.friendly_fire_protection),
+            _ => false,
+        }
+    }
+
+    pub fn invite(
+        &mut self,
+        leader: &hub::Account,
+        target: &hub::Account,
+        max_party_size: usize,
+    ) -> Result<(), Arc<str>> {
+        if leader.player_db_id == target.player_db_id {
+            return Err("you can not invite yourself".into());
+        }
+        if self.member_map.contains_key(&target.player_db_id) {
+            return Err(format!("{} is already in a party", target.nickname).into());
+        }
+        if let Some(party) = self.get(leader.player_db_id) {
+            if party.leader_player_db_id != leader.player_db_id {
+                return Err("only the party leader can invite".into());
+            }
+            if party.member_list.len() >= max_party_size {
+                return Err(format!("party is full ({})", max_party_size).into());
+            }
+        }
+
+        self.invite_map
+            .entry(leader.player_db_id)
+            .or_insert_with(|| Invite {
+                leader: leader.clone(),
+                target_set: HashSet::new(),
+            })
+            .target_set
+            .insert(target.player_db_id);
+        Ok(())
+    }
+
+    pub fn accept(
+        &mut self,
+        member: &hub::Account,
+        leader_nickname: &str,
+        max_party_size: usize,
+    ) -> Result<&Party, Arc<str>> {
+        if self.member_map.contains_key(&member.player_db_id) {
+            return Err("leave your party first".into());
+        }
+        let no_invite = || Arc::from(format!("no party invite from {}", leader_nickname));
+        let leader = self
+            .invite_map
+            .values()
+            .find(|invite| {
+                invite.target_set.contains(&member.player_db_id)
+                    && invite.leader.nickname.eq_ignore_ascii_case(leader_nickname)
+            })
+            .map(|invite| invite.leader.clone())
+            .ok_or_else(no_invite)?;
+        match self.get(leader.player_db_id) {
+            Some(party) if party.leader_player_db_id != leader.player_db_id => {
+                self.invite_map.remove(&leader.player_db_id);
+                return Err(no_invite());
+            }
+            Some(party) if party.member_list.len() >= max_party_size => {
+                return Err(format!("party is full ({})", max_party_size).into());
+            }
+            _ => {}
+        }
+
+        if let Some(invite) = self.invite_map.get_mut(&leader.player_db_id) {
+            invite.target_set.remove(&member.player_db_id);
+            if invite.target_set.is_empty() {
+                self.invite_map.remove(&leader.player_db_id);
+            }
+        }
+        let party_id = self
+            .member_map
+            .entry(leader.player_db_id)
+            .or_insert_with(|| nanoid!().into())
+            .clone();
+        let party = self
+            .party_map
+            .entry(party_id.clone())
+            .or_insert_with(|| Party {
+                id: party_id.clone(),
+                leader_player_db_id: leader.player_db_id,
+                member_list: vec![leader],
+                friendly_fire_protection: false,
+            });
+        party.member_list.push(member.clone());
+        self.member_map.insert(member.player_db_id, party_id);
+        Ok(party)
+    }
+
+    pub fn leave(&mut self, player_db_id: i64) -> Option<Arc<str>> {
+        self.invite_map.remove(&player_db_id);
+        let party_id = self.member_map.remove(&player_db_id)?;
+        let party = self.party_map.get_mut(&party_id)?;
+        party
+            .member_list
+            .retain(|member| member.player_db_id != player_db_id);
+        if party.member_list.is_empty() {
+            self.party_map.remove(&party_id);
+        } else if party.leader_player_db_id == player_db_id {
+            party.leader_player_db_id = party.member_list[0].player_db_id;
+        }
+        Some(party_id)
+    }
+
+    pub fn kick(
+        &mut self,
+        leader_player_db_id: i64,
+        nickname: &str,
+    ) -> Result<hub::Account, Arc<str>> {
+        let party = self
+            .get(leader_player_db_id)
+            .ok_or_else(|| Arc::from("you are not in a party"))?;
+        if party.leader_player_db_id != leader_player_db_id {
+            return Err("only the party leader can kick".into());
+        }
+        let member = match party.find_member(nickname) {
+            Some(member) if member.player_db_id != leader_player_db_id => member.clone(),
+            Some(_) => return Err("use /party leave to leave your party".into()),
+            None => return Err(format!("{} is not in your party", nickname).into()),
+        };
+        self.leave(member.player_db_id);
+        Ok(member)
+    }
+
+    pub fn protect(
+        &mut self,
+        leader_player_db_id: i64,
+        friendly_fire_protection: bool,
+    ) -> Result<&Party, Arc<str>> {
+        let party = self
+            .member_map
+            .get(&leader_player_db_id)
+            .and_then(|party_id| self.party_map.get_mut(party_id))
+            .ok_or_else(|| Arc::from("you are not in a party"))?;
+        if party.leader_player_db_id != leader_player_db_id {
+            return Err("only the party leader can change friendly fire protection".into());
+        }
+        party.friendly_fire_protection = friendly_fire_protection;
+        Ok(party)
+    }
+}
@@ -1,16 +1,12 @@
 use crate::*;
+use rand::Rng;
 use std::time::Duration;
-use tokio::time::Instant;
 
-const PLAYER_BOUND: f64 = 3000.0;
-const INIT_RADIUS: f64 = 20.0;
 const INIT_DIRECTION_ANGLE: f64 = 0.0;
-const INIT_SPEED: f64 = 150.0;
-const RUSH_SPEED: f64 = 300.0;
-const RUSH_DURATION: Duration = Duration::from_secs(2);
+const PARTY_SPAWN_SPACING: f64 = 3.0;
 
-fn random_xy() -> f64 {
-    (rand::random::<f64>() * 2.0 - 1.0) * PLAYER_BOUND
+fn random_xy(rng: &mut impl Rng, bound: f64) -> f64 {
+    (rng.random::<f64>() * 2.0 - 1.0) * bound
 }
 
 #[derive(Debug, Clone)]
@@ -24,26 +20,53 @@ pub struct Player {
     pub direction_angle: f64,
     pub speed: f64,
     pub color: i64,
-    pub rush_instant: Option<Instant>,
+    pub base_color: i64,
+    pub rush_elapsed: Option<Duration>,
+    pub is_bot: bool,
+    pub team: u32,
 }
 
 impl Player {
-    pub fn random(db_id: i64, connection_id: Arc<str>, nickname: Arc<str>, color: i64) -> Self {
+    pub fn random(
+        rng: &mut impl Rng,
+        db_id: i64,
+        connection_id: Arc<str>,
+        nickname: Arc<str>,
+        color: i64,
+        game_config: &config::GameConfig,
+    ) -> Self {
         Self {
             db_id,
             connection_id,
             nickname,
-            x: random_xy(),
-            y: random_xy(),
-            radius: INIT_RADIUS,
+            x: random_xy(rng, game_config.world_bound),
+            y: random_xy(rng, game_config.world_bound),
+            radius: game_config.init_radius,
             direction_angle: INIT_DIRECTION_ANGLE,
-            speed: INIT_SPEED,
+            speed: game_config.init_speed,
             color,
-            rush_instant: None,
+            base_color: color,
+            rush_elapsed: None,
+            is_bot: false,
+            team: 0,
         }
     }
 
-    pub fn tick(&mut self, delta: Duration) {
+    pub fn place_near(
+        &mut self,
+        rng: &mut impl Rng,
+        anchor: &Player,
+        game_config: &config::GameConfig,
+    ) {
+        let angle = rng.random::<f64>() * std::f64::consts::TAU;
+        let distance = anchor.radius + self.radius * PARTY_SPAWN_SPACING;
+        let bound = game_config.world_bound;
+        self.x = (anchor.x + distance * angle.cos()).clamp(-bound, bound);
+        self.y = (anchor.y + distance * angle.sin()).clamp(-bound, bound);
+    }
+
+    pub fn step(&mut self, game_config: &config::GameConfig) {
+        let delta = game_config.tick_duration();
         let delta_secs = delta.as_secs_f64();
 
         let new_x = self.x + self.speed * self.direction_angle.cos() * delta_secs;
@@ -52,24 +75,25 @@ impl Player {
         self.x = new_x;
         self.y = new_y;
 
-        if let Some(rush_instant) = self.rush_instant
-            && rush_instant.elapsed() > RUSH_DURATION
-        {
-            self.speed = INIT_SPEED;
-            self.rush_instant = None;
+        if let Some(rush_elapsed) = self.rush_elapsed.as_mut() {
+            *rush_elapsed += delta;
+            if *rush_elapsed > game_config.rush_duration() {
+                self.speed = game_config.init_speed;
+                self.rush_elapsed = None;
+            }
         }
     }
 
-    pub fn rush(&mut self) {
-        self.speed = RUSH_SPEED;
-        self.rush_instant = Some(Instant::now());
+    pub fn rush(&mut self, game_config: &config::GameConfig) {
+        self.speed = game_config.rush_speed;
+        self.rush_elapsed = Some(Duration::ZERO);
     }
 
-    pub fn respawn(&mut self) {
-        self.x = random_xy();
-        self.y = random_xy();
-        self.radius = INIT_RADIUS;
-        self.speed = INIT_SPEED;
+    pub fn respawn(&mut self, rng: &mut impl Rng, game_config: &config::GameConfig) {
+        self.x = random_xy(rng, game_config.world_bound);
+        self.y = random_xy(rng, game_config.world_bound);
+        self.radius = game_config.init_radius;
+        self.speed = game_config.init_speed;
     }
 
     pub fn increase_mass(&mut self, mass: f64) {
@@ -3,7 +3,7 @@
 pub struct Packet {
     #[prost(
         oneof = "packet::Data",
-        tags = "1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21"
+        tags = "1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31, 32, 33, 34, 35, 36, 37, 38, 39, 40, 41, 42, 43, 44, 45, 46"
     )]
     pub data: ::core::option::Option<packet::Data>,
 }
@@ -53,6 +53,56 @@ pub mod packet {
         LeaderboardRequest(super::LeaderboardRequest),
         #[prost(message, tag = "21")]
         LeaderboardResponse(super::LeaderboardResponse),
+        #[prost(message, tag = "22")]
+        Spectate(super::Spectate),
+        #[prost(message, tag = "23")]
+        SpectateOk(super::SpectateOk),
+        #[prost(message, tag = "24")]
+        SpectateErr(super::SpectateErr),
+        #[prost(message, tag = "25")]
+        SpectateTarget(super::SpectateTarget),
+        #[prost(message, tag = "26")]
+        FriendRequest(super::FriendRequest),
+        #[prost(message, tag = "27")]
+        FriendAccept(super::FriendAccept),
+        #[prost(message, tag = "28")]
+        FriendRemove(super::FriendRemove),
+        #[prost(message, tag = "29")]
+        FriendListRequest(super::FriendListRequest),
+        #[prost(message, tag = "30")]
+        FriendList(super::FriendList),
+        #[prost(message, tag = "31")]
+        FriendErr(super::FriendErr),
+        #[prost(message, tag = "32")]
+        PartyInvite(super::PartyInvite),
+        #[prost(message, tag = "33")]
+        PartyAccept(super::PartyAccept),
+        #[prost(message, tag = "34")]
+        PartyLeave(super::PartyLeave),
+        #[prost(message, tag = "35")]
+        PartyKick(super::PartyKick),
+        #[prost(message, tag = "36")]
+        PartyProtect(super::PartyProtect),
+        #[prost(message, tag = "37")]
+        Party(super::Party),
+        #[prost(message, tag = "38")]
+        PartyErr(super::PartyErr),
+        #[prost(message, tag = "39")]
+        CreateRoom(super::CreateRoom),
+        #[prost(message, tag = "40")]
+        JoinRoom(super::JoinRoom),
+        #[prost(message, tag = "41")]
+        Room(super::Room),
+        #[prost(message, tag = "42")]
+        RoomErr(super::RoomErr),
+        #[prost(message, tag = "43")]
+        TeamScoreBatch(super::TeamScoreBatch),
+        #[prost(message, tag = "44")]
+        TeamResult(super::TeamResult),
+        #[prost(message, tag = "45")]
+        Round(super::Round),
+        #[prost(message, tag = "46")]
+        RoundResult(super::RoundResult),
     }
 }
 #[derive(Clone, Copy, PartialEq, Eq, Hash, ::prost::Message)]
@@ -103,6 +153,8 @@ pub struct Disconnect {
     pub connection_id: ::prost::alloc::string::String,
     #[prost(string, tag = "2")]
     pub reason: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "3")]
+    pub countdown_secs: u64,
 }
 #[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
 pub struct Chat {
@@ -110,6 +162,12 @@ pub struct Chat {
     pub connection_id: ::prost::alloc::string::String,
     #[prost(string, tag = "2")]
     pub msg: ::prost::alloc::string::String,
+    #[prost(enumeration = "ChatChannel", tag = "3")]
+    pub channel: i32,
+    #[prost(string, tag = "4")]
+    pub nickname: ::prost::alloc::string::String,
+    #[prost(string, tag = "5")]
+    pub target_nickname: ::prost::alloc::string::String,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct UpdatePlayer {
@@ -131,6 +189,10 @@ pub struct UpdatePlayer {
     pub color: i64,
     #[prost(bool, tag = "9")]
     pub is_rushing: bool,
+    #[prost(bool, tag = "10")]
+    pub is_bot: bool,
+    #[prost(uint32, tag = "11")]
+    pub team: u32,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct UpdatePlayerBatch {
@@ -190,3 +252,590 @@ pub struct LeaderboardResponse {
     #[prost(message, repeated, tag = "1")]
     pub leaderboard_entry_list: ::prost::alloc::vec::Vec<LeaderboardEntry>,
 }
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct Spectate {
+    #[prost(enumeration = "SpectateMode", tag = "1")]
+    pub mode: i32,
+    #[prost(string, tag = "2")]
+    pub target_connection_id: ::prost::alloc::string::String,
+}
+#[derive(Clone, Copy, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct SpectateOk {}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct SpectateErr {
+    #[prost(string, tag = "1")]
+    pub reason: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct SpectateTarget {
+    #[prost(string, tag = "1")]
+    pub connection_id: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct Friend {
+    #[prost(string, tag = "1")]
+    pub nickname: ::prost::alloc::string::String,
+    #[prost(enumeration = "FriendStatus", tag = "2")]
+    pub status: i32,
+    #[prost(enumeration = "PresenceStatus", tag = "3")]
+    pub presence: i32,
+    #[prost(string, tag = "4")]
+    pub room_id: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct FriendRequest {
+    #[prost(string, tag = "1")]
+    pub nickname: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct FriendAccept {
+    #[prost(string, tag = "1")]
+    pub nickname: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct FriendRemove {
+    #[prost(string, tag = "1")]
+    pub nickname: ::prost::alloc::string::String,
+}
+#[derive(Clone, Copy, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct FriendListRequest {}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FriendList {
+    #[prost(message, repeated, tag = "1")]
+    pub friend_list: ::prost::alloc::vec::Vec<Friend>,
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct FriendErr {
+    #[prost(string, tag = "1")]
+    pub reason: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct PartyInvite {
+    #[prost(string, tag = "1")]
+    pub nickname: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct PartyAccept {
+    #[prost(string, tag = "1")]
+    pub nickname: ::prost::alloc::string::String,
+}
+#[derive(Clone, Copy, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct PartyLeave {}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct PartyKick {
+    #[prost(string, tag = "1")]
+    pub nickname: ::prost::alloc::string::String,
+}
+#[derive(Clone, Copy, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct PartyProtect {
+    #[prost(bool, tag = "1")]
+    pub friendly_fire_protection: bool,
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct Party {
+    #[prost(string, tag = "1")]
+    pub leader_nickname: ::prost::alloc::string::String,
+    #[prost(string, repeated, tag = "2")]
+    pub member_nickname_list: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(bool, tag = "3")]
+    pub friendly_fire_protection: bool,
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct PartyErr {
+    #[prost(string, tag = "1")]
+    pub reason: ::prost::alloc::string::String,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct RoomSettings {
+    #[prost(double, optional, tag = "1")]
+    pub world_bound: ::core::option::Option<f64>,
+    #[prost(uint32, optional, tag = "2")]
+    pub max_player_count: ::core::option::Option<u32>,
+    #[prost(double, optional, tag = "3")]
+    pub spore_density: ::core::option::Option<f64>,
+    #[prost(double, optional, tag = "4")]
+    pub eat_ratio: ::core::option::Option<f64>,
+    #[prost(double, optional, tag = "5")]
+    pub rush_cost_ratio: ::core::option::Option<f64>,
+    #[prost(bool, optional, tag = "6")]
+    pub ranked: ::core::option::Option<bool>,
+    #[prost(uint32, optional, tag = "7")]
+    pub team_count: ::core::option::Option<u32>,
+    #[prost(bool, optional, tag = "8")]
+    pub round_mode: ::core::option::Option<bool>,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct CreateRoom {
+    #[prost(message, optional, tag = "1")]
+    pub settings: ::core::option::Option<RoomSettings>,
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct JoinRoom {
+    #[prost(string, tag = "1")]
+    pub code: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Room {
+    #[prost(string, tag = "1")]
+    pub room_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub code: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "3")]
+    pub settings: ::core::option::Option<RoomSettings>,
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct RoomErr {
+    #[prost(string, tag = "1")]
+    pub reason: ::prost::alloc::string::String,
+}
+#[derive(Clone, Copy, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct TeamScore {
+    #[prost(uint32, tag = "1")]
+    pub team: u32,
+    #[prost(int64, tag = "2")]
+    pub color: i64,
+    #[prost(int64, tag = "3")]
+    pub score: i64,
+    #[prost(uint32, tag = "4")]
+    pub player_count: u32,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TeamScoreBatch {
+    #[prost(message, repeated, tag = "1")]
+    pub team_score_batch: ::prost::alloc::vec::Vec<TeamScore>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TeamResult {
+    #[prost(uint32, tag = "1")]
+    pub winner_team: u32,
+    #[prost(message, repeated, tag = "2")]
+    pub team_score_list: ::prost::alloc::vec::Vec<TeamScore>,
+}
+#[derive(Clone, Copy, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct Round {
+    #[prost(enumeration = "RoundState", tag = "1")]
+    pub state: i32,
+    #[prost(uint64, tag = "2")]
+    pub remaining_ms: u64,
+    #[prost(uint32, tag = "3")]
+    pub round_number: u32,
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct RoundScore {
+    #[prost(string, tag = "1")]
+    pub connection_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub nickname: ::prost::alloc::string::String,
+    #[prost(int64, tag = "3")]
+    pub score: i64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RoundResult {
+    #[prost(uint32, tag = "1")]
+    pub round_number: u32,
+    #[prost(message, repeated, tag = "2")]
+    pub score_list: ::prost::alloc::vec::Vec<RoundScore>,
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum ChatChannel {
+    Global = 0,
+    Whisper = 1,
+    Party = 2,
+    System = 3,
+}
+impl ChatChannel {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Global => "CHAT_CHANNEL_GLOBAL",
+            Self::Whisper => "CHAT_CHANNEL_WHISPER",
+            Self::Party => "CHAT_CHANNEL_PARTY",
+            Self::System => "CHAT_CHANNEL_SYSTEM",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "CHAT_CHANNEL_GLOBAL" => Some(Self::Global),
+            "CHAT_CHANNEL_WHISPER" => Some(Self::Whisper),
+            "CHAT_CHANNEL_PARTY" => Some(Self::Party),
+            "CHAT_CHANNEL_SYSTEM" => Some(Self::System),
+            _ => None,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum SpectateMode {
+    FollowTop = 0,
+    FollowPlayer = 1,
+    FreeRoam = 2,
+}
+impl SpectateMode {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::FollowTop => "SPECTATE_MODE_FOLLOW_TOP",
+            Self::FollowPlayer => "SPECTATE_MODE_FOLLOW_PLAYER",
+            Self::FreeRoam => "SPECTATE_MODE_FREE_ROAM",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "SPECTATE_MODE_FOLLOW_TOP" => Some(Self::FollowTop),
+            "SPECTATE_MODE_FOLLOW_PLAYER" => Some(Self::FollowPlayer),
+            "SPECTATE_MODE_FREE_ROAM" => Some(Self::FreeRoam),
+            _ => None,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum FriendStatus {
+    Accepted = 0,
+    Incoming = 1,
+    Outgoing = 2,
+}
+impl FriendStatus {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Accepted => "FRIEND_STATUS_ACCEPTED",
+            Self::Incoming => "FRIEND_STATUS_INCOMING",
+            Self::Outgoing => "FRIEND_STATUS_OUTGOING",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "FRIEND_STATUS_ACCEPTED" => Some(Self::Accepted),
+            "FRIEND_STATUS_INCOMING" => Some(Self::Incoming),
+            "FRIEND_STATUS_OUTGOING" => Some(Self::Outgoing),
+            _ => None,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum PresenceStatus {
+    Offline = 0,
+    Lobby = 1,
+    InGame = 2,
+    Spectating = 3,
+}
+impl PresenceStatus {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Offline => "PRESENCE_STATUS_OFFLINE",
+            Self::Lobby => "PRESENCE_STATUS_LOBBY",
+            Self::InGame => "PRESENCE_STATUS_IN_GAME",
+            Self::Spectating => "PRESENCE_STATUS_SPECTATING",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "PRESENCE_STATUS_OFFLINE" => Some(Self::Offline),
+            "PRESENCE_STATUS_LOBBY" => Some(Self::Lobby),
+            "PRESENCE_STATUS_IN_GAME" => Some(Self::InGame),
+            "PRESENCE_STATUS_SPECTATING" => Some(Self::Spectating),
+            _ => None,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum RoundState {
+    Waiting = 0,
+    Countdown = 1,
+    Running = 2,
+    Results = 3,
+    Reset = 4,
+}
+impl RoundState {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Waiting => "ROUND_STATE_WAITING",
+            Self::Countdown => "ROUND_STATE_COUNTDOWN",
+            Self::Running => "ROUND_STATE_RUNNING",
+            Self::Results => "ROUND_STATE_RESULTS",
+            Self::Reset => "ROUND_STATE_RESET",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "ROUND_STATE_WAITING" => Some(Self::Waiting),
+            "ROUND_STATE_COUNTDOWN" => Some(Self::Countdown),
+            "ROUND_STATE_RUNNING" => Some(Self::Running),
+            "ROUND_STATE_RESULTS" => Some(Self::Results),
+            "ROUND_STATE_RESET" => Some(Self::Reset),
+            _ => None,
+        }
+    }
+}
+#[derive(Clone, Copy, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ReplayHeader {
+    #[prost(uint32, tag = "1")]
+    pub version: u32,
+    #[prost(uint64, tag = "2")]
+    pub rng_seed: u64,
+    #[prost(int64, tag = "3")]
+    pub started_at: i64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReplayRecord {
+    #[prost(oneof = "replay_record::Data", tags = "1, 2")]
+    pub data: ::core::option::Option<replay_record::Data>,
+}
+/// Nested message and enum types in `ReplayRecord`.
+pub mod replay_record {
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Data {
+        #[prost(message, tag = "1")]
+        Frame(super::ReplayFrame),
+        #[prost(message, tag = "2")]
+        Snapshot(super::ReplaySnapshot),
+    }
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReplayFrame {
+    #[prost(uint64, tag = "1")]
+    pub step: u64,
+    #[prost(message, repeated, tag = "2")]
+    pub input_list: ::prost::alloc::vec::Vec<ReplayInput>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReplayInput {
+    #[prost(
+        oneof = "replay_input::Data",
+        tags = "1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13"
+    )]
+    pub data: ::core::option::Option<replay_input::Data>,
+}
+/// Nested message and enum types in `ReplayInput`.
+pub mod replay_input {
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Data {
+        #[prost(message, tag = "1")]
+        Register(super::ReplayRegister),
+        #[prost(message, tag = "2")]
+        Leave(super::ReplayLeave),
+        #[prost(message, tag = "3")]
+        Join(super::ReplayJoin),
+        #[prost(message, tag = "4")]
+        Chat(super::ReplayChat),
+        #[prost(message, tag = "5")]
+        DirectionAngle(super::ReplayDirectionAngle),
+        #[prost(message, tag = "6")]
+        ConsumeSpore(super::ReplayConsumeSpore),
+        #[prost(message, tag = "7")]
+        ConsumePlayer(super::ReplayConsumePlayer),
+        #[prost(message, tag = "8")]
+        Rush(super::ReplayRush),
+        #[prost(message, tag = "9")]
+        SpawnSpore(super::ReplaySpawnSpore),
+        #[prost(message, tag = "10")]
+        BalanceBot(super::ReplayBalanceBot),
+        #[prost(message, tag = "11")]
+        GameConfig(super::ReplayGameConfig),
+        #[prost(message, tag = "12")]
+        CreateRoom(super::ReplayRoom),
+        #[prost(message, tag = "13")]
+        CloseRoom(super::ReplayCloseRoom),
+    }
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ReplayRegister {
+    #[prost(string, tag = "1")]
+    pub connection_id: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ReplayLeave {
+    #[prost(string, tag = "1")]
+    pub connection_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub reason: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ReplayJoin {
+    #[prost(string, tag = "1")]
+    pub connection_id: ::prost::alloc::string::String,
+    #[prost(int64, tag = "2")]
+    pub player_db_id: i64,
+    #[prost(string, tag = "3")]
+    pub nickname: ::prost::alloc::string::String,
+    #[prost(int64, tag = "4")]
+    pub color: i64,
+    #[prost(string, tag = "5")]
+    pub anchor_connection_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "6")]
+    pub room_id: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ReplayChat {
+    #[prost(string, tag = "1")]
+    pub connection_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub msg: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub nickname: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReplayDirectionAngle {
+    #[prost(string, tag = "1")]
+    pub connection_id: ::prost::alloc::string::String,
+    #[prost(double, tag = "2")]
+    pub direction_angle: f64,
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ReplayConsumeSpore {
+    #[prost(string, tag = "1")]
+    pub connection_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub spore_id: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ReplayConsumePlayer {
+    #[prost(string, tag = "1")]
+    pub connection_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub victim_connection_id: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ReplayRush {
+    #[prost(string, tag = "1")]
+    pub connection_id: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ReplaySpawnSpore {
+    #[prost(string, tag = "1")]
+    pub room_id: ::prost::alloc::string::String,
+}
+#[derive(Clone, Copy, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ReplayBalanceBot {}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ReplayGameConfig {
+    #[prost(string, tag = "1")]
+    pub game_config: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ReplayRoom {
+    #[prost(string, tag = "1")]
+    pub room_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub code: ::prost::alloc::string::String,
+    #[prost(int64, tag = "3")]
+    pub owner_player_db_id: i64,
+    #[prost(string, tag = "4")]
+    pub settings: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ReplayCloseRoom {
+    #[prost(string, tag = "1")]
+    pub room_id: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReplaySnapshot {
+    #[prost(uint64, tag = "1")]
+    pub step: u64,
+    #[prost(bytes = "vec", tag = "2")]
+    pub rng_seed: ::prost::alloc::vec::Vec<u8>,
+    #[prost(uint64, tag = "3")]
+    pub rng_word_pos_high: u64,
+    #[prost(uint64, tag = "4")]
+    pub rng_word_pos_low: u64,
+    #[prost(uint64, tag = "5")]
+    pub spore_count: u64,
+    #[prost(uint64, tag = "6")]
+    pub bot_count: u64,
+    #[prost(string, tag = "7")]
+    pub game_config: ::prost::alloc::string::String,
+    #[prost(message, repeated, tag = "8")]
+    pub client_list: ::prost::alloc::vec::Vec<ReplayClient>,
+    #[prost(message, repeated, tag = "9")]
+    pub spore_list: ::prost::alloc::vec::Vec<ReplaySpore>,
+    #[prost(message, repeated, tag = "10")]
+    pub room_list: ::prost::alloc::vec::Vec<ReplayRoom>,
+    #[prost(message, repeated, tag = "11")]
+    pub round_list: ::prost::alloc::vec::Vec<ReplayRound>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReplayClient {
+    #[prost(string, tag = "1")]
+    pub connection_id: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "2")]
+    pub player: ::core::option::Option<ReplayPlayer>,
+    #[prost(string, tag = "3")]
+    pub room_id: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReplayPlayer {
+    #[prost(int64, tag = "1")]
+    pub db_id: i64,
+    #[prost(string, tag = "2")]
+    pub nickname: ::prost::alloc::string::String,
+    #[prost(double, tag = "3")]
+    pub x: f64,
+    #[prost(double, tag = "4")]
+    pub y: f64,
+    #[prost(double, tag = "5")]
+    pub radius: f64,
+    #[prost(double, tag = "6")]
+    pub direction_angle: f64,
+    #[prost(double, tag = "7")]
+    pub speed: f64,
+    #[prost(int64, tag = "8")]
+    pub color: i64,
+    #[prost(uint64, optional, tag = "9")]
+    pub rush_elapsed_micros: ::core::option::Option<u64>,
+    #[prost(bool, tag = "10")]
+    pub is_bot: bool,
+    #[prost(uint32, tag = "11")]
+    pub team: u32,
+    #[prost(int64, tag = "12")]
+    pub base_color: i64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReplaySpore {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    #[prost(double, tag = "2")]
+    pub x: f64,
+    #[prost(double, tag = "3")]
+    pub y: f64,
+    #[prost(double, tag = "4")]
+    pub radius: f64,
+    #[prost(string, tag = "5")]
+    pub room_id: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ReplayRound {
+    #[prost(string, tag = "1")]
+    pub room_id: ::prost::alloc::string::String,
+    #[prost(uint32, tag = "2")]
+    pub round_number: u32,
+    #[prost(int32, tag = "3")]
+    pub state: i32,
+    #[prost(uint64, tag = "4")]
+    pub state_step: u64,
+}
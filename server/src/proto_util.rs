@@ -1,5 +1,57 @@
 use crate::*;
 
+pub fn packet_type(packet: &proto::Packet) -> &'static str {
+    match &packet.data {
+        Some(proto::packet::Data::Ping(_)) => "ping",
+        Some(proto::packet::Data::Hello(_)) => "hello",
+        Some(proto::packet::Data::Login(_)) => "login",
+        Some(proto::packet::Data::LoginOk(_)) => "login_ok",
+        Some(proto::packet::Data::LoginErr(_)) => "login_err",
+        Some(proto::packet::Data::Register(_)) => "register",
+        Some(proto::packet::Data::RegisterOk(_)) => "register_ok",
+        Some(proto::packet::Data::RegisterErr(_)) => "register_err",
+        Some(proto::packet::Data::Join(_)) => "join",
+        Some(proto::packet::Data::Disconnect(_)) => "disconnect",
+        Some(proto::packet::Data::Chat(_)) => "chat",
+        Some(proto::packet::Data::UpdatePlayer(_)) => "update_player",
+        Some(proto::packet::Data::UpdatePlayerBatch(_)) => "update_player_batch",
+        Some(proto::packet::Data::UpdatePlayerDirectionAngle(_)) => "update_player_direction_angle",
+        Some(proto::packet::Data::UpdateSpore(_)) => "update_spore",
+        Some(proto::packet::Data::UpdateSporeBatch(_)) => "update_spore_batch",
+        Some(proto::packet::Data::ConsumeSpore(_)) => "consume_spore",
+        Some(proto::packet::Data::ConsumePlayer(_)) => "consume_player",
+        Some(proto::packet::Data::Rush(_)) => "rush",
+        Some(proto::packet::Data::LeaderboardRequest(_)) => "leaderboard_request",
+        Some(proto::packet::Data::LeaderboardResponse(_)) => "leaderboard_response",
+        Some(proto::packet::Data::Spectate(_)) => "spectate",
+        Some(proto::packet::Data::SpectateOk(_)) => "spectate_ok",
+        Some(proto::packet::Data::SpectateErr(_)) => "spectate_err",
+        Some(proto::packet::Data::SpectateTarget(_)) => "spectate_target",
+        Some(proto::packet::Data::FriendRequest(_)) => "friend_request",
+        Some(proto::packet::Data::FriendAccept(_)) => "friend_accept",
+        Some(proto::packet::Data::FriendRemove(_)) => "friend_remove",
+        Some(proto::packet::Data::FriendListRequest(_)) => "friend_list_request",
+        Some(proto::packet::Data::FriendList(_)) => "friend_list",
+        Some(proto::packet::Data::FriendErr(_)) => "friend_err",
+        Some(proto::packet::Data::PartyInvite(_)) => "party_invite",
+        Some(proto::packet::Data::PartyAccept(_)) => "party_accept",
+        Some(proto::packet::Data::PartyLeave(_)) => "party_leave",
+        Some(proto::packet::Data::PartyKick(_)) => "party_kick",
+        Some(proto::packet::Data::PartyProtect(_)) => "party_protect",
+        Some(proto::packet::Data::Party(_)) => "party",
+        Some(proto::packet::Data::PartyErr(_)) => "party_err",
+        Some(proto::packet::Data::CreateRoom(_)) => "create_room",
+        Some(proto::packet::Data::JoinRoom(_)) => "join_room",
+        Some(proto::packet::Data::Room(_)) => "room",
+        Some(proto::packet::Data::RoomErr(_)) => "room_err",
+        Some(proto::packet::Data::TeamScoreBatch(_)) => "team_score_batch",
+        Some(proto::packet::Data::TeamResult(_)) => "team_result",
+        Some(proto::packet::Data::Round(_)) => "round",
+        Some(proto::packet::Data::RoundResult(_)) => "round_result",
+        None => "none",
+    }
+}
+
 pub fn hello_packet(connection_id: Arc<str>) -> proto::Packet {
     proto::Packet {
         data: Some(proto::packet::Data::Hello(proto::Hello {
@@ -36,15 +88,34 @@ pub fn register_err_packet(reason: Arc<str>) -> proto::Packet {
     }
 }
 
-pub fn chat_packet(connection_id: Arc<str>, msg: Arc<str>) -> proto::Packet {
+pub fn chat_packet(
+    channel: proto::ChatChannel,
+    connection_id: Arc<str>,
+    nickname: Arc<str>,
+    target_nickname: Arc<str>,
+    msg: Arc<str>,
+) -> proto::Packet {
     proto::Packet {
         data: Some(proto::packet::Data::Chat(proto::Chat {
             connection_id: connection_id.to_string(),
             msg: msg.to_string(),
+            channel: channel.into(),
+            nickname: nickname.to_string(),
+            target_nickname: target_nickname.to_string(),
         })),
     }
 }
 
+pub fn system_chat_packet(msg: Arc<str>) -> proto::Packet {
+    chat_packet(
+        proto::ChatChannel::System,
+        "".into(),
+        "".into(),
+        "".into(),
+        msg,
+    )
+}
+
 pub fn update_player(player: &player::Player) -> proto::UpdatePlayer {
     proto::UpdatePlayer {
         connection_id: player.connection_id.to_string(),
@@ -55,7 +126,9 @@ pub fn update_player(player: &player::Player) -> proto::UpdatePlayer {
         direction_angle: player.direction_angle,
         speed: player.speed,
         color: player.color,
-        is_rushing: player.rush_instant.is_some(),
+        is_rushing: player.rush_elapsed.is_some(),
+        is_bot: player.is_bot,
+        team: player.team,
     }
 }
 
@@ -120,6 +193,17 @@ pub fn disconnect_packet(connection_id: Arc<str>, reason: Arc<str>) -> proto::Pa
         data: Some(proto::packet::Data::Disconnect(proto::Disconnect {
             connection_id: connection_id.to_string(),
             reason: reason.to_string(),
+            countdown_secs: 0,
+        })),
+    }
+}
+
+pub fn shutdown_packet(reason: Arc<str>, countdown_secs: u64) -> proto::Packet {
+    proto::Packet {
+        data: Some(proto::packet::Data::Disconnect(proto::Disconnect {
+            connection_id: String::new(),
+            reason: reason.to_string(),
+            countdown_secs,
         })),
     }
 }
@@ -141,3 +225,104 @@ pub fn leaderboard_response(leaderboard_entry_list: &[command::LeaderboardEntry]
         )),
     }
 }
+
+pub fn spectate_ok_packet() -> proto::Packet {
+    proto::Packet {
+        data: Some(proto::packet::Data::SpectateOk(proto::SpectateOk {})),
+    }
+}
+
+pub fn spectate_err_packet(reason: Arc<str>) -> proto::Packet {
+    proto::Packet {
+        data: Some(proto::packet::Data::SpectateErr(proto::SpectateErr {
+            reason: reason.to_string(),
+        })),
+    }
+}
+
+pub fn spectate_target_packet(connection_id: Option<Arc<str>>) -> proto::Packet {
+    proto::Packet {
+        data: Some(proto::packet::Data::SpectateTarget(proto::SpectateTarget {
+            connection_id: connection_id.as_deref().unwrap_or_default().to_string(),
+        })),
+    }
+}
+
+pub fn friend_list_packet(friend_list: Vec<proto::Friend>) -> proto::Packet {
+    proto::Packet {
+        data: Some(proto::packet::Data::FriendList(proto::FriendList {
+            friend_list,
+        })),
+    }
+}
+
+pub fn friend_err_packet(reason: Arc<str>) -> proto::Packet {
+    proto::Packet {
+        data: Some(proto::packet::Data::FriendErr(proto::FriendErr {
+            reason: reason.to_string(),
+        })),
+    }
+}
+
+pub fn party_packet(party: proto::Party) -> proto::Packet {
+    proto::Packet {
+        data: Some(proto::packet::Data::Party(party)),
+    }
+}
+
+pub fn party_err_packet(reason: Arc<str>) -> proto::Packet {
+    proto::Packet {
+        data: Some(proto::packet::Data::PartyErr(proto::PartyErr {
+            reason: reason.to_string(),
+        })),
+    }
+}
+
+pub fn room_packet(room: proto::Room) -> proto::Packet {
+    proto::Packet {
+        data: Some(proto::packet::Data::Room(room)),
+    }
+}
+
+pub fn room_err_packet(reason: Arc<str>) -> proto::Packet {
+    proto::Packet {
+        data: Some(proto::packet::Data::RoomErr(proto::RoomErr {
+            reason: reason.to_string(),
+        })),
+    }
+}
+
+pub fn team_score_batch_packet(team_score_batch: Vec<proto::TeamScore>) -> proto::Packet {
+    proto::Packet {
+        data: Some(proto::packet::Data::TeamScoreBatch(proto::TeamScoreBatch {
+            team_score_batch,
+        })),
+    }
+}
+
+pub fn team_result_packet(
+    winner_team: u32,
+    team_score_list: Vec<proto::TeamScore>,
+) -> proto::Packet {
+    proto::Packet {
+        data: Some(proto::packet::Data::TeamResult(proto::TeamResult {
+            winner_team,
+            team_score_list,
+        })),
+    }
+}
+
+pub fn round_packet(round: proto::Round) -> proto::Packet {
+    proto::Packet {
+        data: Some(proto::packet::Data::Round(round)),
+    }
+}
+
+pub fn round_result_packet(round_number: u32, score_list: Vec<proto::RoundScore>) -> proto::Packet {
+    proto::Packet {
+        data: Some(proto::packet::Data::RoundResult(proto::RoundResult {
+            round_number,
+            score_list,
+        })),
+    }
+}
@@ -0,0 +1,542 @@
+use crate::*;
+use anyhow::{Context, Result, bail};
+use bytes::Bytes;
+use futures_util::{SinkExt, StreamExt};
+use prost::Message as _;
+use std::{path::Path, time::Duration};
+use tokio::{
+    io::AsyncWriteExt,
+    net::TcpListener,
+    select,
+    sync::mpsc::{UnboundedSender, unbounded_channel},
+    task::JoinHandle,
+    time::{MissedTickBehavior, interval},
+};
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{error, info, warn};
+
+pub const VERSION: u32 = 1;
+pub const MIN_SPEED: f64 = 0.01;
+pub const MAX_SPEED: f64 = 100.0;
+
+const SPECTATOR_CONNECTION_ID: &str = "replay-spectator";
+const SPORE_CHUNKS: usize = 20;
+
+#[derive(Debug)]
+pub struct Recorder {
+    pub path: Arc<str>,
+    pub snapshot_interval_step: u64,
+    pub input_list: Vec<proto::ReplayInput>,
+    pub last_frame_step: u64,
+    pub bytes_sender: UnboundedSender<Bytes>,
+    pub writer_join_handle: JoinHandle<()>,
+}
+
+impl Recorder {
+    pub fn start(
+        directory: &str,
+        snapshot_interval_step: u64,
+        header: proto::ReplayHeader,
+    ) -> Result<Self> {
+        std::fs::create_dir_all(directory)
+            .with_context(|| format!("create replay directory {:?} error", directory))?;
+        let path = Path::new(directory).join(format!(
+            "agarust-{}-{}.replay",
+            header.started_at, header.rng_seed
+        ));
+        let file = std::fs::File::create_new(&path)
+            .with_context(|| format!("create replay file {:?} error", path))?;
+        let path: Arc<str> = path.to_string_lossy().into();
+
+        let (bytes_sender, mut bytes_receiver) = unbounded_channel::<Bytes>();
+        let writer_path = path.clone();
+        let writer_join_handle = tokio::spawn(async move {
+            let mut writer = tokio::io::BufWriter::new(tokio::fs::File::from_std(file));
+            while let Some(bytes) = bytes_receiver.recv().await {
+                if let Err(e) = writer.write_all(&bytes).await {
+                    error!("replay write error {:?}: {:?}", writer_path, e);
+                    return;
+                }
+            }
+            if let Err(e) = writer.flush().await {
+                error!("replay flush error {:?}: {:?}", writer_path, e);
+            }
+        });
+
+        let recorder = Self {
+            path,
+            snapshot_interval_step,
+            input_list: vec![],
+            last_frame_step: 0,
+            bytes_sender,
+            writer_join_handle,
+        };
+        recorder.write(header.encode_length_delimited_to_vec());
+
+        Ok(recorder)
+    }
+
+    pub fn record(&mut self, data: proto::replay_input::Data) {
+        self.input_list
+            .push(proto::ReplayInput { data: Some(data) });
+    }
+
+    pub fn write_frame(&mut self, step: u64) {
+        if self.input_list.is_empty() {
+            return;
+        }
+        let frame = proto::ReplayFrame {
+            step,
+            input_list: std::mem::take(&mut self.input_list),
+        };
+        self.write_record(proto::replay_record::Data::Frame(frame));
+        self.last_frame_step = step;
+    }
+
+    pub fn write_snapshot(&mut self, snapshot: proto::ReplaySnapshot) {
+        self.write_record(proto::replay_record::Data::Snapshot(snapshot));
+    }
+
+    pub async fn finish(mut self, step: u64) -> Arc<str> {
+        if self.last_frame_step < step {
+            let frame = proto::ReplayFrame {
+                step,
+                input_list: vec![],
+            };
+            self.write_record(proto::replay_record::Data::Frame(frame));
+        }
+        drop(self.bytes_sender);
+        let _ = self.writer_join_handle.await;
+        self.path
+    }
+
+    fn write_record(&mut self, data: proto::replay_record::Data) {
+        let record = proto::ReplayRecord { data: Some(data) };
+        self.write(record.encode_length_delimited_to_vec());
+    }
+
+    fn write(&self, bytes: Vec<u8>) {
+        let _ = self.bytes_sender.send(bytes.into());
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Replay {
+    pub header: proto::ReplayHeader,
+    pub snapshot_list: Vec<proto::ReplaySnapshot>,
+    pub frame_list: Vec<proto::ReplayFrame>,
+}
+
+impl Replay {
+    pub fn load(path: &str) -> Result<Self> {
+        let bytes = std::fs::read(path).with_context(|| format!("read replay {:?} error", path))?;
+        let mut buf = bytes.as_slice();
+
+        let header = proto::ReplayHeader::decode_length_delimited(&mut buf)
+            .context("decode replay header error")?;
+        if header.version != VERSION {
+            bail!(
+                "replay version {} is not supported, expected {}",
+                header.version,
+                VERSION
+            );
+        }
+
+        let mut snapshot_list = vec![];
+        let mut frame_list = vec![];
+        while !buf.is_empty() {
+            let record = match proto::ReplayRecord::decode_length_delimited(&mut buf) {
+                Ok(record) => record,
+                Err(e) => {
+                    warn!("replay truncated, stop reading: {:?}", e);
+                    break;
+                }
+            };
+            match record.data {
+                Some(proto::replay_record::Data::Snapshot(snapshot)) => {
+                    snapshot_list.push(snapshot)
+                }
+                Some(proto::replay_record::Data::Frame(frame)) => frame_list.push(frame),
+                None => {}
+            }
+        }
+
+        if snapshot_list.is_empty() {
+            bail!("replay has no snapshot");
+        }
+
+        Ok(Self {
+            header,
+            snapshot_list,
+            frame_list,
+        })
+    }
+
+    pub fn first_step(&self) -> u64 {
+        self.snapshot_list[0].step
+    }
+
+    pub fn last_step(&self) -> u64 {
+        let last_snapshot_step = self
+            .snapshot_list
+            .last()
+            .map_or(0, |snapshot| snapshot.step);
+        let last_frame_step = self.frame_list.last().map_or(0, |frame| frame.step);
+        last_snapshot_step.max(last_frame_step)
+    }
+}
+
+pub fn replay_player(player: &player::Player) -> proto::ReplayPlayer {
+    proto::ReplayPlayer {
+        db_id: player.db_id,
+        nickname: player.nickname.to_string(),
+        x: player.x,
+        y: player.y,
+        radius: player.radius,
+        direction_angle: player.direction_angle,
+        speed: player.speed,
+        color: player.color,
+        rush_elapsed_micros: player
+            .rush_elapsed
+            .map(|rush_elapsed| rush_elapsed.as_micros() as u64),
+        is_bot: player.is_bot,
+        team: player.team,
+        base_color: player.base_color,
+    }
+}
+
+pub fn player(connection_id: Arc<str>, replay_player: proto::ReplayPlayer) -> player::Player {
+    player::Player {
+        db_id: replay_player.db_id,
+        connection_id,
+        nickname: replay_player.nickname.into(),
+        x: replay_player.x,
+        y: replay_player.y,
+        radius: replay_player.radius,
+        direction_angle: replay_player.direction_angle,
+        speed: replay_player.speed,
+        color: replay_player.color,
+        base_color: match replay_player.base_color {
+            0 => replay_player.color,
+            base_color => base_color,
+        },
+        rush_elapsed: replay_player.rush_elapsed_micros.map(Duration::from_micros),
+        is_bot: replay_player.is_bot,
+        team: replay_player.team,
+    }
+}
+
+pub fn replay_spore(spore: &spore::Spore) -> proto::ReplaySpore {
+    proto::ReplaySpore {
+        id: spore.id.to_string(),
+        x: spore.x,
+        y: spore.y,
+        radius: spore.radius,
+        room_id: spore.room_id.to_string(),
+    }
+}
+
+pub fn spore(replay_spore: proto::ReplaySpore) -> spore::Spore {
+    spore::Spore {
+        id: replay_spore.id.into(),
+        room_id: room::room_id(replay_spore.room_id),
+        x: replay_spore.x,
+        y: replay_spore.y,
+        radius: replay_spore.radius,
+    }
+}
+
+pub fn replay_room(room: &room::Room) -> proto::ReplayRoom {
+    proto::ReplayRoom {
+        room_id: room.id.to_string(),
+        code: room.code.to_string(),
+        owner_player_db_id: room.owner_player_db_id,
+        settings: toml::to_string(&room.settings).unwrap_or_default(),
+    }
+}
+
+pub fn room(
+    replay_room: proto::ReplayRoom,
+    game_config: &config::GameConfig,
+) -> Result<room::Room> {
+    Ok(room::Room::new(
+        replay_room.room_id.into(),
+        replay_room.code.into(),
+        replay_room.owner_player_db_id,
+        toml::from_str(&replay_room.settings)?,
+        game_config,
+    ))
+}
+
+pub fn replay_round(round: &round::Round) -> proto::ReplayRound {
+    proto::ReplayRound {
+        room_id: round.room_id.to_string(),
+        round_number: round.round_number,
+        state: round.state.into(),
+        state_step: round.state_step,
+    }
+}
+
+pub fn round(replay_round: proto::ReplayRound) -> round::Round {
+    round::Round {
+        room_id: replay_round.room_id.into(),
+        round_number: replay_round.round_number,
+        state: proto::RoundState::try_from(replay_round.state).unwrap_or_default(),
+        state_step: replay_round.state_step,
+        started_at: 0,
+    }
+}
+
+pub async fn play(path: &str, bind_addr: &str, speed: f64, start_offset: Duration) -> Result<()> {
+    if !(speed.is_finite() && (MIN_SPEED..=MAX_SPEED).contains(&speed)) {
+        bail!("speed must be between {} and {}", MIN_SPEED, MAX_SPEED);
+    }
+
+    let replay = Replay::load(path)?;
+    info!(
+        "replay loaded: {:?} steps {}..={}, {} snapshots, {} frames",
+        path,
+        replay.first_step(),
+        replay.last_step(),
+        replay.snapshot_list.len(),
+        replay.frame_list.len()
+    );
+
+    let db = db::Db::connect("memory:").await?;
+    let (db_writer_command_sender, _) = unbounded_channel::<command::Command>();
+    let mut hub = hub::Hub::new(db, config::Config::default(), db_writer_command_sender);
+
+    let first_game_config =
+        toml::from_str::<config::GameConfig>(&replay.snapshot_list[0].game_config)?;
+    let start_step = replay.first_step()
+        + (start_offset.as_millis() / first_game_config.tick_duration().as_millis()) as u64;
+    let start_step = start_step.min(replay.last_step());
+
+    let snapshot = replay
+        .snapshot_list
+        .iter()
+        .rev()
+        .find(|snapshot| snapshot.step <= start_step)
+        .unwrap_or(&replay.snapshot_list[0]);
+    hub.restore_replay_snapshot(snapshot.clone())?;
+
+    let (spectator_sender, mut spectator_receiver) = unbounded_channel::<command::Command>();
+    hub.client_map.insert(
+        SPECTATOR_CONNECTION_ID.into(),
+        hub::Client {
+            socket_addr: SocketAddr::from(([0, 0, 0, 0], 0)),
+            connection_id: SPECTATOR_CONNECTION_ID.into(),
+            client_agent_command_sender: spectator_sender,
+            player: None,
+            spectator: Some(spectator::Spectator::new(spectator::SpectateMode::FreeRoam)),
+            account: None,
+            role: role::Role::default(),
+            room_id: room::MAIN_ROOM_ID.into(),
+        },
+    );
+
+    let restored_step = hub.step_count;
+    let mut frame_iter = replay
+        .frame_list
+        .iter()
+        .filter(|frame| frame.step > restored_step)
+        .peekable();
+    let mut snapshot_iter = replay
+        .snapshot_list
+        .iter()
+        .filter(|snapshot| snapshot.step > restored_step)
+        .peekable();
+
+    while hub.step_count < start_step {
+        play_step(&mut hub, &mut frame_iter, &mut snapshot_iter);
+    }
+    while spectator_receiver.try_recv().is_ok() {}
+    info!("replay start at step {}", hub.step_count);
+
+    let tcp_listener = TcpListener::bind(bind_addr).await?;
+    info!("replay spectator listening on {:?}", bind_addr);
+    let (subscriber_sender, mut subscriber_receiver) =
+        unbounded_channel::<UnboundedSender<Bytes>>();
+    tokio::spawn(accept_spectator(tcp_listener, subscriber_sender));
+
+    let mut subscriber_list: Vec<UnboundedSender<Bytes>> = vec![];
+    let mut step_interval = interval(hub.game_config.tick_duration().div_f64(speed));
+    step_interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    while hub.step_count < replay.last_step() {
+        let step_duration = hub.game_config.tick_duration().div_f64(speed);
+        if step_interval.period() != step_duration {
+            step_interval = interval(step_duration);
+            step_interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        }
+
+        select! {
+            _ = step_interval.tick() => {
+                play_step(&mut hub, &mut frame_iter, &mut snapshot_iter);
+                while let Ok(command) = spectator_receiver.try_recv() {
+                    if let command::Command::SendBytes { bytes, .. } = command {
+                        subscriber_list.retain(|subscriber| subscriber.send(bytes.clone()).is_ok());
+                    }
+                }
+            }
+            Some(subscriber) = subscriber_receiver.recv() => {
+                for bytes in world_bytes_list(&hub) {
+                    let _ = subscriber.send(bytes);
+                }
+                subscriber_list.push(subscriber);
+            }
+        }
+    }
+
+    info!("replay finished at step {}", hub.step_count);
+
+    Ok(())
+}
+
+pub(crate) fn play_step<'a>(
+    hub: &mut hub::Hub,
+    frame_iter: &mut std::iter::Peekable<impl Iterator<Item = &'a proto::ReplayFrame>>,
+    snapshot_iter: &mut std::iter::Peekable<impl Iterator<Item = &'a proto::ReplaySnapshot>>,
+) {
+    let step = hub.step_count + 1;
+    if let Some(frame) = frame_iter.next_if(|frame| frame.step == step) {
+        frame
+            .input_list
+            .iter()
+            .filter_map(|input| input.data.clone())
+            .for_each(|data| hub.apply_replay_input(data));
+    }
+
+    hub.tick(Duration::ZERO, 0);
+
+    if let Some(snapshot) = snapshot_iter.next_if(|snapshot| snapshot.step == step) {
+        let mut replay_snapshot = hub.replay_snapshot();
+        replay_snapshot
+            .client_list
+            .retain(|client| client.connection_id != SPECTATOR_CONNECTION_ID);
+        if replay_snapshot != *snapshot {
+            warn!("replay diverged from snapshot at step {}", step);
+        }
+    }
+}
+
+fn world_bytes_list(hub: &hub::Hub) -> Vec<Bytes> {
+    let mut packet_list = vec![proto_util::hello_packet(SPECTATOR_CONNECTION_ID.into())];
+
+    let spore_list = hub
+        .spore_map
+        .values()
+        .filter(|spore| &*spore.room_id == room::MAIN_ROOM_ID)
+        .cloned()
+        .collect::<Vec<_>>();
+    packet_list.extend(
+        spore_list
+            .chunks(SPORE_CHUNKS)
+            .map(proto_util::update_spore_batch_packet),
+    );
+
+    let player_list = hub
+        .client_map
+        .values()
+        .filter(|client| &*client.room_id == room::MAIN_ROOM_ID)
+        .filter_map(|client| client.player.as_ref())
+        .collect::<Vec<_>>();
+    packet_list.push(proto_util::update_player_batch_packet(&player_list));
+
+    packet_list
+        .iter()
+        .map(|packet| packet.encode_to_vec().into())
+        .collect()
+}
+
+async fn accept_spectator(
+    tcp_listener: TcpListener,
+    subscriber_sender: UnboundedSender<UnboundedSender<Bytes>>,
+) {
+    loop {
+        let (tcp_stream, socket_addr) = match tcp_listener.accept().await {
+            Ok(accept) => accept,
+            Err(e) => {
+                warn!("replay accept error: {:?}", e);
+                continue;
+            }
+        };
+        info!("replay spectator connected: {:?}", socket_addr);
+
+        let (bytes_sender, mut bytes_receiver) = unbounded_channel::<Bytes>();
+        if subscriber_sender.send(bytes_sender).is_err() {
+            return;
+        }
+
+        tokio::spawn(async move {
+            let ws_stream = match tokio_tungstenite::accept_async(tcp_stream).await {
+                Ok(ws_stream) => ws_stream,
+                Err(e) => {
+                    warn!(
+                        "replay websocket handshake error {:?}: {:?}",
+                        socket_addr, e
+                    );
+                    return;
+                }
+            };
+            let (mut ws_sink, mut ws_stream) = ws_stream.split();
+            loop {
+                select! {
+                    bytes = bytes_receiver.recv() => {
+                        let bytes = match bytes {
+                            Some(bytes) => bytes,
+                            None => break,
+                        };
+                        if ws_sink.send(Message::Binary(bytes)).await.is_err() {
+                            break;
+                        }
+                    }
+                    message = ws_stream.next() => {
+                        match message {
+                            Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                            _ => {}
+                        }
+                    }
+                }
+            }
+            info!("replay spectator disconnected: {:?}", socket_addr);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn truncated_replay_still_loads() {
+        let directory = std::env::temp_dir().join(format!("agarust-replay-{}", nanoid::nanoid!()));
+        let header = proto::ReplayHeader {
+            version: VERSION,
+            rng_seed: 42,
+            started_at: 0,
+        };
+        let mut recorder = Recorder::start(&directory.to_string_lossy(), 10, header).unwrap();
+        recorder.write_snapshot(proto::ReplaySnapshot::default());
+        for step in 1..=20 {
+            recorder.record(proto::replay_input::Data::BalanceBot(
+                proto::ReplayBalanceBot {},
+            ));
+            recorder.write_frame(step);
+        }
+        let path = recorder.finish(20).await;
+
+        let replay = Replay::load(&path).unwrap();
+        assert_eq!(replay.frame_list.len(), 20);
+        assert_eq!(replay.last_step(), 20);
+
+        let bytes = std::fs::read(&*path).unwrap();
+        std::fs::write(&*path, &bytes[..bytes.len() - 1]).unwrap();
+        let replay = Replay::load(&path).unwrap();
+        assert_eq!(replay.snapshot_list.len(), 1);
+        assert_eq!(replay.frame_list.len(), 19);
+        assert_eq!(replay.last_step(), 19);
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,316 @@
+use crate::*;
+use indexmap::IndexMap;
+use nanoid::nanoid;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::time::Instant;
+
+pub const MAIN_ROOM_ID: &str = "main";
+pub const ROOM_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+const CODE_LENGTH: usize = 6;
+const CODE_ALPHABET: [char; 32] = [
+    'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'J', 'K', 'L', 'M', 'N', 'P', 'Q', 'R', 'S', 'T', 'U',
+    'V', 'W', 'X', 'Y', 'Z', '2', '3', '4', '5', '6', '7', '8', '9',
+];
+const SPORE_DENSITY_AREA: f64 = 1_000_000.0;
+const DEFAULT_MAX_PLAYER_COUNT: usize = 10;
+const MAX_PLAYER_COUNT: usize = 100;
+const MIN_WORLD_BOUND: f64 = 500.0;
+const MAX_WORLD_BOUND: f64 = 10000.0;
+const MAX_SPORE_DENSITY: f64 = 200.0;
+const MAX_SPORE_COUNT: usize = 5000;
+const MAX_EAT_RATIO: f64 = 3.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RoomAction {
+    Create { settings: proto::RoomSettings },
+    Join { code: Arc<str> },
+    Info,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoomSettings {
+    pub world_bound: f64,
+    pub max_player_count: usize,
+    pub spore_density: f64,
+    pub eat_ratio: f64,
+    pub rush_cost_ratio: f64,
+    pub ranked: bool,
+    #[serde(default)]
+    pub team_count: usize,
+    #[serde(default)]
+    pub round_mode: bool,
+}
+
+impl RoomSettings {
+    pub fn new(
+        settings: &proto::RoomSettings,
+        game_config: &config::GameConfig,
+    ) -> Result<Self, Arc<str>> {
+        let room_settings = Self {
+            world_bound: settings.world_bound.unwrap_or(game_config.world_bound),
+            max_player_count: settings
+                .max_player_count
+                .map_or(DEFAULT_MAX_PLAYER_COUNT, |max_player_count| {
+                    max_player_count as usize
+                }),
+            spore_density: settings
+                .spore_density
+                .unwrap_or_else(|| spore_density(game_config)),
+            eat_ratio: settings.eat_ratio.unwrap_or(game_config.eat_ratio),
+            rush_cost_ratio: settings
+                .rush_cost_ratio
+                .unwrap_or(game_config.rush_cost_ratio),
+            ranked: settings.ranked.unwrap_or(false),
+            team_count: settings
+                .team_count
+                .map_or(game_config.team_count, |team_count| team_count as usize),
+            round_mode: settings.round_mode.unwrap_or(game_config.round_mode),
+        };
+        room_settings.validate()?;
+        if room_settings.ranked && !room_settings.is_standard(game_config) {
+            return Err("ranked rooms must use the main room gameplay settings".into());
+        }
+        Ok(room_settings)
+    }
+
+    pub fn is_standard(&self, game_config: &config::GameConfig) -> bool {
+        self.world_bound == game_config.world_bound
+            && self.spore_density == spore_density(game_config)
+            && self.eat_ratio == game_config.eat_ratio
+            && self.rush_cost_ratio == game_config.rush_cost_ratio
+            && self.team_count == game_config.team_count
+            && self.round_mode == game_config.round_mode
+    }
+
+    pub fn is_ranked(&self, game_config: &config::GameConfig) -> bool {
+        self.ranked && self.is_standard(game_config)
+    }
+
+    fn validate(&self) -> Result<(), Arc<str>> {
+        if !(MIN_WORLD_BOUND..=MAX_WORLD_BOUND).contains(&self.world_bound) {
+            return Err(format!(
+                "world_bound must be in [{}, {}]",
+                MIN_WORLD_BOUND, MAX_WORLD_BOUND
+            )
+            .into());
+        }
+        if !(1..=MAX_PLAYER_COUNT).contains(&self.max_player_count) {
+            return Err(format!("max_player_count must be in [1, {}]", MAX_PLAYER_COUNT).into());
+        }
+        if !(0.0..=MAX_SPORE_DENSITY).contains(&self.spore_density) {
+            return Err(format!("spore_density must be in [0, {}]", MAX_SPORE_DENSITY).into());
+        }
+        if !(1.0..=MAX_EAT_RATIO).contains(&self.eat_ratio) {
+            return Err(format!("eat_ratio must be in [1, {}]", MAX_EAT_RATIO).into());
+        }
+        if !(0.0..1.0).contains(&self.rush_cost_ratio) {
+            return Err("rush_cost_ratio must be in [0, 1)".into());
+        }
+        if self.team_count == 1 || self.team_count > team::MAX_TEAM_COUNT {
+            return Err(format!(
+                "team_count must be 0 (free-for-all) or in [2, {}]",
+                team::MAX_TEAM_COUNT
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    pub fn max_spore_count(&self) -> usize {
+        ((self.spore_density * area(self.world_bound) / SPORE_DENSITY_AREA).round() as usize)
+            .min(MAX_SPORE_COUNT)
+    }
+
+    pub fn game_config(&self, game_config: &config::GameConfig) -> config::GameConfig {
+        config::GameConfig {
+            world_bound: self.world_bound,
+            max_spore_count: self.max_spore_count(),
+            eat_ratio: self.eat_ratio,
+            rush_cost_ratio: self.rush_cost_ratio,
+            team_count: self.team_count,
+            round_mode: self.round_mode,
+            bot_target_population: 0,
+            ..game_config.clone()
+        }
+    }
+
+    pub fn to_proto(&self) -> proto::RoomSettings {
+        proto::RoomSettings {
+            world_bound: Some(self.world_bound),
+            max_player_count: Some(self.max_player_count as u32),
+            spore_density: Some(self.spore_density),
+            eat_ratio: Some(self.eat_ratio),
+            rush_cost_ratio: Some(self.rush_cost_ratio),
+            ranked: Some(self.ranked),
+            team_count: Some(self.team_count as u32),
+            round_mode: Some(self.round_mode),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Room {
+    pub id: Arc<str>,
+    pub code: Arc<str>,
+    pub owner_player_db_id: i64,
+    pub settings: RoomSettings,
+    pub game_config: config::GameConfig,
+    pub empty_since: Option<Instant>,
+}
+
+impl Room {
+    pub fn new(
+        id: Arc<str>,
+        code: Arc<str>,
+        owner_player_db_id: i64,
+        settings: RoomSettings,
+        game_config: &config::GameConfig,
+    ) -> Self {
+        Self {
+            id,
+            code,
+            owner_player_db_id,
+            game_config: settings.game_config(game_config),
+            settings,
+            empty_since: Some(Instant::now()),
+        }
+    }
+
+    pub fn to_proto(&self) -> proto::Room {
+        proto::Room {
+            room_id: self.id.to_string(),
+            code: self.code.to_string(),
+            settings: Some(self.settings.to_proto()),
+        }
+    }
+
+    pub fn describe(&self, player_count: usize) -> Arc<str> {
+        format!(
+            "room {}: {}/{} players, {}, world_bound {}, {} spores, eat_ratio {}, rush_cost_ratio {}, {}",
+            self.code,
+            player_count,
+            self.settings.max_player_count,
+            match (self.settings.team_count, self.settings.round_mode) {
+                (0, false) => "free-for-all".to_string(),
+                (0, true) => "free-for-all rounds".to_string(),
+                (team_count, false) => format!("{} teams", team_count),
+                (team_count, true) => format!("{} teams rounds", team_count),
+            },
+            self.settings.world_bound,
+            self.settings.max_spore_count(),
+            self.settings.eat_ratio,
+            self.settings.rush_cost_ratio,
+            match self.settings.ranked {
+                true => "ranked",
+                false => "unranked",
+            }
+        )
+        .into()
+    }
+}
+
+pub fn random_code() -> Arc<str> {
+    nanoid!(CODE_LENGTH, &CODE_ALPHABET).into()
+}
+
+pub fn room_id(room_id: String) -> Arc<str> {
+    match room_id.is_empty() {
+        true => MAIN_ROOM_ID.into(),
+        false => room_id.into(),
+    }
+}
+
+pub fn game_config<'a>(
+    room_map: &'a IndexMap<Arc<str>, Room>,
+    game_config: &'a config::GameConfig,
+    room_id: &str,
+) -> &'a config::GameConfig {
+    room_map
+        .get(room_id)
+        .map_or(game_config, |room| &room.game_config)
+}
+
+pub fn parse_setting(
+    settings: &mut proto::RoomSettings,
+    key: &str,
+    value: &str,
+) -> Result<(), Arc<str>> {
+    let invalid = || Arc::from(format!("invalid value for {}: {}", key, value));
+    match key {
+        "world_bound" => settings.world_bound = Some(value.parse().map_err(|_| invalid())?),
+        "max_player_count" => {
+            settings.max_player_count = Some(value.parse().map_err(|_| invalid())?)
+        }
+        "spore_density" => settings.spore_density = Some(value.parse().map_err(|_| invalid())?),
+        "eat_ratio" => settings.eat_ratio = Some(value.parse().map_err(|_| invalid())?),
+        "rush_cost_ratio" => settings.rush_cost_ratio = Some(value.parse().map_err(|_| invalid())?),
+        "ranked" => settings.ranked = Some(value.parse().map_err(|_| invalid())?),
+        "team_count" => settings.team_count = Some(value.parse().map_err(|_| invalid())?),
+        "round_mode" => settings.round_mode = Some(value.parse().map_err(|_| invalid())?),
+        _ => return Err(format!("unknown room setting: {}", key).into()),
+    }
+    Ok(())
+}
+
+fn spore_density(game_config: &config::GameConfig) -> f64 {
+    game_config.max_spore_count as f64 * SPORE_DENSITY_AREA / area(game_config.world_bound)
+}
+
+fn area(world_bound: f64) -> f64 {
+    (world_bound * 2.0) * (world_bound * 2.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranked_room_requires_main_room_settings() {
+        let game_config = config::GameConfig::default();
+        let ranked = proto::RoomSettings {
+            ranked: Some(true),
+            max_player_count: Some(4),
+            ..Default::default()
+        };
+        let settings = RoomSettings::new(&ranked, &game_config).unwrap();
+        assert!(settings.is_ranked(&game_config));
+
+        for custom in [
+            proto::RoomSettings {
+                world_bound: Some(MIN_WORLD_BOUND),
+                ..ranked
+            },
+            proto::RoomSettings {
+                spore_density: Some(MAX_SPORE_DENSITY),
+                ..ranked
+            },
+            proto::RoomSettings {
+                eat_ratio: Some(MAX_EAT_RATIO),
+                ..ranked
+            },
+        ] {
+            assert!(RoomSettings::new(&custom, &game_config).is_err());
+            let unranked = proto::RoomSettings {
+                ranked: Some(false),
+                ..custom
+            };
+            assert!(RoomSettings::new(&unranked, &game_config).is_ok());
+        }
+    }
+
+    #[test]
+    fn ranked_room_is_unranked_after_main_config_changes() {
+        let game_config = config::GameConfig::default();
+        let ranked = proto::RoomSettings {
+            ranked: Some(true),
+            ..Default::default()
+        };
+        let settings = RoomSettings::new(&ranked, &game_config).unwrap();
+        let game_config = config::GameConfig {
+            eat_ratio: game_config.eat_ratio + 0.1,
+            ..game_config
+        };
+        assert!(!settings.is_ranked(&game_config));
+    }
+}
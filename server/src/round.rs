@@ -0,0 +1,234 @@
+use crate::*;
+use std::time::Duration;
+
+const RESULT_SIZE: usize = 10;
+const SYNC_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone)]
+pub struct Round {
+    pub room_id: Arc<str>,
+    pub round_number: u32,
+    pub state: proto::RoundState,
+    pub state_step: u64,
+    pub started_at: i64,
+}
+
+impl Round {
+    pub fn new(room_id: Arc<str>) -> Self {
+        Self {
+            room_id,
+            round_number: 0,
+            state: proto::RoundState::Waiting,
+            state_step: 0,
+            started_at: 0,
+        }
+    }
+
+    pub fn step(
+        &mut self,
+        player_count: usize,
+        game_config: &config::GameConfig,
+    ) -> Option<proto::RoundState> {
+        self.state_step += 1;
+        let is_elapsed = self.state_step >= duration_step(self.state, game_config);
+        let is_ready = player_count >= game_config.round_min_player_count;
+        let next_state = match self.state {
+            proto::RoundState::Waiting if is_ready && is_elapsed => proto::RoundState::Countdown,
+            proto::RoundState::Countdown if !is_ready => proto::RoundState::Waiting,
+            proto::RoundState::Countdown if is_elapsed => proto::RoundState::Running,
+            proto::RoundState::Running if is_elapsed => proto::RoundState::Results,
+            proto::RoundState::Results if is_elapsed => proto::RoundState::Reset,
+            proto::RoundState::Reset if is_elapsed => proto::RoundState::Waiting,
+            _ => return None,
+        };
+        if next_state == proto::RoundState::Running {
+            self.round_number += 1;
+        }
+        self.state = next_state;
+        self.state_step = 0;
+        Some(next_state)
+    }
+
+    pub fn remaining_ms(&self, game_config: &config::GameConfig) -> u64 {
+        duration_step(self.state, game_config).saturating_sub(self.state_step)
+            * game_config.tick_duration_ms
+    }
+
+    pub fn to_proto(&self, game_config: &config::GameConfig) -> proto::Round {
+        proto::Round {
+            state: self.state.into(),
+            remaining_ms: self.remaining_ms(game_config),
+            round_number: self.round_number,
+        }
+    }
+
+    pub fn describe(&self, player_count: usize, game_config: &config::GameConfig) -> Arc<str> {
+        let remaining =
+            util::format_duration(Duration::from_millis(self.remaining_ms(game_config)));
+        match self.state {
+            proto::RoundState::Waiting => format!(
+                "round {}: waiting for players ({}/{})",
+                self.round_number + 1,
+                player_count,
+                game_config.round_min_player_count
+            ),
+            proto::RoundState::Countdown => {
+                format!("round {} starts in {}", self.round_number + 1, remaining)
+            }
+            proto::RoundState::Running => {
+                format!("round {} ends in {}", self.round_number, remaining)
+            }
+            proto::RoundState::Results | proto::RoundState::Reset => format!(
+                "round {} is over, next round in {}",
+                self.round_number, remaining
+            ),
+        }
+        .into()
+    }
+}
+
+pub fn is_sync_step(step_count: u64, game_config: &config::GameConfig) -> bool {
+    game_config.is_interval_step(step_count, SYNC_INTERVAL)
+}
+
+pub fn duration_step(state: proto::RoundState, game_config: &config::GameConfig) -> u64 {
+    let duration_ms = match state {
+        proto::RoundState::Waiting => game_config.round_waiting_ms,
+        proto::RoundState::Countdown => game_config.round_countdown_ms,
+        proto::RoundState::Running => game_config.round_running_ms,
+        proto::RoundState::Results => game_config.round_results_ms,
+        proto::RoundState::Reset => game_config.round_reset_ms,
+    };
+    duration_ms / game_config.tick_duration_ms
+}
+
+pub fn score_list<'a>(
+    player_list: impl Iterator<Item = &'a player::Player>,
+) -> Vec<proto::RoundScore> {
+    let mut player_list = player_list.collect::<Vec<_>>();
+    player_list.sort_by(|a, b| b.radius.total_cmp(&a.radius));
+    player_list
+        .into_iter()
+        .take(RESULT_SIZE)
+        .map(|player| proto::RoundScore {
+            connection_id: player.connection_id.to_string(),
+            nickname: player.nickname.to_string(),
+            score: util::radius_to_mass(player.radius) as i64,
+        })
+        .collect()
+}
+
+pub fn describe_result(round_number: u32, score_list: &[proto::RoundScore]) -> Arc<str> {
+    match score_list.first() {
+        Some(winner) => format!(
+            "round {} is over, {} wins with {}",
+            round_number, winner.nickname, winner.score
+        ),
+        None => format!("round {} is over, nobody played", round_number),
+    }
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game_config() -> config::GameConfig {
+        config::GameConfig {
+            round_mode: true,
+            round_min_player_count: 2,
+            round_waiting_ms: 100,
+            round_countdown_ms: 150,
+            round_running_ms: 200,
+            round_results_ms: 100,
+            round_reset_ms: 50,
+            ..Default::default()
+        }
+    }
+
+    fn step_until_change(
+        round: &mut Round,
+        player_count: usize,
+        game_config: &config::GameConfig,
+    ) -> (u64, proto::RoundState) {
+        for step in 1..=1000 {
+            if let Some(state) = round.step(player_count, game_config) {
+                return (step, state);
+            }
+        }
+        panic!("round stuck in {:?}", round.state);
+    }
+
+    #[test]
+    fn step_runs_full_lifecycle() {
+        let game_config = game_config();
+        let mut round = Round::new(room::MAIN_ROOM_ID.into());
+
+        assert_eq!(
+            step_until_change(&mut round, 2, &game_config),
+            (2, proto::RoundState::Countdown)
+        );
+        assert_eq!(
+            step_until_change(&mut round, 2, &game_config),
+            (3, proto::RoundState::Running)
+        );
+        assert_eq!(round.round_number, 1);
+        assert_eq!(
+            step_until_change(&mut round, 0, &game_config),
+            (4, proto::RoundState::Results)
+        );
+        assert_eq!(
+            step_until_change(&mut round, 0, &game_config),
+            (2, proto::RoundState::Reset)
+        );
+        assert_eq!(
+            step_until_change(&mut round, 0, &game_config),
+            (1, proto::RoundState::Waiting)
+        );
+        assert_eq!(round.round_number, 1);
+        assert_eq!(round.state_step, 0);
+    }
+
+    #[test]
+    fn step_waits_for_min_player_count() {
+        let game_config = game_config();
+        let mut round = Round::new(room::MAIN_ROOM_ID.into());
+
+        for _ in 0..10 {
+            assert_eq!(round.step(1, &game_config), None);
+        }
+        assert_eq!(round.state, proto::RoundState::Waiting);
+        assert_eq!(
+            round.step(2, &game_config),
+            Some(proto::RoundState::Countdown)
+        );
+    }
+
+    #[test]
+    fn step_falls_back_to_waiting_when_countdown_loses_players() {
+        let game_config = game_config();
+        let mut round = Round::new(room::MAIN_ROOM_ID.into());
+        step_until_change(&mut round, 2, &game_config);
+
+        assert_eq!(round.step(2, &game_config), None);
+        assert_eq!(
+            round.step(1, &game_config),
+            Some(proto::RoundState::Waiting)
+        );
+        assert_eq!(round.round_number, 0);
+        assert_eq!(round.state_step, 0);
+    }
+
+    #[test]
+    fn remaining_ms_counts_down() {
+        let game_config = game_config();
+        let mut round = Round::new(room::MAIN_ROOM_ID.into());
+
+        assert_eq!(round.remaining_ms(&game_config), 100);
+        round.step(0, &game_config);
+        assert_eq!(round.remaining_ms(&game_config), 50);
+        round.step(0, &game_config);
+        round.step(0, &game_config);
+        assert_eq!(round.remaining_ms(&game_config), 0);
+    }
+}
@@ -0,0 +1,94 @@
+use crate::*;
+use std::time::Duration;
+use tokio::{
+    sync::{mpsc::UnboundedSender, oneshot},
+    time::{Instant, sleep},
+};
+use tracing::{info, warn};
+
+const DISCONNECT_WAIT_TIMEOUT: Duration = Duration::from_secs(5);
+const DISCONNECT_POLL_DURATION: Duration = Duration::from_millis(100);
+
+pub async fn signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            warn!("ctrl_c signal error: {:?}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut terminate_signal) => {
+                terminate_signal.recv().await;
+            }
+            Err(e) => {
+                warn!("terminate signal error: {:?}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => info!("SIGINT received"),
+        _ = terminate => info!("SIGTERM received"),
+    }
+}
+
+pub async fn run(
+    hub_command_sender: UnboundedSender<command::Command>,
+    db_writer_command_sender: UnboundedSender<command::Command>,
+    countdown: Duration,
+) {
+    info!("shutdown countdown: {:?}", countdown);
+    let _ = hub_command_sender.send(command::Command::Shutdown {
+        reason: "server shutdown".into(),
+        countdown,
+    });
+    sleep(countdown).await;
+
+    info!("shutdown disconnect all clients");
+    let _ = hub_command_sender.send(command::Command::DisconnectAll);
+
+    let deadline = Instant::now() + DISCONNECT_WAIT_TIMEOUT;
+    loop {
+        let (response_sender, response_receiver) = oneshot::channel();
+        if hub_command_sender
+            .send(command::Command::AdminHubState { response_sender })
+            .is_err()
+        {
+            break;
+        }
+        let human_count = match response_receiver.await {
+            Ok(hub_state) => hub_state
+                .client_list
+                .iter()
+                .filter(|client| !client.player.as_ref().is_some_and(|player| player.is_bot))
+                .count(),
+            Err(_) => break,
+        };
+        if human_count == 0 {
+            break;
+        }
+        if Instant::now() > deadline {
+            warn!("shutdown disconnect timeout, {} clients left", human_count);
+            break;
+        }
+        sleep(DISCONNECT_POLL_DURATION).await;
+    }
+
+    info!("shutdown flush db writer");
+    let (response_sender, response_receiver) = oneshot::channel();
+    if db_writer_command_sender
+        .send(command::Command::FlushAll { response_sender })
+        .is_ok()
+    {
+        let _ = response_receiver.await;
+    }
+
+    info!("shutdown complete");
+}
@@ -0,0 +1,43 @@
+use crate::*;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpectateMode {
+    FollowTop,
+    FollowPlayer { connection_id: Arc<str> },
+    FreeRoam,
+}
+
+#[derive(Debug, Clone)]
+pub struct Spectator {
+    pub mode: SpectateMode,
+    pub target_connection_id: Option<Arc<str>>,
+}
+
+impl Spectator {
+    pub fn new(mode: SpectateMode) -> Self {
+        Self {
+            mode,
+            target_connection_id: None,
+        }
+    }
+}
+
+pub fn spectate_mode(spectate: &proto::Spectate) -> SpectateMode {
+    match spectate.mode() {
+        proto::SpectateMode::FollowTop => SpectateMode::FollowTop,
+        proto::SpectateMode::FollowPlayer => SpectateMode::FollowPlayer {
+            connection_id: spectate.target_connection_id.as_str().into(),
+        },
+        proto::SpectateMode::FreeRoam => SpectateMode::FreeRoam,
+    }
+}
+
+pub fn top_player<'a>(
+    player_list: impl Iterator<Item = &'a player::Player>,
+) -> Option<&'a player::Player> {
+    player_list.max_by(|a, b| {
+        a.radius
+            .total_cmp(&b.radius)
+            .then_with(|| b.connection_id.cmp(&a.connection_id))
+    })
+}
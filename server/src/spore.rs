@@ -1,27 +1,32 @@
 use crate::*;
-use nanoid::nanoid;
+use rand::Rng;
 
-const SPORE_BOUND: f64 = 3000.0;
-
-fn random_xy() -> f64 {
-    (rand::random::<f64>() * 2.0 - 1.0) * SPORE_BOUND
+fn random_xy(rng: &mut impl Rng, bound: f64) -> f64 {
+    (rng.random::<f64>() * 2.0 - 1.0) * bound
 }
 
 #[derive(Debug, Clone)]
 pub struct Spore {
     pub id: Arc<str>,
+    pub room_id: Arc<str>,
     pub x: f64,
     pub y: f64,
     pub radius: f64,
 }
 
 impl Spore {
-    pub fn random() -> Self {
-        let radius = (rand::random::<f64>() * 3.0 + 10.0).max(5.0);
+    pub fn random(
+        id: Arc<str>,
+        room_id: Arc<str>,
+        rng: &mut impl Rng,
+        game_config: &config::GameConfig,
+    ) -> Self {
+        let radius = (rng.random::<f64>() * 3.0 + 10.0).max(5.0);
         Self {
-            id: nanoid!().into(),
-            x: random_xy(),
-            y: random_xy(),
+            id,
+            room_id,
+            x: random_xy(rng, game_config.world_bound),
+            y: random_xy(rng, game_config.world_bound),
             radius,
         }
     }
@@ -0,0 +1,206 @@
+use crate::*;
+use std::time::Duration;
+
+pub const MAX_TEAM_COUNT: usize = 4;
+const TEAM_COLOR_LIST: [i64; MAX_TEAM_COUNT] = [0xE74C3CFF, 0x3498DBFF, 0x2ECC71FF, 0xF1C40FFF];
+const TEAM_NAME_LIST: [&str; MAX_TEAM_COUNT] = ["red", "blue", "green", "yellow"];
+const SCORE_INTERVAL: Duration = Duration::from_secs(1);
+
+pub fn name(team: u32) -> &'static str {
+    TEAM_NAME_LIST
+        .get((team as usize).wrapping_sub(1))
+        .copied()
+        .unwrap_or("none")
+}
+
+pub fn assign<'a>(
+    player: &mut player::Player,
+    player_list: impl Iterator<Item = &'a player::Player>,
+    team_count: usize,
+) {
+    if team_count == 0 {
+        join(player, 0);
+        return;
+    }
+    let mut player_count_list = vec![0; team_count];
+    for other in player_list {
+        if other.connection_id != player.connection_id
+            && let Some(player_count) =
+                player_count_list.get_mut((other.team as usize).wrapping_sub(1))
+        {
+            *player_count += 1;
+        }
+    }
+    let team = (0..team_count)
+        .min_by_key(|&index| player_count_list[index])
+        .unwrap_or_default();
+    join(player, team as u32 + 1);
+}
+
+pub fn rebalance<'a>(player_list: impl Iterator<Item = &'a mut player::Player>, team_count: usize) {
+    for (index, player) in player_list.enumerate() {
+        match team_count {
+            0 => join(player, 0),
+            _ => join(player, (index % team_count) as u32 + 1),
+        }
+    }
+}
+
+fn join(player: &mut player::Player, team: u32) {
+    player.team = team;
+    player.color = TEAM_COLOR_LIST
+        .get((team as usize).wrapping_sub(1))
+        .copied()
+        .unwrap_or(player.base_color);
+}
+
+pub fn is_teammate(
+    player: &player::Player,
+    victim: &player::Player,
+    game_config: &config::GameConfig,
+) -> bool {
+    game_config.team_count > 0 && player.team != 0 && player.team == victim.team
+}
+
+pub fn is_score_step(step_count: u64, game_config: &config::GameConfig) -> bool {
+    game_config.is_interval_step(step_count, SCORE_INTERVAL)
+}
+
+pub fn score_list<'a>(
+    player_list: impl Iterator<Item = &'a player::Player>,
+    team_count: usize,
+) -> Vec<proto::TeamScore> {
+    let mut score_list = (0..team_count)
+        .map(|team| proto::TeamScore {
+            team: team as u32 + 1,
+            color: TEAM_COLOR_LIST[team],
+            score: 0,
+            player_count: 0,
+        })
+        .collect::<Vec<_>>();
+    for player in player_list {
+        if let Some(team_score) = score_list.get_mut((player.team as usize).wrapping_sub(1)) {
+            team_score.score += util::radius_to_mass(player.radius) as i64;
+            team_score.player_count += 1;
+        }
+    }
+    score_list
+}
+
+pub fn leader(score_list: &[proto::TeamScore]) -> Option<u32> {
+    score_list
+        .iter()
+        .filter(|team_score| team_score.player_count > 0)
+        .max_by_key(|team_score| team_score.score)
+        .map(|team_score| team_score.team)
+}
+
+pub fn winner(score_list: &[proto::TeamScore], game_config: &config::GameConfig) -> Option<u32> {
+    leader(score_list).filter(|&team| {
+        score_list.iter().any(|team_score| {
+            team_score.team == team && team_score.score >= game_config.team_target_score
+        })
+    })
+}
+
+pub fn describe_result(winner_team: u32, score_list: &[proto::TeamScore]) -> Arc<str> {
+    let score_list = score_list
+        .iter()
+        .map(|team_score| format!("{} {}", name(team_score.team), team_score.score))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("team {} wins! {}", name(winner_team), score_list).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn player_list(player_count: usize) -> Vec<player::Player> {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let game_config = config::GameConfig::default();
+        (0..player_count)
+            .map(|index| {
+                player::Player::random(
+                    &mut rng,
+                    index as i64,
+                    format!("player-{}", index).into(),
+                    format!("player {}", index).into(),
+                    0x123456FF,
+                    &game_config,
+                )
+            })
+            .collect()
+    }
+
+    fn team_size_list(player_list: &[player::Player], team_count: usize) -> Vec<usize> {
+        (1..=team_count as u32)
+            .map(|team| {
+                player_list
+                    .iter()
+                    .filter(|player| player.team == team)
+                    .count()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn assign_joins_smallest_team() {
+        let mut player_list = player_list(7);
+        for index in 0..player_list.len() {
+            let (joined, rest) = player_list.split_at_mut(index);
+            assign(&mut rest[0], joined.iter(), 3);
+        }
+
+        assert_eq!(team_size_list(&player_list, 3), vec![3, 2, 2]);
+        assert_eq!(player_list[0].team, 1);
+        assert_eq!(player_list[1].team, 2);
+        assert_eq!(player_list[2].team, 3);
+        assert_eq!(player_list[0].color, TEAM_COLOR_LIST[0]);
+
+        player_list[1].team = 1;
+        let (player, other_list) = player_list.split_last_mut().unwrap();
+        assign(player, other_list.iter(), 3);
+        assert_eq!(player.team, 2);
+    }
+
+    #[test]
+    fn assign_ignores_self_and_resets_without_teams() {
+        let mut player_list = player_list(3);
+        player_list[0].team = 1;
+        player_list[1].team = 2;
+        player_list[2].team = 1;
+        let mut player = player_list[2].clone();
+        assign(&mut player, player_list.iter(), 2);
+        assert_eq!(player.team, 1);
+
+        let player = &mut player_list[0];
+        assign(player, std::iter::empty(), 0);
+        assert_eq!(player.team, 0);
+        assert_eq!(player.color, player.base_color);
+    }
+
+    #[test]
+    fn rebalance_follows_team_count_change() {
+        let mut player_list = player_list(5);
+        rebalance(player_list.iter_mut(), 2);
+        assert_eq!(team_size_list(&player_list, 2), vec![3, 2]);
+
+        rebalance(player_list.iter_mut(), 4);
+        assert_eq!(team_size_list(&player_list, 4), vec![2, 1, 1, 1]);
+        assert!(
+            player_list
+                .iter()
+                .all(|player| player.color == TEAM_COLOR_LIST[player.team as usize - 1])
+        );
+
+        rebalance(player_list.iter_mut(), 0);
+        assert!(
+            player_list
+                .iter()
+                .all(|player| player.team == 0 && player.color == player.base_color)
+        );
+    }
+}
@@ -1,4 +1,9 @@
-use std::f64::consts::PI;
+use std::{
+    f64::consts::PI,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+pub const MAX_SANCTION_DURATION: Duration = Duration::from_secs(10 * 365 * 24 * 60 * 60);
 
 pub fn radius_to_mass(radius: f64) -> f64 {
     PI * radius * radius
@@ -23,3 +28,42 @@ pub fn check_distance_is_close(
 
     distance_sq < threshold_sq
 }
+
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+pub fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+pub fn expires_at(now: i64, duration: Duration) -> i64 {
+    i64::try_from(duration.as_millis())
+        .ok()
+        .and_then(|millis| now.checked_add(millis))
+        .unwrap_or(i64::MAX)
+}
+
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let part_list = [
+        (secs / 86400, "d"),
+        (secs % 86400 / 3600, "h"),
+        (secs % 3600 / 60, "m"),
+        (secs % 60, "s"),
+    ];
+    let formatted = part_list
+        .iter()
+        .filter(|(value, _)| *value > 0)
+        .map(|(value, unit)| format!("{}{}", value, unit))
+        .collect::<Vec<_>>()
+        .join(" ");
+    if formatted.is_empty() {
+        "0s".to_string()
+    } else {
+        formatted
+    }
+}